@@ -0,0 +1,262 @@
+//! `mdnote-api`: an opt-in HTTP/JSON API over the open vault, for scripts and other
+//! apps on the same machine.
+//!
+//! The server only listens on 127.0.0.1. Every request except the OpenAPI document must
+//! carry the vault's bearer token, requests whose `Host` is not the loopback address are
+//! refused (so a web page cannot reach the API through DNS rebinding), and browsers may
+//! only call it from origins listed in the vault settings.
+
+mod openapi;
+mod routes;
+
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use serde_json::Value;
+use tiny_http::{Header, Request, Response, Server};
+use tracing::{debug, warn};
+use mdnote_core::errors::{AppError, Result};
+use mdnote_core::services::database::DbState;
+use mdnote_core::services::settings::SettingsService;
+use routes::{Match, Op, Query};
+
+const WORKER_COUNT: usize = 4;
+const MAX_BODY_BYTES: u64 = 8 * 1024 * 1024;
+const ALLOWED_METHODS: &str = "GET, POST, PATCH, DELETE, OPTIONS";
+const ALLOWED_HEADERS: &str = "Authorization, Content-Type";
+
+pub struct ApiConfig {
+    /// 0 picks a free port; see `ApiServer::port`.
+    pub port: u16,
+    /// Browser origins allowed to call the API, e.g. `http://localhost:3000`.
+    pub allowed_origins: Vec<String>,
+}
+
+/// A running API server. Dropping it stops the server and waits for in-flight requests.
+pub struct ApiServer {
+    server: Arc<Server>,
+    workers: Vec<JoinHandle<()>>,
+    stopping: Arc<AtomicBool>,
+    port: u16,
+}
+
+impl ApiServer {
+    /// Binds 127.0.0.1 on the configured port and starts serving requests against
+    /// whichever vault `state` has open.
+    pub fn start(state: DbState, config: ApiConfig) -> Result<Self> {
+        let server = Server::http(("127.0.0.1", config.port))
+            .map_err(|e| AppError::io(format_args!("Could not start the API on port {}", config.port), e))?;
+        let port = server
+            .server_addr()
+            .to_ip()
+            .map(|addr| addr.port())
+            .ok_or_else(|| AppError::IoError { message: "API server is not listening on TCP".to_string(), source: None })?;
+
+        let server = Arc::new(server);
+        let stopping = Arc::new(AtomicBool::new(false));
+        let context = Arc::new(Context {
+            state,
+            port,
+            allowed_origins: config.allowed_origins.iter().map(|o| normalize_origin(o)).collect(),
+        });
+
+        let workers = (0..WORKER_COUNT)
+            .map(|_| {
+                let server = server.clone();
+                let stopping = stopping.clone();
+                let context = context.clone();
+                std::thread::spawn(move || loop {
+                    match server.recv() {
+                        Ok(request) => context.handle(request),
+                        Err(_) if stopping.load(Ordering::SeqCst) => break,
+                        Err(e) => warn!("API connection error: {}", e),
+                    }
+                })
+            })
+            .collect();
+
+        debug!("API listening on 127.0.0.1:{}", port);
+        Ok(Self { server, workers, stopping, port })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Stops accepting requests and waits for in-flight ones to finish.
+    pub fn stop(self) {
+        drop(self)
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        for _ in &self.workers {
+            self.server.unblock();
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+struct Context {
+    state: DbState,
+    port: u16,
+    allowed_origins: Vec<String>,
+}
+
+struct Reply {
+    status: u16,
+    body: Option<Value>,
+}
+
+impl Reply {
+    fn error(err: &AppError) -> Self {
+        let status = status_for(err);
+        if status >= 500 {
+            warn!("API request failed: {}", err.report());
+        }
+        Self { status, body: serde_json::to_value(err).ok() }
+    }
+
+    /// A request turned away before it reached the vault, in the same shape as `AppError`.
+    fn refused(status: u16, code: &str, message: &str) -> Self {
+        Self { status, body: Some(serde_json::json!({ "code": code, "message": message })) }
+    }
+}
+
+impl Context {
+    fn handle(&self, mut request: Request) {
+        let origin = header(&request, "Origin").map(str::to_string);
+        let reply = self.reply(&mut request, origin.as_deref());
+        let preflight = request.method().as_str() == "OPTIONS";
+
+        let body = reply.body.map(|v| serde_json::to_vec(&v).unwrap_or_default()).unwrap_or_default();
+        let mut response = Response::from_data(body).with_status_code(reply.status);
+        if reply.status != 204 {
+            response.add_header(make_header("Content-Type", "application/json"));
+        }
+        if let Some(origin) = origin.filter(|o| self.origin_allowed(o)) {
+            response.add_header(make_header("Access-Control-Allow-Origin", &origin));
+            response.add_header(make_header("Vary", "Origin"));
+            if preflight {
+                response.add_header(make_header("Access-Control-Allow-Methods", ALLOWED_METHODS));
+                response.add_header(make_header("Access-Control-Allow-Headers", ALLOWED_HEADERS));
+                response.add_header(make_header("Access-Control-Max-Age", "600"));
+            }
+        }
+
+        if let Err(e) = request.respond(response) {
+            debug!("API client went away: {}", e);
+        }
+    }
+
+    fn reply(&self, request: &mut Request, origin: Option<&str>) -> Reply {
+        if !self.host_allowed(header(request, "Host")) {
+            return Reply::refused(403, "FORBIDDEN", "Host not allowed");
+        }
+        if origin.is_some_and(|o| !self.origin_allowed(o)) {
+            return Reply::refused(403, "FORBIDDEN", "Origin not allowed");
+        }
+        if request.method().as_str() == "OPTIONS" {
+            return Reply { status: 204, body: None };
+        }
+
+        let url = request.url().to_string();
+        let (path, raw_query) = url.split_once('?').unwrap_or((&url, ""));
+        let (route, params) = match routes::find(request.method().as_str(), path) {
+            Match::Found(route, params) => (route, params),
+            Match::MethodNotAllowed => return Reply::refused(405, "METHOD_NOT_ALLOWED", "Method not allowed"),
+            Match::NotFound => return Reply::refused(404, "NOT_FOUND", "No such endpoint"),
+        };
+
+        if route.op == Op::OpenApi {
+            return Reply { status: 200, body: Some(openapi::document(self.port)) };
+        }
+        if let Err(reply) = self.authorize(header(request, "Authorization")) {
+            return reply;
+        }
+
+        let mut body = Vec::new();
+        if let Err(e) = request.as_reader().take(MAX_BODY_BYTES + 1).read_to_end(&mut body) {
+            return Reply::error(&AppError::io("Could not read the request body", e));
+        }
+        if body.len() as u64 > MAX_BODY_BYTES {
+            return Reply::refused(413, "PAYLOAD_TOO_LARGE", "Request body too large");
+        }
+
+        match routes::dispatch(&self.state, route.op, &params, &Query::parse(raw_query), &body) {
+            Ok(body) => Reply { status: route.status, body },
+            Err(e) => Reply::error(&e),
+        }
+    }
+
+    /// Only loopback host names are served, whatever address the name resolved to.
+    fn host_allowed(&self, host: Option<&str>) -> bool {
+        let Some(host) = host else { return false };
+        let port = self.port.to_string();
+        ["127.0.0.1", "localhost"]
+            .iter()
+            .any(|name| host.eq_ignore_ascii_case(&format!("{}:{}", name, port)))
+    }
+
+    fn origin_allowed(&self, origin: &str) -> bool {
+        let origin = normalize_origin(origin);
+        self.allowed_origins.contains(&origin)
+    }
+
+    /// Checks the bearer token against the open vault's settings, so regenerating it
+    /// takes effect immediately and each vault has its own. A vault with the API turned
+    /// off refuses every request even while the server is still up.
+    fn authorize(&self, authorization: Option<&str>) -> std::result::Result<(), Reply> {
+        let settings = self
+            .state
+            .pool()
+            .and_then(|pool| pool.with_reader(|db| SettingsService::new(db).get_settings()))
+            .map_err(|e| Reply::error(&e))?;
+        if !settings.api_enabled {
+            return Err(Reply::refused(403, "FORBIDDEN", "The API is disabled for this vault"));
+        }
+
+        let given = authorization.and_then(|v| v.strip_prefix("Bearer ")).map(str::trim);
+        match (given, settings.api_token) {
+            (Some(given), Some(token)) if constant_time_eq(token.as_bytes(), given.as_bytes()) => Ok(()),
+            _ => Err(Reply::refused(401, "UNAUTHORIZED", "Missing or invalid bearer token")),
+        }
+    }
+}
+
+fn status_for(err: &AppError) -> u16 {
+    match err {
+        AppError::NotFound(_) => 404,
+        AppError::Validation { .. } => 400,
+        AppError::AlreadyExists(_) | AppError::Conflict { .. } => 409,
+        AppError::Locked(_) => 423,
+        AppError::Unsupported(_) => 501,
+        AppError::LockError { .. } => 503,
+        AppError::SqliteError { .. } | AppError::IoError { .. } | AppError::Corrupt { .. } => 500,
+    }
+}
+
+fn header<'r>(request: &'r Request, name: &'static str) -> Option<&'r str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+fn make_header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("header names and values are ASCII")
+}
+
+fn normalize_origin(origin: &str) -> String {
+    origin.trim().trim_end_matches('/').to_ascii_lowercase()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,104 @@
+use serde_json::{json, Map, Value};
+use crate::routes::{body_schemas, Op, Route, ROUTES};
+
+/// Builds the OpenAPI 3 document from the route table.
+pub fn document(port: u16) -> Value {
+    let mut paths = Map::new();
+    for route in ROUTES {
+        let entry = paths
+            .entry(route.path.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        entry[route.method.to_ascii_lowercase()] = operation(route);
+    }
+
+    let mut schemas = body_schemas();
+    schemas["Error"] = error_schema();
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "MDNote local API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Read and change the open vault from scripts and other apps on this machine.",
+        },
+        "servers": [{ "url": format!("http://127.0.0.1:{}", port) }],
+        "security": [{ "bearer": [] }],
+        "paths": paths,
+        "components": {
+            "securitySchemes": { "bearer": { "type": "http", "scheme": "bearer" } },
+            "schemas": schemas,
+        },
+    })
+}
+
+fn operation(route: &Route) -> Value {
+    let path_params = route
+        .path
+        .split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } }));
+    let query_params = route
+        .query
+        .iter()
+        .map(|q| json!({ "name": q.name, "in": "query", "required": q.required, "schema": { "type": q.kind } }));
+    let parameters: Vec<Value> = path_params.chain(query_params).collect();
+
+    let success = if route.status == 204 {
+        json!({ "description": "Done" })
+    } else {
+        json!({ "description": "OK", "content": { "application/json": {} } })
+    };
+    let mut op = json!({
+        "operationId": operation_id(route),
+        "summary": route.summary,
+        "parameters": parameters,
+        "responses": {
+            route.status.to_string(): success,
+            "default": {
+                "description": "Error",
+                "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Error" } } },
+            },
+        },
+    });
+    if route.op == Op::OpenApi {
+        op["security"] = json!([]);
+    }
+    if let Some(schema) = route.body {
+        op["requestBody"] = json!({
+            "required": true,
+            "content": { "application/json": { "schema": { "$ref": format!("#/components/schemas/{}", schema) } } },
+        });
+    }
+    op
+}
+
+/// Every failure has a stable `code` to switch on and a `message` for people.
+fn error_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["code", "message"],
+        "properties": {
+            "code": {
+                "type": "string",
+                "enum": [
+                    "NOT_FOUND", "VALIDATION", "ALREADY_EXISTS", "CONFLICT", "LOCKED", "CORRUPT",
+                    "UNSUPPORTED", "BUSY", "DATABASE", "IO",
+                    "UNAUTHORIZED", "FORBIDDEN", "METHOD_NOT_ALLOWED", "PAYLOAD_TOO_LARGE",
+                ],
+            },
+            "message": { "type": "string" },
+            "field": { "type": "string", "description": "The invalid parameter, for some VALIDATION errors" },
+            "current": { "type": "object", "description": "The stored note, for CONFLICT errors on note updates" },
+        },
+    })
+}
+
+/// `ListNotes` -> `listNotes`
+fn operation_id(route: &Route) -> String {
+    let name = format!("{:?}", route.op);
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => name,
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Deserializer};
+use serde_json::{json, Value};
+use mdnote_core::errors::{AppError, Result};
+use mdnote_core::services::backlink::BacklinkService;
+use mdnote_core::services::database::DbState;
+use mdnote_core::services::folder::FolderService;
+use mdnote_core::services::note::NoteService;
+use mdnote_core::services::search::SearchService;
+use mdnote_core::services::tag::TagService;
+
+const DEFAULT_LIST_LIMIT: i32 = 50;
+const DEFAULT_SEARCH_LIMIT: i32 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    OpenApi,
+    ListNotes,
+    CreateNote,
+    GetNote,
+    UpdateNote,
+    DeleteNote,
+    GetNoteTags,
+    AddNoteTag,
+    RemoveNoteTag,
+    GetBacklinks,
+    GetOutgoingLinks,
+    ListFolders,
+    CreateFolder,
+    UpdateFolder,
+    DeleteFolder,
+    ListTags,
+    GetTagTree,
+    CreateTag,
+    DeleteTag,
+    Search,
+}
+
+/// One endpoint. The table below drives both dispatch and the OpenAPI document, so the
+/// two cannot drift apart.
+pub struct Route {
+    pub method: &'static str,
+    /// Path template; `{name}` segments are captured as path parameters.
+    pub path: &'static str,
+    pub op: Op,
+    pub summary: &'static str,
+    pub query: &'static [QueryParam],
+    /// Name of the request body schema in `components/schemas`, if the endpoint takes one.
+    pub body: Option<&'static str>,
+    /// Success status; 204 responses have no body.
+    pub status: u16,
+}
+
+pub struct QueryParam {
+    pub name: &'static str,
+    pub kind: &'static str,
+    pub required: bool,
+}
+
+const fn query(name: &'static str, kind: &'static str, required: bool) -> QueryParam {
+    QueryParam { name, kind, required }
+}
+
+pub const ROUTES: &[Route] = &[
+    Route { method: "GET", path: "/api/openapi.json", op: Op::OpenApi, summary: "This document", query: &[], body: None, status: 200 },
+    Route {
+        method: "GET", path: "/api/notes", op: Op::ListNotes, summary: "List notes, most recently updated first",
+        query: &[
+            query("folder_id", "string", false),
+            query("tag_id", "string", false),
+            query("include_descendants", "boolean", false),
+            query("limit", "integer", false),
+            query("offset", "integer", false),
+        ],
+        body: None, status: 200,
+    },
+    Route { method: "POST", path: "/api/notes", op: Op::CreateNote, summary: "Create a note", query: &[], body: Some("CreateNote"), status: 201 },
+    Route { method: "GET", path: "/api/notes/{id}", op: Op::GetNote, summary: "Get a note. Encrypted notes come back locked, with empty content", query: &[], body: None, status: 200 },
+    Route { method: "PATCH", path: "/api/notes/{id}", op: Op::UpdateNote, summary: "Change a note's title, content or folder", query: &[], body: Some("UpdateNote"), status: 200 },
+    Route { method: "DELETE", path: "/api/notes/{id}", op: Op::DeleteNote, summary: "Delete a note", query: &[], body: None, status: 204 },
+    Route { method: "GET", path: "/api/notes/{id}/tags", op: Op::GetNoteTags, summary: "Tags on a note", query: &[], body: None, status: 200 },
+    Route { method: "POST", path: "/api/notes/{id}/tags", op: Op::AddNoteTag, summary: "Tag a note", query: &[], body: Some("AddNoteTag"), status: 204 },
+    Route { method: "DELETE", path: "/api/notes/{id}/tags/{tag_id}", op: Op::RemoveNoteTag, summary: "Remove a tag from a note", query: &[], body: None, status: 204 },
+    Route { method: "GET", path: "/api/notes/{id}/backlinks", op: Op::GetBacklinks, summary: "Notes linking to a note", query: &[], body: None, status: 200 },
+    Route { method: "GET", path: "/api/notes/{id}/links", op: Op::GetOutgoingLinks, summary: "Notes a note links to", query: &[], body: None, status: 200 },
+    Route { method: "GET", path: "/api/folders", op: Op::ListFolders, summary: "List folders", query: &[], body: None, status: 200 },
+    Route { method: "POST", path: "/api/folders", op: Op::CreateFolder, summary: "Create a folder", query: &[], body: Some("CreateFolder"), status: 201 },
+    Route { method: "PATCH", path: "/api/folders/{id}", op: Op::UpdateFolder, summary: "Rename or move a folder", query: &[], body: Some("UpdateFolder"), status: 200 },
+    Route {
+        method: "DELETE", path: "/api/folders/{id}", op: Op::DeleteFolder, summary: "Delete a folder. Its notes move to the root unless delete_notes is set",
+        query: &[query("delete_notes", "boolean", false)],
+        body: None, status: 204,
+    },
+    Route { method: "GET", path: "/api/tags", op: Op::ListTags, summary: "List tags with note counts", query: &[], body: None, status: 200 },
+    Route { method: "GET", path: "/api/tags/tree", op: Op::GetTagTree, summary: "Tags as a tree of nested paths", query: &[], body: None, status: 200 },
+    Route { method: "POST", path: "/api/tags", op: Op::CreateTag, summary: "Create a tag", query: &[], body: Some("CreateTag"), status: 201 },
+    Route { method: "DELETE", path: "/api/tags/{id}", op: Op::DeleteTag, summary: "Delete a tag", query: &[], body: None, status: 204 },
+    Route {
+        method: "GET", path: "/api/search", op: Op::Search, summary: "Full-text search",
+        query: &[query("q", "string", true), query("limit", "integer", false)],
+        body: None, status: 200,
+    },
+];
+
+pub enum Match<'r> {
+    Found(&'r Route, HashMap<&'static str, String>),
+    /// The path exists but not for this method.
+    MethodNotAllowed,
+    NotFound,
+}
+
+pub fn find(method: &str, path: &str) -> Match<'static> {
+    let mut path_matched = false;
+    for route in ROUTES {
+        if let Some(params) = match_path(route.path, path) {
+            if route.method == method {
+                return Match::Found(route, params);
+            }
+            path_matched = true;
+        }
+    }
+    if path_matched { Match::MethodNotAllowed } else { Match::NotFound }
+}
+
+fn match_path(template: &'static str, path: &str) -> Option<HashMap<&'static str, String>> {
+    let mut wanted = template.trim_matches('/').split('/');
+    let mut actual = path.trim_matches('/').split('/');
+    let mut params = HashMap::new();
+    loop {
+        match (wanted.next(), actual.next()) {
+            (None, None) => return Some(params),
+            (Some(w), Some(a)) => {
+                if let Some(name) = w.strip_prefix('{').and_then(|w| w.strip_suffix('}')) {
+                    if a.is_empty() {
+                        return None;
+                    }
+                    params.insert(name, percent_encoding::percent_decode_str(a).decode_utf8_lossy().into_owned());
+                } else if w != a {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Parsed `?key=value` pairs.
+pub struct Query(HashMap<String, String>);
+
+impl Query {
+    pub fn parse(raw: &str) -> Self {
+        let decode = |s: &str| percent_encoding::percent_decode_str(&s.replace('+', " ")).decode_utf8_lossy().into_owned();
+        let pairs = raw
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((k, v)) => (decode(k), decode(v)),
+                None => (decode(pair), String::new()),
+            })
+            .collect();
+        Self(pairs)
+    }
+
+    fn string(&self, name: &str) -> Option<String> {
+        self.0.get(name).filter(|v| !v.is_empty()).cloned()
+    }
+
+    fn int(&self, name: &str, default: i32) -> Result<i32> {
+        match self.0.get(name) {
+            Some(v) => v.parse().map_err(|_| AppError::invalid(name, format!("{} must be an integer", name))),
+            None => Ok(default),
+        }
+    }
+
+    fn flag(&self, name: &str) -> Result<bool> {
+        match self.0.get(name).map(String::as_str) {
+            None | Some("false") | Some("0") => Ok(false),
+            Some("") | Some("true") | Some("1") => Ok(true),
+            Some(_) => Err(AppError::invalid(name, format!("{} must be true or false", name))),
+        }
+    }
+}
+
+/// Tells an absent field (`None`) from an explicit `null` (`Some(None)`).
+fn present<'de, D, T>(deserializer: D) -> std::result::Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize)]
+struct CreateNote {
+    title: String,
+    #[serde(default)]
+    content: String,
+    folder_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UpdateNote {
+    title: Option<String>,
+    content: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    folder_id: Option<Option<String>>,
+    expected_updated_at: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct AddNoteTag {
+    tag_id: String,
+}
+
+#[derive(Deserialize)]
+struct CreateFolder {
+    name: String,
+    parent_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UpdateFolder {
+    name: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    parent_id: Option<Option<String>>,
+}
+
+#[derive(Deserialize)]
+struct CreateTag {
+    name: String,
+    color: Option<String>,
+}
+
+/// JSON schemas for the request bodies above, referenced by `Route::body`.
+pub fn body_schemas() -> Value {
+    let nullable_string = json!({ "type": "string", "nullable": true });
+    json!({
+        "CreateNote": {
+            "type": "object",
+            "required": ["title"],
+            "properties": { "title": { "type": "string" }, "content": { "type": "string" }, "folder_id": nullable_string },
+        },
+        "UpdateNote": {
+            "type": "object",
+            "description": "Omitted fields are left unchanged; a null folder_id moves the note to the root. With expected_updated_at, the update fails with 409 if the note has changed since that version.",
+            "properties": {
+                "title": { "type": "string" },
+                "content": { "type": "string" },
+                "folder_id": nullable_string,
+                "expected_updated_at": { "type": "integer", "format": "int64" },
+            },
+        },
+        "AddNoteTag": {
+            "type": "object",
+            "required": ["tag_id"],
+            "properties": { "tag_id": { "type": "string" } },
+        },
+        "CreateFolder": {
+            "type": "object",
+            "required": ["name"],
+            "properties": { "name": { "type": "string" }, "parent_id": nullable_string },
+        },
+        "UpdateFolder": {
+            "type": "object",
+            "description": "Omitted fields are left unchanged; a null parent_id moves the folder to the root.",
+            "properties": { "name": { "type": "string" }, "parent_id": nullable_string },
+        },
+        "CreateTag": {
+            "type": "object",
+            "required": ["name"],
+            "properties": { "name": { "type": "string" }, "color": nullable_string },
+        },
+    })
+}
+
+fn parse_body<T: for<'de> Deserialize<'de>>(body: &[u8]) -> Result<T> {
+    serde_json::from_slice(body).map_err(|e| AppError::validation(format!("Invalid request body: {}", e)))
+}
+
+fn to_json<T: serde::Serialize>(value: T) -> Result<Option<Value>> {
+    serde_json::to_value(value)
+        .map(Some)
+        .map_err(|e| AppError::validation(e.to_string()))
+}
+
+/// Runs `op` against the open vault. `None` means an empty (204) response.
+pub fn dispatch(
+    state: &DbState,
+    op: Op,
+    params: &HashMap<&'static str, String>,
+    query: &Query,
+    body: &[u8],
+) -> Result<Option<Value>> {
+    let id = || params.get("id").cloned().unwrap_or_default();
+    let pool = state.pool()?;
+
+    match op {
+        Op::OpenApi => unreachable!("served without touching the vault"),
+        Op::ListNotes => {
+            let folder_id = query.string("folder_id");
+            let tag_id = query.string("tag_id");
+            let include_descendants = query.flag("include_descendants")?;
+            let limit = query.int("limit", DEFAULT_LIST_LIMIT)?;
+            let offset = query.int("offset", 0)?;
+            let (notes, total) = pool.with_reader(|db| {
+                NoteService::new(db).list_notes(folder_id, tag_id, include_descendants, limit, offset)
+            })?;
+            to_json(json!({ "notes": notes, "total": total }))
+        }
+        Op::CreateNote => {
+            let body: CreateNote = parse_body(body)?;
+            to_json(pool.with_writer(|db| db.transaction(|| {
+                let note = NoteService::new(db).create_note(body.folder_id, body.title, body.content)?;
+                BacklinkService::new(db).sync_links(note.id.clone(), &note.content)?;
+                Ok(note)
+            }))?)
+        }
+        Op::GetNote => to_json(pool.with_reader(|db| NoteService::new(db).get_note(id()))?),
+        Op::UpdateNote => {
+            let body: UpdateNote = parse_body(body)?;
+            to_json(pool.with_writer(|db| db.transaction(|| {
+                let content_changed = body.content.is_some();
+                let note = NoteService::new(db).update_note(id(), body.title, body.content, body.folder_id, body.expected_updated_at)?;
+                if content_changed && !note.encrypted {
+                    BacklinkService::new(db).sync_links(note.id.clone(), &note.content)?;
+                }
+                Ok(note)
+            }))?)
+        }
+        Op::DeleteNote => {
+            pool.with_writer(|db| NoteService::new(db).delete_note(id()))?;
+            Ok(None)
+        }
+        Op::GetNoteTags => to_json(pool.with_reader(|db| TagService::new(db).get_note_tags(id()))?),
+        Op::AddNoteTag => {
+            let body: AddNoteTag = parse_body(body)?;
+            pool.with_writer(|db| TagService::new(db).add_tag_to_note(id(), body.tag_id))?;
+            Ok(None)
+        }
+        Op::RemoveNoteTag => {
+            let tag_id = params.get("tag_id").cloned().unwrap_or_default();
+            pool.with_writer(|db| TagService::new(db).remove_tag_from_note(id(), tag_id))?;
+            Ok(None)
+        }
+        Op::GetBacklinks => to_json(pool.with_reader(|db| BacklinkService::new(db).get_backlinks(id()))?),
+        Op::GetOutgoingLinks => to_json(pool.with_reader(|db| BacklinkService::new(db).get_outgoing_links(id()))?),
+        Op::ListFolders => to_json(pool.with_reader(|db| FolderService::new(db).list_folders())?),
+        Op::CreateFolder => {
+            let body: CreateFolder = parse_body(body)?;
+            to_json(pool.with_writer(|db| FolderService::new(db).create_folder(body.name, body.parent_id))?)
+        }
+        Op::UpdateFolder => {
+            let body: UpdateFolder = parse_body(body)?;
+            to_json(pool.with_writer(|db| FolderService::new(db).update_folder(id(), body.name, body.parent_id))?)
+        }
+        Op::DeleteFolder => {
+            let delete_notes = query.flag("delete_notes")?;
+            pool.with_writer(|db| FolderService::new(db).delete_folder(id(), delete_notes))?;
+            Ok(None)
+        }
+        Op::ListTags => to_json(pool.with_reader(|db| TagService::new(db).list_tags())?),
+        Op::GetTagTree => to_json(pool.with_reader(|db| TagService::new(db).get_tag_tree())?),
+        Op::CreateTag => {
+            let body: CreateTag = parse_body(body)?;
+            to_json(pool.with_writer(|db| TagService::new(db).create_tag(body.name, body.color))?)
+        }
+        Op::DeleteTag => {
+            pool.with_writer(|db| TagService::new(db).delete_tag(id()))?;
+            Ok(None)
+        }
+        Op::Search => {
+            let q = query
+                .string("q")
+                .ok_or_else(|| AppError::invalid("q", "q is required"))?;
+            let limit = query.int("limit", DEFAULT_SEARCH_LIMIT)?;
+            to_json(pool.with_reader(|db| SearchService::new(db).search_notes(q, limit))?)
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use serde_json::{json, Value};
+use mdnote_api::{ApiConfig, ApiServer};
+use mdnote_core::models::settings::Settings;
+use mdnote_core::services::settings::SettingsService;
+use mdnote_core::{DbPool, DbState};
+
+const ALLOWED_ORIGIN: &str = "http://localhost:5173";
+
+/// A server over a fresh vault in a temp dir, torn down on drop.
+struct Fixture {
+    state: DbState,
+    server: Option<ApiServer>,
+    token: String,
+    dir: PathBuf,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("mdnote-api-test-{}", ulid::Ulid::new()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let state = DbState::new();
+        state.open("test".into(), DbPool::new(dir.clone(), 2).unwrap()).unwrap();
+        let token = state
+            .pool()
+            .unwrap()
+            .with_writer(|db| {
+                let settings = SettingsService::new(db);
+                settings.update_settings(Settings { api_enabled: true, ..Settings::default() })?;
+                settings.ensure_api_token()
+            })
+            .unwrap();
+        let config = ApiConfig { port: 0, allowed_origins: vec![ALLOWED_ORIGIN.to_string()] };
+        let server = ApiServer::start(state.clone(), config).unwrap();
+
+        Self { state, server: Some(server), token, dir }
+    }
+
+    fn port(&self) -> u16 {
+        self.server.as_ref().unwrap().port()
+    }
+
+    fn call(&self, method: &str, path: &str, body: Option<Value>) -> (u16, Value) {
+        let auth = format!("Authorization: Bearer {}\r\n", self.token);
+        self.raw(method, path, &auth, body)
+    }
+
+    /// Sends one request with `extra` headers and returns the status and JSON body
+    /// (`Null` when empty).
+    fn raw(&self, method: &str, path: &str, extra: &str, body: Option<Value>) -> (u16, Value) {
+        let (status, _, body) = self.exchange(method, path, &format!("Host: 127.0.0.1:{}\r\n{}", self.port(), extra), body);
+        (status, body)
+    }
+
+    fn exchange(&self, method: &str, path: &str, headers: &str, body: Option<Value>) -> (u16, String, Value) {
+        let body = body.map(|b| b.to_string()).unwrap_or_default();
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port())).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method, path, headers, body.len(), body
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, payload) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        let json = if payload.is_empty() { Value::Null } else { serde_json::from_str(payload).unwrap() };
+        (status, head.to_string(), json)
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        if let Some(server) = self.server.take() {
+            server.stop();
+        }
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[test]
+fn notes_round_trip_over_http() {
+    let api = Fixture::new();
+
+    let (status, folder) = api.call("POST", "/api/folders", Some(json!({ "name": "Inbox" })));
+    assert_eq!(status, 201);
+
+    let (status, note) = api.call(
+        "POST",
+        "/api/notes",
+        Some(json!({ "title": "Groceries", "content": "milk and eggs", "folder_id": folder["id"] })),
+    );
+    assert_eq!(status, 201);
+    let id = note["id"].as_str().unwrap().to_string();
+
+    let (status, fetched) = api.call("GET", &format!("/api/notes/{}", id), None);
+    assert_eq!(status, 200);
+    assert_eq!(fetched["content"], "milk and eggs");
+
+    let (status, moved) = api.call("PATCH", &format!("/api/notes/{}", id), Some(json!({ "folder_id": null })));
+    assert_eq!(status, 200);
+    assert_eq!(moved["folder_id"], Value::Null);
+    assert_eq!(moved["title"], "Groceries");
+
+    // A write based on the version before the move is refused
+    let stale = json!({ "content": "milk", "expected_updated_at": note["updated_at"] });
+    let (status, err) = api.call("PATCH", &format!("/api/notes/{}", id), Some(stale));
+    assert_eq!(status, 409);
+    assert_eq!(err["code"], "CONFLICT");
+    assert_eq!(err["current"]["folder_id"], Value::Null);
+
+    let (_, linking) = api.call("POST", "/api/notes", Some(json!({ "title": "Plan", "content": format!("see [[{}]]", id) })));
+    let (_, backlinks) = api.call("GET", &format!("/api/notes/{}/backlinks", id), None);
+    assert_eq!(backlinks[0]["source_id"], linking["id"]);
+
+    let (_, listed) = api.call("GET", "/api/notes?limit=10", None);
+    assert_eq!(listed["total"], 2);
+
+    let (status, hits) = api.call("GET", "/api/search?q=eggs", None);
+    assert_eq!(status, 200);
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+
+    let (status, tag) = api.call("POST", "/api/tags", Some(json!({ "name": "shopping" })));
+    assert_eq!(status, 201);
+    let (status, err) = api.call("POST", "/api/tags", Some(json!({ "name": "Shopping" })));
+    assert_eq!((status, &err["code"]), (409, &json!("ALREADY_EXISTS")));
+    let (status, _) = api.call("POST", &format!("/api/notes/{}/tags", id), Some(json!({ "tag_id": tag["id"] })));
+    assert_eq!(status, 204);
+    let (_, tags) = api.call("GET", &format!("/api/notes/{}/tags", id), None);
+    assert_eq!(tags[0]["name"], "shopping");
+
+    assert_eq!(api.call("DELETE", &format!("/api/notes/{}", id), None).0, 204);
+    let (status, err) = api.call("GET", &format!("/api/notes/{}", id), None);
+    assert_eq!(status, 404);
+    assert_eq!(err["code"], "NOT_FOUND");
+}
+
+#[test]
+fn links_next_to_multibyte_text_are_indexed() {
+    let api = Fixture::new();
+
+    let (_, target) = api.call("POST", "/api/notes", Some(json!({ "title": "Café", "content": "" })));
+    let id = target["id"].as_str().unwrap();
+
+    // 31 two-byte characters put the 40-byte context window mid-character
+    let content = format!("{}[[{}]] {}", "é".repeat(31), id, "ü".repeat(40));
+    let (status, source) = api.call("POST", "/api/notes", Some(json!({ "title": "Crème", "content": content })));
+    assert_eq!(status, 201);
+
+    let (status, _) = api.call("PATCH", &format!("/api/notes/{}", source["id"].as_str().unwrap()), Some(json!({ "content": format!("x{}", content) })));
+    assert_eq!(status, 200);
+
+    let (status, backlinks) = api.call("GET", &format!("/api/notes/{}/backlinks", id), None);
+    assert_eq!(status, 200);
+    assert_eq!(backlinks[0]["source_id"], source["id"]);
+    assert!(backlinks[0]["context"].as_str().unwrap().contains(id));
+
+    // The writer survived: later writes still go through
+    let (status, _) = api.call("POST", "/api/notes", Some(json!({ "title": "After" })));
+    assert_eq!(status, 201);
+}
+
+#[test]
+fn requests_need_the_vault_token() {
+    let api = Fixture::new();
+
+    assert_eq!(api.raw("GET", "/api/notes", "", None).0, 401);
+    assert_eq!(api.raw("GET", "/api/notes", "Authorization: Bearer nope\r\n", None).0, 401);
+    // The spec is public so clients can discover the API before they have a token
+    assert_eq!(api.raw("GET", "/api/openapi.json", "", None).0, 200);
+
+    let (status, err) = api.call("POST", "/api/notes", Some(json!({ "content": "no title" })));
+    assert_eq!(status, 400);
+    assert_eq!(err["code"], "VALIDATION");
+    let (status, err) = api.call("GET", "/api/notes?limit=many", None);
+    assert_eq!((status, &err["field"]), (400, &json!("limit")));
+    assert_eq!(api.call("PUT", "/api/notes", None).0, 405);
+    assert_eq!(api.call("GET", "/api/nothing", None).0, 404);
+
+    let regenerated = api
+        .state
+        .pool()
+        .unwrap()
+        .with_writer(|db| SettingsService::new(db).regenerate_api_token())
+        .unwrap();
+    assert_eq!(api.call("GET", "/api/notes", None).0, 401);
+    let fresh = format!("Authorization: Bearer {}\r\n", regenerated);
+    assert_eq!(api.raw("GET", "/api/notes", &fresh, None).0, 200);
+
+    api.state
+        .pool()
+        .unwrap()
+        .with_writer(|db| SettingsService::new(db).update_settings(Settings::default()))
+        .unwrap();
+    assert_eq!(api.raw("GET", "/api/notes", &fresh, None).0, 403);
+}
+
+#[test]
+fn foreign_hosts_and_origins_are_refused() {
+    let api = Fixture::new();
+    let auth = format!("Authorization: Bearer {}\r\n", api.token);
+
+    let rebound = format!("Host: evil.example:{}\r\n{}", api.port(), auth);
+    assert_eq!(api.exchange("GET", "/api/notes", &rebound, None).0, 403);
+
+    let foreign = format!("{}Origin: https://evil.example\r\n", auth);
+    assert_eq!(api.raw("GET", "/api/notes", &foreign, None).0, 403);
+
+    let allowed = format!("{}Origin: {}\r\n", auth, ALLOWED_ORIGIN);
+    let host = format!("Host: localhost:{}\r\n{}", api.port(), allowed);
+    let (status, head, _) = api.exchange("GET", "/api/notes", &host, None);
+    assert_eq!(status, 200);
+    assert!(head.contains(&format!("Access-Control-Allow-Origin: {}", ALLOWED_ORIGIN)));
+
+    let preflight = format!("Origin: {}\r\nAccess-Control-Request-Method: POST\r\n", ALLOWED_ORIGIN);
+    let (status, head, _) = api.exchange("OPTIONS", "/api/notes", &format!("Host: 127.0.0.1:{}\r\n{}", api.port(), preflight), None);
+    assert_eq!(status, 204);
+    assert!(head.contains("Access-Control-Allow-Headers"));
+}
+
+#[test]
+fn openapi_document_lists_every_route() {
+    let api = Fixture::new();
+    let (status, spec) = api.raw("GET", "/api/openapi.json", "", None);
+    assert_eq!(status, 200);
+    assert_eq!(spec["openapi"], "3.0.3");
+    assert_eq!(spec["paths"]["/api/notes/{id}"]["patch"]["operationId"], "updateNote");
+    assert_eq!(
+        spec["paths"]["/api/notes"]["post"]["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+        "#/components/schemas/CreateNote"
+    );
+    assert!(spec["components"]["schemas"]["UpdateFolder"].is_object());
+}
+
+#[test]
+fn a_locked_vault_is_not_served() {
+    let mut api = Fixture::new();
+    assert_eq!(api.call("GET", "/api/notes", None).0, 200);
+
+    // Locking while the server runs answers every request as locked until the shell stops it
+    api.state.lock().unwrap();
+    let (status, err) = api.call("GET", "/api/notes", None);
+    assert_eq!((status, &err["code"]), (423, &json!("LOCKED")));
+    assert_eq!(api.call("POST", "/api/notes", Some(json!({ "title": "Leak" }))).0, 423);
+
+    let port = api.port();
+    api.server.take().unwrap().stop();
+    assert!(TcpStream::connect(("127.0.0.1", port)).is_err());
+}
@@ -0,0 +1,383 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command as Process;
+use mdnote_core::errors::{AppError, Result};
+use mdnote_core::models::note::{Note, NoteSummary};
+use mdnote_core::models::tag::Tag;
+use mdnote_core::services::backlink::{Backlink, BacklinkService};
+use mdnote_core::services::folder::FolderService;
+use mdnote_core::services::merge::merge_text;
+use mdnote_core::services::note::NoteService;
+use mdnote_core::services::search::SearchService;
+use mdnote_core::services::tag::TagService;
+use mdnote_core::DatabaseService;
+use serde::Serialize;
+use crate::mcp::{self, Policy};
+use crate::output::{format_time, Output};
+use crate::{Command, TagAction};
+
+const PAGE_SIZE: i32 = 500;
+
+#[derive(Serialize)]
+struct NoteDetails {
+    #[serde(flatten)]
+    note: Note,
+    tags: Vec<Tag>,
+}
+
+#[derive(Serialize)]
+struct NoteList {
+    notes: Vec<NoteSummary>,
+    total: i64,
+}
+
+#[derive(Serialize)]
+struct Links {
+    outgoing: Vec<NoteSummary>,
+    backlinks: Vec<Backlink>,
+}
+
+#[derive(Serialize)]
+struct ExportedNote {
+    id: String,
+    path: String,
+}
+
+#[derive(Serialize)]
+struct ExportReport {
+    exported: Vec<ExportedNote>,
+    /// Encrypted notes that are locked and were left out
+    skipped: Vec<String>,
+}
+
+pub fn run(db: &DatabaseService, command: Command, out: &Output) -> Result<()> {
+    match command {
+        Command::New { title, folder, content } => {
+            let folder_id = match folder {
+                Some(f) => resolve_folder(db, &f)?,
+                None => None,
+            };
+            let content = match content {
+                Some(c) => c,
+                None => read_piped_stdin()?,
+            };
+            let note = NoteService::new(db).create_note(folder_id, title, content)?;
+            BacklinkService::new(db).sync_links(note.id.clone(), &note.content)?;
+            out.emit(&note, |n| println!("{}", n.id));
+        }
+        Command::Show { id } => {
+            let note = NoteService::new(db).get_note(id)?;
+            let tags = TagService::new(db).get_note_tags(note.id.clone())?;
+            out.emit(&NoteDetails { note, tags }, print_note);
+        }
+        Command::Edit { id } => {
+            let note = edit_note(db, id)?;
+            out.emit(&note, |n| println!("{}", n.id));
+        }
+        Command::Ls { folder, tag, descendants, limit, offset } => {
+            let folder_id = match folder {
+                Some(f) => resolve_folder(db, &f)?,
+                None => None,
+            };
+            let tag_id = match tag {
+                Some(t) => Some(resolve_tag(db, &t)?.id),
+                None => None,
+            };
+            let (notes, total) = NoteService::new(db).list_notes(folder_id, tag_id, descendants, limit, offset)?;
+            out.emit(&NoteList { notes, total }, |list| {
+                for note in &list.notes {
+                    print_summary(note);
+                }
+                if list.total > list.notes.len() as i64 {
+                    eprintln!("({} of {} notes)", list.notes.len(), list.total);
+                }
+            });
+        }
+        Command::Search { query, limit } => {
+            let results = SearchService::new(db).search_notes(query, limit)?;
+            out.emit(&results, |results| {
+                for result in results {
+                    println!("{}  {}", result.id, result.title);
+                    println!("    {}", result.snippet.replace('\n', " "));
+                }
+            });
+        }
+        Command::Tag { action } => run_tag(db, action, out)?,
+        Command::Mv { id, folder } => {
+            let folder_id = resolve_folder(db, &folder)?;
+            let note = NoteService::new(db).update_note(id, None, None, Some(folder_id), None)?;
+            out.emit(&note, |n| println!("{}", n.id));
+        }
+        Command::Rm { ids } => {
+            let notes = NoteService::new(db);
+            for id in &ids {
+                notes.delete_note(id.clone())?;
+            }
+            out.emit(&ids, |ids| ids.iter().for_each(|id| println!("{}", id)));
+        }
+        Command::Links { id } => {
+            let links = BacklinkService::new(db);
+            let report = Links {
+                outgoing: links.get_outgoing_links(id.clone())?,
+                backlinks: links.get_backlinks(id)?,
+            };
+            out.emit(&report, |report| {
+                println!("Links to:");
+                for note in &report.outgoing {
+                    println!("  {}  {}", note.id, note.title);
+                }
+                println!("Linked from:");
+                for link in &report.backlinks {
+                    println!("  {}  {}", link.source_id, link.source_title);
+                }
+            });
+        }
+        Command::Export { ids, all, out: dir } => export(db, ids, all, dir, out)?,
+        Command::Mcp { read_only, folders } => mcp::serve(db, Policy::new(db, read_only, &folders)?)?,
+    }
+    Ok(())
+}
+
+fn run_tag(db: &DatabaseService, action: TagAction, out: &Output) -> Result<()> {
+    let tags = TagService::new(db);
+    match action {
+        TagAction::Ls { note: None } => {
+            let all = tags.list_tags()?;
+            out.emit(&all, |all| {
+                for tag in all {
+                    println!("#{} ({})", tag.name, tag.note_count);
+                }
+            });
+        }
+        TagAction::Ls { note: Some(note_id) } => {
+            let note_tags = tags.get_note_tags(note_id)?;
+            out.emit(&note_tags, |tags| print_tags(tags));
+        }
+        TagAction::Add { note, tags: names } => {
+            // Fail on a missing note before creating any tags
+            NoteService::new(db).get_note(note.clone())?;
+            for name in names {
+                let name = name.trim_start_matches('#');
+                let tag = match tags.find_tag_by_name(name)? {
+                    Some(tag) => tag,
+                    None => tags.create_tag(name.to_string(), None)?,
+                };
+                tags.add_tag_to_note(note.clone(), tag.id)?;
+            }
+            out.emit(&tags.get_note_tags(note)?, |tags| print_tags(tags));
+        }
+        TagAction::Rm { note, tags: names } => {
+            for name in names {
+                let tag = resolve_tag(db, &name)?;
+                tags.remove_tag_from_note(note.clone(), tag.id)?;
+            }
+            out.emit(&tags.get_note_tags(note)?, |tags| print_tags(tags));
+        }
+    }
+    Ok(())
+}
+
+/// Opens the note's content in the user's editor and saves it if it changed.
+fn edit_note(db: &DatabaseService, id: String) -> Result<Note> {
+    let notes = NoteService::new(db);
+    let note = notes.get_note(id)?;
+    if note.locked {
+        return Err(AppError::Locked(format!("Note {} is encrypted; unlock it in the app to edit it", note.id)));
+    }
+
+    // Owner-only and unguessable, since it holds the plaintext; removed when dropped,
+    // whichever way this function returns
+    let mut file = tempfile::Builder::new().prefix("mdnote-").suffix(".md").tempfile()?;
+    file.write_all(note.content.as_bytes())?;
+    file.flush()?;
+    launch_editor(file.path())?;
+    let edited = fs::read_to_string(file.path())?;
+    drop(file);
+
+    if edited == note.content {
+        return Ok(note);
+    }
+    let note = match notes.update_note(note.id.clone(), None, Some(edited.clone()), None, Some(note.updated_at)) {
+        // Changed elsewhere while the editor was open: keep both sets of edits
+        Err(AppError::Conflict { current: Some(current), .. }) => {
+            let merge = merge_text(&note.content, &edited, &current.content);
+            if merge.conflicts > 0 {
+                eprintln!("note {} changed while you edited it; {} conflicting region(s) are marked in the note", current.id, merge.conflicts);
+            }
+            notes.update_note(current.id, None, Some(merge.merged), None, Some(current.updated_at))?
+        }
+        saved => saved?,
+    };
+    BacklinkService::new(db).sync_links(note.id.clone(), &note.content)?;
+    Ok(note)
+}
+
+fn launch_editor(path: &Path) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // $EDITOR may carry arguments, e.g. `code --wait`
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().ok_or_else(|| AppError::validation("$EDITOR is empty"))?;
+
+    let status = Process::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|e| AppError::io(format_args!("Could not start {}", program), e))?;
+    if !status.success() {
+        return Err(AppError::IoError { message: format!("{} exited with {}", program, status), source: None });
+    }
+    Ok(())
+}
+
+fn export(db: &DatabaseService, ids: Vec<String>, all: bool, dir: Option<PathBuf>, out: &Output) -> Result<()> {
+    let ids = if all { all_note_ids(db)? } else { ids };
+    if ids.is_empty() {
+        return Err(AppError::validation("Pass note ids or --all"));
+    }
+
+    let notes = NoteService::new(db);
+    let Some(dir) = dir else {
+        if ids.len() > 1 {
+            return Err(AppError::validation("--out is required to export more than one note"));
+        }
+        let note = notes.get_note(ids[0].clone())?;
+        if note.locked {
+            return Err(AppError::Locked(format!("Note {} is encrypted", note.id)));
+        }
+        out.emit(&note, |n| print!("{}", n.content));
+        return Ok(());
+    };
+
+    fs::create_dir_all(&dir)?;
+    let mut report = ExportReport { exported: Vec::new(), skipped: Vec::new() };
+    let mut used = HashSet::new();
+    for id in ids {
+        let note = notes.get_note(id)?;
+        if note.locked {
+            report.skipped.push(note.id);
+            continue;
+        }
+
+        let stem = file_stem(&note.title).unwrap_or_else(|| note.id.clone());
+        let stem = if used.insert(stem.to_lowercase()) { stem } else { format!("{}-{}", stem, note.id) };
+        let path = dir.join(format!("{}.md", stem));
+        fs::write(&path, &note.content)?;
+        report.exported.push(ExportedNote { id: note.id, path: path.to_string_lossy().into_owned() });
+    }
+
+    out.emit(&report, |report| {
+        for note in &report.exported {
+            println!("{}", note.path);
+        }
+        for id in &report.skipped {
+            eprintln!("skipped encrypted note {}", id);
+        }
+    });
+    Ok(())
+}
+
+/// Every note id in the vault: root notes, then each folder's.
+fn all_note_ids(db: &DatabaseService) -> Result<Vec<String>> {
+    Ok(notes_in_folders(db, all_folders(db)?)?.into_iter().map(|n| n.id).collect())
+}
+
+/// The root (`None`) followed by every folder in the vault.
+pub(crate) fn all_folders(db: &DatabaseService) -> Result<Vec<Option<String>>> {
+    let mut folders: Vec<Option<String>> = vec![None];
+    folders.extend(FolderService::new(db).list_folders()?.into_iter().map(|f| Some(f.id)));
+    Ok(folders)
+}
+
+/// Every note directly in `folders`, a page at a time.
+pub(crate) fn notes_in_folders(db: &DatabaseService, folders: Vec<Option<String>>) -> Result<Vec<NoteSummary>> {
+    let notes = NoteService::new(db);
+    let mut found = Vec::new();
+    for folder in folders {
+        let mut offset = 0;
+        loop {
+            let (page, total) = notes.list_notes(folder.clone(), None, false, PAGE_SIZE, offset)?;
+            found.extend(page);
+            offset += PAGE_SIZE;
+            if i64::from(offset) >= total {
+                break;
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// A file name for `title` with characters that are unsafe on common file systems replaced.
+fn file_stem(title: &str) -> Option<String> {
+    let stem: String = title
+        .chars()
+        .map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) { '-' } else { c })
+        .collect();
+    let stem = stem.trim().trim_matches('.').to_string();
+    (!stem.is_empty()).then_some(stem)
+}
+
+/// A folder id for `folder`, given as an id or a unique name; "/" is the root.
+pub(crate) fn resolve_folder(db: &DatabaseService, folder: &str) -> Result<Option<String>> {
+    if folder == "/" {
+        return Ok(None);
+    }
+    let folders = FolderService::new(db).list_folders()?;
+    if let Some(f) = folders.iter().find(|f| f.id == folder) {
+        return Ok(Some(f.id.clone()));
+    }
+
+    let mut named = folders.into_iter().filter(|f| f.name == folder);
+    match (named.next(), named.next()) {
+        (Some(f), None) => Ok(Some(f.id)),
+        (Some(_), Some(_)) => Err(AppError::validation(format!("Several folders are named {:?}; use its id", folder))),
+        (None, _) => Err(AppError::NotFound(format!("Folder {} not found", folder))),
+    }
+}
+
+/// A tag given as an id or a name, with or without the leading `#`.
+fn resolve_tag(db: &DatabaseService, tag: &str) -> Result<Tag> {
+    let tags = TagService::new(db);
+    if let Some(found) = tags.find_tag_by_name(tag.trim_start_matches('#'))? {
+        return Ok(found);
+    }
+    tags.get_tag(tag.to_string())
+}
+
+fn read_piped_stdin() -> Result<String> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Ok(String::new());
+    }
+    let mut content = String::new();
+    stdin.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+fn print_note(details: &NoteDetails) {
+    let note = &details.note;
+    println!("{}", note.title);
+    println!("id: {}  updated: {}", note.id, format_time(note.updated_at));
+    if !details.tags.is_empty() {
+        print_tags(&details.tags);
+    }
+    println!();
+    if note.locked {
+        println!("[encrypted — unlock it in the app to read it]");
+    } else {
+        println!("{}", note.content);
+    }
+}
+
+fn print_summary(note: &NoteSummary) {
+    let marker = if note.encrypted { " [encrypted]" } else { "" };
+    println!("{}  {}  {}{}", note.id, format_time(note.updated_at), note.title, marker);
+}
+
+fn print_tags(tags: &[Tag]) {
+    let names: Vec<String> = tags.iter().map(|t| format!("#{}", t.name)).collect();
+    println!("{}", names.join(" "));
+}
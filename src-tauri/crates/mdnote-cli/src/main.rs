@@ -0,0 +1,137 @@
+//! `mdnote-cli`: read and change an MDNote vault from scripts and cron jobs.
+//!
+//! It opens the same `mdnote.db` as the desktop app. The database runs in WAL mode with
+//! a busy timeout, so the CLI and a running app can use it at the same time.
+
+mod commands;
+mod mcp;
+mod output;
+mod vault;
+
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use output::Output;
+
+#[derive(Parser)]
+#[command(name = "mdnote-cli", version, about = "Script your MDNote vault from the shell")]
+struct Cli {
+    /// Vault to use: a registered vault's name or id, or a directory containing mdnote.db.
+    /// Defaults to the vault the app opened last.
+    #[arg(long, global = true, env = "MDNOTE_VAULT")]
+    vault: Option<String>,
+
+    /// Print JSON instead of human-readable text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a note. Without --content, the content is read from stdin when piped.
+    New {
+        title: String,
+        /// Folder id or name
+        #[arg(long)]
+        folder: Option<String>,
+        #[arg(long)]
+        content: Option<String>,
+    },
+    /// Print a note with its tags
+    Show { id: String },
+    /// Edit a note's content in $VISUAL or $EDITOR
+    Edit { id: String },
+    /// List notes in a folder or with a tag (root notes by default)
+    Ls {
+        /// Folder id or name
+        #[arg(long)]
+        folder: Option<String>,
+        /// Tag id or name
+        #[arg(long)]
+        tag: Option<String>,
+        /// Also list notes tagged with descendants of --tag (`project` includes `project/alpha`)
+        #[arg(long, requires = "tag")]
+        descendants: bool,
+        #[arg(long, default_value_t = 50)]
+        limit: i32,
+        #[arg(long, default_value_t = 0)]
+        offset: i32,
+    },
+    /// Full-text search over titles and content
+    Search {
+        query: String,
+        #[arg(long, default_value_t = 20)]
+        limit: i32,
+    },
+    /// List tags, or add and remove them on a note
+    Tag {
+        #[command(subcommand)]
+        action: TagAction,
+    },
+    /// Move a note into a folder ("/" for the root)
+    Mv { id: String, folder: String },
+    /// Delete notes
+    Rm {
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    /// Show the notes a note links to and the notes linking to it
+    Links { id: String },
+    /// Write notes as Markdown files into --out, or print a single note to stdout
+    Export {
+        ids: Vec<String>,
+        /// Export every note in the vault
+        #[arg(long, conflicts_with = "ids")]
+        all: bool,
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Serve the vault to AI assistants as a Model Context Protocol server on stdin/stdout
+    Mcp {
+        /// Offer only the tools that read
+        #[arg(long)]
+        read_only: bool,
+        /// Only share notes in this folder and its subfolders (id, name, or "/" for the root).
+        /// Repeat to share several folders.
+        #[arg(long = "folder")]
+        folders: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagAction {
+    /// List all tags, or the tags of one note
+    Ls { note: Option<String> },
+    /// Tag a note, creating tags that don't exist yet
+    Add {
+        note: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Remove tags from a note
+    Rm {
+        note: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let out = Output::new(cli.json);
+
+    let result = vault::resolve(cli.vault.as_deref())
+        .and_then(vault::open)
+        .and_then(|db| commands::run(&db, cli.command, &out));
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            out.error(&e);
+            ExitCode::FAILURE
+        }
+    }
+}
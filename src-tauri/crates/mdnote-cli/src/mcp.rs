@@ -0,0 +1,440 @@
+//! `mdnote-cli mcp`: a Model Context Protocol server, so local AI assistants can read and
+//! write notes through tools and resources instead of scraping the UI.
+//!
+//! It speaks newline-delimited JSON-RPC 2.0 on stdin/stdout, one message per line.
+//! Logging must go to stderr; anything else on stdout would corrupt the stream.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+use mdnote_core::errors::{AppError, Result};
+use mdnote_core::models::note::Note;
+use mdnote_core::services::backlink::BacklinkService;
+use mdnote_core::services::folder::FolderService;
+use mdnote_core::services::note::NoteService;
+use mdnote_core::services::search::SearchService;
+use mdnote_core::services::tag::TagService;
+use mdnote_core::DatabaseService;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use crate::commands::{all_folders, notes_in_folders, resolve_folder};
+
+/// Protocol revisions this server can speak, newest first.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+const NOTE_URI_PREFIX: &str = "mdnote://notes/";
+const DEFAULT_SEARCH_LIMIT: i32 = 20;
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+const RESOURCE_NOT_FOUND: i64 = -32002;
+
+/// What the connected assistant may see and do.
+pub struct Policy {
+    read_only: bool,
+    /// The shared folders by id, each with its subfolders; `None` in the list is the root.
+    /// `None` for the whole policy means every folder.
+    roots: Option<Vec<Option<String>>>,
+}
+
+impl Policy {
+    /// `folders` are ids or names as accepted by `--folder`, "/" being the root. Empty
+    /// means the whole vault. Names are resolved once, so renaming a shared folder keeps
+    /// it shared and a new folder taking its old name doesn't become shared.
+    pub fn new(db: &DatabaseService, read_only: bool, folders: &[String]) -> Result<Self> {
+        if folders.is_empty() {
+            return Ok(Self { read_only, roots: None });
+        }
+        let roots = folders.iter().map(|f| resolve_folder(db, f)).collect::<Result<Vec<_>>>()?;
+        Ok(Self { read_only, roots: Some(roots) })
+    }
+
+    /// The folders visible right now, or `None` for all of them. Taken from the folder
+    /// tree per request, so subfolders created since startup are included and deleted
+    /// folders drop out.
+    fn folders(&self, db: &DatabaseService) -> Result<Option<HashSet<Option<String>>>> {
+        let Some(roots) = &self.roots else { return Ok(None) };
+
+        let mut children: HashMap<String, Vec<String>> = HashMap::new();
+        let mut existing = HashSet::new();
+        for folder in FolderService::new(db).list_folders()? {
+            if let Some(parent) = folder.parent_id {
+                children.entry(parent).or_default().push(folder.id.clone());
+            }
+            existing.insert(folder.id);
+        }
+
+        let mut allowed = HashSet::new();
+        let mut pending: Vec<Option<String>> = roots
+            .iter()
+            .filter(|root| root.as_ref().is_none_or(|id| existing.contains(id)))
+            .cloned()
+            .collect();
+        while let Some(folder) = pending.pop() {
+            if let Some(id) = &folder {
+                pending.extend(children.get(id).into_iter().flatten().cloned().map(Some));
+            }
+            allowed.insert(folder);
+        }
+        Ok(Some(allowed))
+    }
+
+    fn allows(&self, db: &DatabaseService, folder_id: &Option<String>) -> Result<bool> {
+        Ok(self.folders(db)?.is_none_or(|allowed| allowed.contains(folder_id)))
+    }
+
+    fn folder_list(&self, db: &DatabaseService) -> Result<Option<Vec<Option<String>>>> {
+        Ok(self.folders(db)?.map(|allowed| allowed.into_iter().collect()))
+    }
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    /// Absent for notifications, which get no response.
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Serves requests from stdin until it is closed.
+pub fn serve(db: &DatabaseService, policy: Policy) -> Result<()> {
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    Session { db, policy }.run(stdin.lock(), stdout.lock())
+}
+
+struct Session<'a> {
+    db: &'a DatabaseService,
+    policy: Policy,
+}
+
+impl Session<'_> {
+    fn run(&self, input: impl BufRead, mut output: impl Write) -> Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle(&line) {
+                writeln!(output, "{}", response)
+                    .and_then(|_| output.flush())
+                    ?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle(&self, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => return Some(reply(Value::Null, Err(RpcError::new(PARSE_ERROR, e.to_string())))),
+        };
+        let request: Request = match serde_json::from_value(message) {
+            Ok(request) => request,
+            Err(e) => return Some(reply(Value::Null, Err(RpcError::new(INVALID_REQUEST, e.to_string())))),
+        };
+
+        // Responses to our own requests and notifications such as `initialized` need no answer
+        let id = request.id?;
+        Some(reply(id, self.dispatch(&request.method, request.params)))
+    }
+
+    fn dispatch(&self, method: &str, params: Value) -> std::result::Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(self.initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": self.tools() })),
+            "tools/call" => self.call_tool(params),
+            "resources/list" => self.list_resources().map_err(internal),
+            "resources/templates/list" => Ok(json!({
+                "resourceTemplates": [{
+                    "uriTemplate": format!("{}{{id}}", NOTE_URI_PREFIX),
+                    "name": "Note",
+                    "description": "A note's Markdown content, by note id",
+                    "mimeType": "text/markdown",
+                }],
+            })),
+            "resources/read" => self.read_resource(params),
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("Unknown method {}", method))),
+        }
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        let requested = params["protocolVersion"].as_str().unwrap_or_default();
+        let version = PROTOCOL_VERSIONS
+            .iter()
+            .find(|v| **v == requested)
+            .unwrap_or(&PROTOCOL_VERSIONS[0]);
+        let mut instructions = "Notes are Markdown. Link notes with [[note-id]] and tag them inline with #tag.".to_string();
+        if self.policy.read_only {
+            instructions.push_str(" This vault is shared read-only.");
+        }
+
+        json!({
+            "protocolVersion": version,
+            "capabilities": { "tools": {}, "resources": {} },
+            "serverInfo": { "name": "mdnote", "version": env!("CARGO_PKG_VERSION") },
+            "instructions": instructions,
+        })
+    }
+
+    /// Write tools are not offered at all in read-only mode.
+    fn tools(&self) -> Vec<Value> {
+        let id = json!({ "type": "string", "description": "Note id" });
+        let mut tools = vec![
+            json!({
+                "name": "search_notes",
+                "description": "Full-text search over note titles and content. Returns ids, titles and snippets.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "query": { "type": "string", "description": "FTS5 query, e.g. `rust AND async`" },
+                        "limit": { "type": "integer", "minimum": 1 },
+                    },
+                    "required": ["query"],
+                },
+                "annotations": { "readOnlyHint": true },
+            }),
+            json!({
+                "name": "get_note",
+                "description": "A note's title, Markdown content and tags.",
+                "inputSchema": { "type": "object", "properties": { "id": id }, "required": ["id"] },
+                "annotations": { "readOnlyHint": true },
+            }),
+            json!({
+                "name": "list_tags",
+                "description": "Every tag with the number of notes carrying it.",
+                "inputSchema": { "type": "object", "properties": {} },
+                "annotations": { "readOnlyHint": true },
+            }),
+            json!({
+                "name": "get_backlinks",
+                "description": "Notes that link to a note, with the text around each link.",
+                "inputSchema": { "type": "object", "properties": { "id": id }, "required": ["id"] },
+                "annotations": { "readOnlyHint": true },
+            }),
+        ];
+        if !self.policy.read_only {
+            tools.push(json!({
+                "name": "create_note",
+                "description": "Create a note and return it.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "title": { "type": "string" },
+                        "content": { "type": "string", "description": "Markdown" },
+                        "folder": { "type": "string", "description": "Folder id or name; the root when omitted" },
+                    },
+                    "required": ["title"],
+                },
+            }));
+            tools.push(json!({
+                "name": "append_to_note",
+                "description": "Add Markdown to the end of a note, on a new line.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": { "id": id, "text": { "type": "string" } },
+                    "required": ["id", "text"],
+                },
+            }));
+        }
+        tools
+    }
+
+    fn call_tool(&self, params: Value) -> std::result::Result<Value, RpcError> {
+        let name = params["name"].as_str().unwrap_or_default().to_string();
+        let args = match params.get("arguments") {
+            Some(Value::Null) | None => json!({}),
+            Some(args) => args.clone(),
+        };
+
+        let result = match name.as_str() {
+            "search_notes" => self.search_notes(parse_args(args)?),
+            "get_note" => self.get_note(parse_args(args)?),
+            "list_tags" => self.list_tags(),
+            "get_backlinks" => self.get_backlinks(parse_args(args)?),
+            "create_note" if !self.policy.read_only => self.create_note(parse_args(args)?),
+            "append_to_note" if !self.policy.read_only => self.append_to_note(parse_args(args)?),
+            _ => return Err(RpcError::new(INVALID_PARAMS, format!("Unknown tool {}", name))),
+        };
+
+        // Failures inside a tool are reported to the model, not as protocol errors
+        Ok(match result {
+            Ok(text) => json!({ "content": [{ "type": "text", "text": text }], "isError": false }),
+            Err(e) => json!({ "content": [{ "type": "text", "text": e.to_string() }], "isError": true }),
+        })
+    }
+
+    fn search_notes(&self, args: SearchArgs) -> Result<String> {
+        let search = SearchService::new(self.db);
+        let limit = args.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+        // Filtering inside the query keeps pages full when folders are hidden
+        match self.policy.folder_list(self.db)? {
+            Some(folders) => to_text(search.search_notes_in_folders(args.query, &folders, limit)?),
+            None => to_text(search.search_notes(args.query, limit)?),
+        }
+    }
+
+    /// Tags and note counts as far as the shared folders go, so hidden notes stay hidden.
+    fn list_tags(&self) -> Result<String> {
+        let tags = TagService::new(self.db);
+        match self.policy.folder_list(self.db)? {
+            Some(folders) => to_text(tags.list_tags_in_folders(&folders)?),
+            None => to_text(tags.list_tags()?),
+        }
+    }
+
+    fn get_note(&self, args: NoteArgs) -> Result<String> {
+        let note = self.note(args.id)?;
+        let tags = TagService::new(self.db).get_note_tags(note.id.clone())?;
+        to_text(json!({ "note": note, "tags": tags }))
+    }
+
+    fn get_backlinks(&self, args: NoteArgs) -> Result<String> {
+        let note = self.note(args.id)?;
+        let mut links = BacklinkService::new(self.db).get_backlinks(note.id)?;
+        if let Some(allowed) = self.policy.folders(self.db)? {
+            let notes = NoteService::new(self.db);
+            let mut visible = Vec::new();
+            for link in links {
+                match notes.get_note(link.source_id.clone()) {
+                    Ok(source) if allowed.contains(&source.folder_id) => visible.push(link),
+                    Ok(_) | Err(AppError::NotFound(_)) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            links = visible;
+        }
+        to_text(links)
+    }
+
+    fn create_note(&self, args: CreateArgs) -> Result<String> {
+        let folder_id = match args.folder {
+            Some(folder) => resolve_folder(self.db, &folder)?,
+            None => None,
+        };
+        if !self.policy.allows(self.db, &folder_id)? {
+            return Err(AppError::invalid("folder", "That folder is not shared; pass a shared folder"));
+        }
+
+        let note = NoteService::new(self.db).create_note(folder_id, args.title, args.content.unwrap_or_default())?;
+        BacklinkService::new(self.db).sync_links(note.id.clone(), &note.content)?;
+        to_text(note)
+    }
+
+    fn append_to_note(&self, args: AppendArgs) -> Result<String> {
+        let note = self.note(args.id)?;
+        if note.locked {
+            return Err(AppError::Locked(format!("Note {} is encrypted", note.id)));
+        }
+
+        let mut content = note.content;
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&args.text);
+        let note = NoteService::new(self.db).update_note(note.id, None, Some(content), None, Some(note.updated_at))?;
+        BacklinkService::new(self.db).sync_links(note.id.clone(), &note.content)?;
+        to_text(note)
+    }
+
+    fn list_resources(&self) -> Result<Value> {
+        let folders = match self.policy.folder_list(self.db)? {
+            Some(folders) => folders,
+            None => all_folders(self.db)?,
+        };
+        let resources: Vec<Value> = notes_in_folders(self.db, folders)?
+            .into_iter()
+            .map(|note| {
+                json!({
+                    "uri": format!("{}{}", NOTE_URI_PREFIX, note.id),
+                    "name": note.title,
+                    "mimeType": "text/markdown",
+                })
+            })
+            .collect();
+        Ok(json!({ "resources": resources }))
+    }
+
+    fn read_resource(&self, params: Value) -> std::result::Result<Value, RpcError> {
+        let uri = params["uri"].as_str().unwrap_or_default().to_string();
+        let Some(id) = uri.strip_prefix(NOTE_URI_PREFIX) else {
+            return Err(RpcError::new(RESOURCE_NOT_FOUND, format!("Unknown resource {}", uri)));
+        };
+
+        let note = self.note(id.to_string()).map_err(|e| match e {
+            AppError::NotFound(msg) => RpcError::new(RESOURCE_NOT_FOUND, msg),
+            e => internal(e),
+        })?;
+        if note.locked {
+            return Err(internal(AppError::Locked(format!("Note {} is encrypted", note.id))));
+        }
+        Ok(json!({ "contents": [{ "uri": uri, "mimeType": "text/markdown", "text": note.content }] }))
+    }
+
+    /// A note the policy lets the assistant see. Others look missing rather than forbidden.
+    fn note(&self, id: String) -> Result<Note> {
+        let note = NoteService::new(self.db).get_note(id.clone())?;
+        if !self.policy.allows(self.db, &note.folder_id)? {
+            return Err(AppError::NotFound(format!("Note {} not found", id)));
+        }
+        Ok(note)
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchArgs {
+    query: String,
+    limit: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct NoteArgs {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct CreateArgs {
+    title: String,
+    content: Option<String>,
+    folder: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AppendArgs {
+    id: String,
+    text: String,
+}
+
+fn parse_args<T: for<'de> Deserialize<'de>>(args: Value) -> std::result::Result<T, RpcError> {
+    serde_json::from_value(args).map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid arguments: {}", e)))
+}
+
+fn to_text<T: serde::Serialize>(value: T) -> Result<String> {
+    serde_json::to_string_pretty(&value).map_err(|e| AppError::validation(e.to_string()))
+}
+
+fn internal(e: AppError) -> RpcError {
+    RpcError::new(INTERNAL_ERROR, e.to_string())
+}
+
+fn reply(id: Value, result: std::result::Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": e.code, "message": e.message } }),
+    }
+}
@@ -0,0 +1,40 @@
+use mdnote_core::errors::AppError;
+use serde::Serialize;
+
+/// Prints results either as JSON or through a human-readable formatter.
+pub struct Output {
+    json: bool,
+}
+
+impl Output {
+    pub fn new(json: bool) -> Self {
+        Self { json }
+    }
+
+    pub fn emit<T: Serialize>(&self, value: &T, human: impl FnOnce(&T)) {
+        if self.json {
+            match serde_json::to_string_pretty(value) {
+                Ok(json) => println!("{}", json),
+                Err(e) => eprintln!("error: could not serialize output: {}", e),
+            }
+        } else {
+            human(value);
+        }
+    }
+
+    pub fn error(&self, error: &AppError) {
+        if self.json {
+            if let Ok(json) = serde_json::to_string(error) {
+                eprintln!("{}", json);
+                return;
+            }
+        }
+        eprintln!("error: {}", error.report());
+    }
+}
+
+pub fn format_time(millis: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,67 @@
+use std::path::{Path, PathBuf};
+use mdnote_core::errors::{AppError, Result};
+use mdnote_core::services::database::DB_FILE_NAME;
+use mdnote_core::services::encryption::{is_encrypted, recover_reencryption, KeyParams};
+use mdnote_core::services::vault::VaultService;
+use mdnote_core::DatabaseService;
+
+/// Must match `identifier` in tauri.conf.json: the app keeps its data in `<data dir>/<identifier>`.
+const APP_IDENTIFIER: &str = "com.salvadalba.mdnote";
+const PASSPHRASE_ENV: &str = "MDNOTE_PASSPHRASE";
+
+fn app_data_dir() -> Result<PathBuf> {
+    dirs::data_dir()
+        .map(|dir| dir.join(APP_IDENTIFIER))
+        .ok_or_else(|| AppError::NotFound("Could not locate the app data directory".to_string()))
+}
+
+/// Finds the vault directory for `--vault`: a registered vault's id or name, else a path.
+/// Without one, the vault the app opened last.
+pub fn resolve(vault: Option<&str>) -> Result<PathBuf> {
+    let app_data_dir = app_data_dir()?;
+    let vaults = VaultService::new(&app_data_dir);
+
+    let dir = match vault {
+        Some(wanted) => {
+            let registered = vaults
+                .list_vaults()?
+                .into_iter()
+                .find(|v| v.id == wanted || v.name.eq_ignore_ascii_case(wanted));
+            match registered {
+                Some(v) => PathBuf::from(v.path),
+                None => PathBuf::from(wanted),
+            }
+        }
+        None => match vaults.last_opened()? {
+            Some(v) => PathBuf::from(v.path),
+            None => match vaults.list_vaults()?.into_iter().next() {
+                Some(v) => PathBuf::from(v.path),
+                None => app_data_dir,
+            },
+        },
+    };
+
+    // Never create a vault by accident, e.g. from a mistyped path
+    if !dir.join(DB_FILE_NAME).exists() {
+        return Err(AppError::NotFound(format!("No MDNote database in {}", dir.display())));
+    }
+    Ok(dir)
+}
+
+/// Opens the vault's database, keyed from `MDNOTE_PASSPHRASE` when it is encrypted.
+pub fn open(dir: PathBuf) -> Result<DatabaseService> {
+    recover_reencryption(&dir)?;
+    let key = if is_encrypted(&dir) {
+        let passphrase = std::env::var(PASSPHRASE_ENV).map_err(|_| {
+            AppError::Locked(format!("This vault is encrypted. Set {} to unlock it.", PASSPHRASE_ENV))
+        })?;
+        Some(key_params(&dir)?.derive(&passphrase)?)
+    } else {
+        None
+    };
+    DatabaseService::open(dir, key)
+}
+
+fn key_params(dir: &Path) -> Result<KeyParams> {
+    KeyParams::load(dir)?.ok_or_else(|| AppError::corrupt("Missing key parameters".to_string()))
+}
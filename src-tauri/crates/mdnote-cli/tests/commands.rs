@@ -0,0 +1,157 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use serde_json::Value;
+use mdnote_core::services::folder::FolderService;
+use mdnote_core::DatabaseService;
+
+/// An empty vault in a temp dir with an `Archive` folder.
+struct Vault {
+    dir: PathBuf,
+}
+
+impl Vault {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("mdnote-cli-test-{}", ulid::Ulid::new()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = DatabaseService::new(dir.clone()).unwrap();
+        FolderService::new(&db).create_folder("Archive".into(), None).unwrap();
+        Self { dir }
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_mdnote-cli"));
+        command
+            .arg("--vault")
+            .arg(&self.dir)
+            .args(args)
+            // Keep the app's real vault registry out of reach
+            .env("XDG_DATA_HOME", self.dir.join("data"))
+            .env("HOME", &self.dir);
+        command
+    }
+
+    /// Runs the CLI and returns its stdout, failing the test if it exits with an error.
+    fn run(&self, args: &[&str]) -> String {
+        let output = self.command(args).output().unwrap();
+        assert_success(&output, args);
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    /// Runs the CLI with `--json` and parses its stdout.
+    fn json(&self, args: &[&str]) -> Value {
+        let args = [&["--json"], args].concat();
+        serde_json::from_str(&self.run(&args)).unwrap()
+    }
+}
+
+impl Drop for Vault {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn assert_success(output: &Output, args: &[&str]) {
+    assert!(
+        output.status.success(),
+        "mdnote-cli {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn notes_are_created_listed_and_searched() {
+    let vault = Vault::new();
+
+    let id = vault.run(&["new", "Groceries", "--content", "milk and eggs"]);
+    let id = id.trim();
+    assert_eq!(id.len(), 26);
+    let created = vault.json(&["new", "Plans", "--content", "buy eggs", "--folder", "Archive"]);
+    assert_eq!(created["title"], "Plans");
+
+    let listed = vault.run(&["ls"]);
+    assert_eq!(listed.lines().count(), 1);
+    assert!(listed.starts_with(id) && listed.trim_end().ends_with("Groceries"));
+    let listed = vault.json(&["ls", "--folder", "Archive"]);
+    assert_eq!(listed["total"], 1);
+    assert_eq!(listed["notes"][0]["id"], created["id"]);
+
+    let hits = vault.run(&["search", "milk"]);
+    assert_eq!(hits.lines().next().unwrap(), format!("{}  Groceries", id));
+    assert!(hits.contains("==milk=="));
+    let hits = vault.json(&["search", "eggs"]);
+    assert_eq!(hits.as_array().unwrap().len(), 2);
+
+    let failed = vault.command(&["--json", "ls", "--folder", "Nowhere"]).output().unwrap();
+    assert!(!failed.status.success());
+    let error: Value = serde_json::from_slice(&failed.stderr).unwrap();
+    assert_eq!(error["code"], "NOT_FOUND");
+}
+
+#[test]
+fn notes_are_moved_exported_and_removed() {
+    let vault = Vault::new();
+    let first = vault.run(&["new", "First", "--content", "one"]).trim().to_string();
+    let second = vault.run(&["new", "Second/half", "--content", "two"]).trim().to_string();
+
+    assert_eq!(vault.run(&["mv", &first, "Archive"]).trim(), first);
+    let moved = vault.json(&["mv", &second, "Archive"]);
+    assert!(moved["folder_id"].is_string());
+    assert_eq!(vault.json(&["ls"])["total"], 0);
+    assert_eq!(vault.json(&["ls", "--folder", "Archive"])["total"], 2);
+
+    assert_eq!(vault.run(&["export", &first]), "one");
+    assert_eq!(vault.json(&["export", &first])["content"], "one");
+
+    let out = vault.dir.join("export");
+    let exported = vault.run(&["export", "--all", "--out", out.to_str().unwrap()]);
+    assert_eq!(exported.lines().count(), 2);
+    assert_eq!(std::fs::read_to_string(out.join("Second-half.md")).unwrap(), "two");
+    let report = vault.json(&["export", &first, "--out", vault.dir.join("again").to_str().unwrap()]);
+    assert_eq!(report["exported"][0]["id"], first.as_str());
+    assert_eq!(report["skipped"], Value::Array(Vec::new()));
+
+    assert_eq!(vault.run(&["rm", &first]).trim(), first);
+    assert_eq!(vault.json(&["rm", &second]), serde_json::json!([second]));
+    assert_eq!(vault.json(&["ls", "--folder", "Archive"])["total"], 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn edit_uses_a_private_temp_file_and_removes_it() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let vault = Vault::new();
+    let id = vault.run(&["new", "Draft", "--content", "first line"]).trim().to_string();
+
+    // The "editor" appends a line and notes where the file was and how it was shared
+    let seen = vault.dir.join("seen");
+    let editor = vault.dir.join("editor.sh");
+    std::fs::write(
+        &editor,
+        format!("#!/bin/sh\necho \"$1\" > {0}\nls -l \"$1\" | cut -c1-10 >> {0}\nprintf '\\nsecond line' >> \"$1\"\n", seen.display()),
+    )
+    .unwrap();
+    std::fs::set_permissions(&editor, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let output = vault.command(&["edit", &id]).env("VISUAL", &editor).output().unwrap();
+    assert_success(&output, &["edit"]);
+    assert_eq!(vault.json(&["show", &id])["content"], "first line\nsecond line");
+
+    let seen = std::fs::read_to_string(seen).unwrap();
+    let mut seen = seen.lines();
+    let path = seen.next().unwrap();
+    assert!(!path.contains(&id), "temp file name should not reveal the note: {}", path);
+    assert_eq!(seen.next(), Some("-rw-------"));
+    assert!(!std::path::Path::new(path).exists());
+
+    // A failing editor leaves nothing behind either
+    let failed = vault.dir.join("failed");
+    let editor = vault.dir.join("failing.sh");
+    std::fs::write(&editor, format!("#!/bin/sh\necho \"$1\" > {}\nexit 1\n", failed.display())).unwrap();
+    std::fs::set_permissions(&editor, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let output = vault.command(&["edit", &id]).env("VISUAL", &editor).output().unwrap();
+    assert!(!output.status.success());
+    let path = std::fs::read_to_string(failed).unwrap();
+    assert!(!std::path::Path::new(path.trim()).exists());
+}
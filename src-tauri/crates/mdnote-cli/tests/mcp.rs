@@ -0,0 +1,257 @@
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use serde_json::{json, Value};
+use mdnote_core::services::folder::FolderService;
+use mdnote_core::services::note::NoteService;
+use mdnote_core::DatabaseService;
+
+/// A vault in a temp dir with a shared folder tree and a private folder.
+struct Vault {
+    dir: PathBuf,
+    shared_note: String,
+    nested_note: String,
+    private_note: String,
+}
+
+impl Vault {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("mdnote-cli-test-{}", ulid::Ulid::new()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let db = DatabaseService::new(dir.clone()).unwrap();
+        let folders = FolderService::new(&db);
+        let shared = folders.create_folder("Shared".into(), None).unwrap();
+        let nested = folders.create_folder("Nested".into(), Some(shared.id.clone())).unwrap();
+        let private = folders.create_folder("Private".into(), None).unwrap();
+
+        let notes = NoteService::new(&db);
+        let note = |folder: &str, title: &str| {
+            notes.create_note(Some(folder.to_string()), title.into(), format!("{} about apples", title)).unwrap().id
+        };
+        Self {
+            shared_note: note(&shared.id, "Shared plan"),
+            nested_note: note(&nested.id, "Nested plan"),
+            private_note: note(&private.id, "Diary"),
+            dir,
+        }
+    }
+
+    /// Starts `mdnote-cli mcp` with `args` on piped stdin and stdout.
+    fn start(&self, args: &[&str]) -> Child {
+        Command::new(env!("CARGO_BIN_EXE_mdnote-cli"))
+            .arg("--vault")
+            .arg(&self.dir)
+            .arg("mcp")
+            .args(args)
+            // Keep the app's real vault registry out of reach
+            .env("XDG_DATA_HOME", self.dir.join("data"))
+            .env("HOME", &self.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap()
+    }
+
+    /// Runs `mdnote-cli mcp` with `args`, feeds it `messages` and returns its responses.
+    fn session(&self, args: &[&str], messages: &[Value]) -> Vec<Value> {
+        let mut child = self.start(args);
+        let mut stdin = child.stdin.take().unwrap();
+        for message in messages {
+            writeln!(stdin, "{}", message).unwrap();
+        }
+        drop(stdin);
+
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+}
+
+impl Drop for Vault {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn call(id: i64, tool: &str, arguments: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": "tools/call", "params": { "name": tool, "arguments": arguments } })
+}
+
+fn request(id: i64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+/// The JSON a tool returned as text.
+fn tool_json(response: &Value) -> Value {
+    assert_eq!(response["result"]["isError"], false, "{}", response);
+    serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap()
+}
+
+fn tool_names(response: &Value) -> Vec<String> {
+    response["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+fn uris(response: &Value) -> Vec<String> {
+    response["result"]["resources"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["uri"].as_str().unwrap().to_string())
+        .collect()
+}
+
+fn uri(id: &str) -> String {
+    format!("mdnote://notes/{}", id)
+}
+
+#[test]
+fn assistant_reads_and_writes_notes() {
+    let vault = Vault::new();
+
+    let responses = vault.session(&[], &[
+        request(1, "initialize", json!({ "protocolVersion": "2024-11-05", "capabilities": {}, "clientInfo": { "name": "test", "version": "0" } })),
+        json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+        request(2, "tools/list", json!({})),
+        call(3, "create_note", json!({ "title": "Ideas", "content": "First", "folder": "Shared" })),
+        call(4, "search_notes", json!({ "query": "apples" })),
+        request(5, "resources/list", json!({})),
+        request(6, "no/such/method", json!({})),
+        call(7, "get_note", json!({ "id": "missing" })),
+    ]);
+
+    // The notification gets no response
+    assert_eq!(responses.len(), 7);
+    assert_eq!(responses[0]["result"]["protocolVersion"], "2024-11-05");
+    assert_eq!(responses[0]["result"]["serverInfo"]["name"], "mdnote");
+    assert!(tool_names(&responses[1]).contains(&"append_to_note".to_string()));
+
+    let created = tool_json(&responses[2]);
+    let id = created["id"].as_str().unwrap().to_string();
+    assert_eq!(tool_json(&responses[3]).as_array().unwrap().len(), 3);
+    assert_eq!(uris(&responses[4]).len(), 4);
+    assert_eq!(responses[5]["error"]["code"], -32601);
+    assert_eq!(responses[6]["result"]["isError"], true);
+
+    let responses = vault.session(&[], &[
+        call(1, "append_to_note", json!({ "id": id, "text": format!("See [[{}]]", vault.shared_note) })),
+        request(2, "resources/read", json!({ "uri": uri(&id) })),
+        call(3, "get_backlinks", json!({ "id": vault.shared_note })),
+    ]);
+    assert_eq!(tool_json(&responses[0])["content"], format!("First\nSee [[{}]]", vault.shared_note));
+    assert_eq!(responses[1]["result"]["contents"][0]["mimeType"], "text/markdown");
+    assert!(responses[1]["result"]["contents"][0]["text"].as_str().unwrap().starts_with("First\n"));
+    assert_eq!(tool_json(&responses[2])[0]["source_id"], id);
+}
+
+#[test]
+fn read_only_session_sees_only_shared_folders() {
+    let vault = Vault::new();
+
+    let responses = vault.session(&["--read-only", "--folder", "Shared"], &[
+        request(1, "tools/list", json!({})),
+        call(2, "create_note", json!({ "title": "Sneaky" })),
+        call(3, "search_notes", json!({ "query": "apples" })),
+        call(4, "get_note", json!({ "id": vault.private_note })),
+        request(5, "resources/list", json!({})),
+        request(6, "resources/read", json!({ "uri": uri(&vault.private_note) })),
+        call(7, "get_note", json!({ "id": vault.nested_note })),
+    ]);
+
+    let tools = tool_names(&responses[0]);
+    assert!(tools.contains(&"search_notes".to_string()));
+    assert!(!tools.contains(&"create_note".to_string()));
+    assert_eq!(responses[1]["error"]["code"], -32602);
+
+    let hits = tool_json(&responses[2]);
+    let hit_ids: Vec<&str> = hits.as_array().unwrap().iter().map(|h| h["id"].as_str().unwrap()).collect();
+    assert_eq!(hit_ids.len(), 2);
+    assert!(!hit_ids.contains(&vault.private_note.as_str()));
+
+    assert_eq!(responses[3]["result"]["isError"], true);
+    let mut listed = uris(&responses[4]);
+    listed.sort();
+    let mut expected = vec![uri(&vault.shared_note), uri(&vault.nested_note)];
+    expected.sort();
+    assert_eq!(listed, expected);
+    assert_eq!(responses[5]["error"]["code"], -32002);
+    assert_eq!(tool_json(&responses[6])["note"]["title"], "Nested plan");
+}
+
+#[test]
+fn hidden_folders_do_not_shape_search_pages_or_tags() {
+    let vault = Vault::new();
+    {
+        let db = DatabaseService::new(vault.dir.clone()).unwrap();
+        let notes = NoteService::new(&db);
+        let private = resolve_folder(&db, "Private");
+        for i in 0..5 {
+            notes.create_note(private.clone(), format!("Secret {}", i), "apples apples apples #secret".into()).unwrap();
+        }
+        let shared = resolve_folder(&db, "Shared");
+        notes.create_note(shared, "Groceries".into(), "apples #plan".into()).unwrap();
+    }
+
+    let responses = vault.session(&["--folder", "Shared"], &[
+        call(1, "search_notes", json!({ "query": "apples", "limit": 2 })),
+        call(2, "list_tags", json!({})),
+    ]);
+
+    let hits = tool_json(&responses[0]);
+    assert_eq!(hits.as_array().unwrap().len(), 2);
+    assert!(hits.as_array().unwrap().iter().all(|h| !h["title"].as_str().unwrap().starts_with("Secret")));
+
+    let tags = tool_json(&responses[1]);
+    let names: Vec<(&str, i64)> = tags.as_array().unwrap().iter()
+        .map(|t| (t["name"].as_str().unwrap(), t["note_count"].as_i64().unwrap()))
+        .collect();
+    assert_eq!(names, [("plan", 1)]);
+}
+
+#[test]
+fn shared_folders_follow_the_folder_tree_during_a_session() {
+    let vault = Vault::new();
+    let mut child = vault.start(&["--folder", "Shared"]);
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut ask = |message: Value| {
+        writeln!(stdin, "{}", message).unwrap();
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        serde_json::from_str::<Value>(&line).unwrap()
+    };
+    // The policy is in place once the first answer comes back
+    ask(request(1, "ping", json!({})));
+
+    let (later_note, nested) = {
+        let db = DatabaseService::new(vault.dir.clone()).unwrap();
+        let folders = FolderService::new(&db);
+        let later = folders.create_folder("Later".into(), resolve_folder(&db, "Shared")).unwrap();
+        let nested = resolve_folder(&db, "Nested").unwrap();
+        let note = NoteService::new(&db).create_note(Some(later.id), "Later plan".into(), String::new()).unwrap();
+        (note.id, nested)
+    };
+    assert_eq!(tool_json(&ask(call(2, "get_note", json!({ "id": later_note }))))["note"]["title"], "Later plan");
+
+    {
+        let db = DatabaseService::new(vault.dir.clone()).unwrap();
+        FolderService::new(&db).update_folder(nested, None, Some(None)).unwrap();
+    }
+    assert_eq!(ask(call(3, "get_note", json!({ "id": vault.nested_note })))["result"]["isError"], true);
+
+    drop(stdin);
+    assert!(child.wait().unwrap().success());
+}
+
+fn resolve_folder(db: &DatabaseService, name: &str) -> Option<String> {
+    FolderService::new(db).list_folders().unwrap().into_iter().find(|f| f.name == name).map(|f| f.id)
+}
@@ -0,0 +1,212 @@
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use crate::models::note::Note;
+
+/// The underlying error an `AppError` was raised from, kept for logs.
+pub type ErrorSource = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+#[derive(Debug)]
+pub enum AppError {
+    NotFound(String),
+    SqliteError { message: String, source: Option<ErrorSource> },
+    IoError { message: String, source: Option<ErrorSource> },
+    LockError { message: String, source: Option<ErrorSource> },
+    /// Bad input. `field` names the offending argument when there is a single one.
+    Validation { field: Option<String>, reason: String },
+    /// Something with the same name or key is already there.
+    AlreadyExists(String),
+    /// The change was based on stale data. For notes, `current` is the version stored now.
+    Conflict { message: String, current: Option<Box<Note>> },
+    Corrupt { message: String, source: Option<ErrorSource> },
+    Unsupported(String),
+    Locked(String),
+}
+
+impl AppError {
+    /// Invalid input in the argument called `field`.
+    pub fn invalid(field: &str, reason: impl Into<String>) -> Self {
+        AppError::Validation { field: Some(field.to_string()), reason: reason.into() }
+    }
+
+    /// Invalid input that isn't down to a single argument.
+    pub fn validation(reason: impl Into<String>) -> Self {
+        AppError::Validation { field: None, reason: reason.into() }
+    }
+
+    /// An I/O failure while doing `context`, keeping `source` for logs.
+    pub fn io(context: impl fmt::Display, source: impl Into<ErrorSource>) -> Self {
+        let source = source.into();
+        AppError::IoError { message: format!("{}: {}", context, source), source: Some(source) }
+    }
+
+    /// A resource that is busy or held by someone else, with no underlying error to keep.
+    pub fn lock(message: impl Into<String>) -> Self {
+        AppError::LockError { message: message.into(), source: None }
+    }
+
+    /// Damaged stored data, with no underlying error to keep.
+    pub fn corrupt(message: impl Into<String>) -> Self {
+        AppError::Corrupt { message: message.into(), source: None }
+    }
+
+    /// The stable identifier clients switch on. Never change an existing code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::SqliteError { .. } => "DATABASE",
+            AppError::IoError { .. } => "IO",
+            AppError::LockError { .. } => "BUSY",
+            AppError::Validation { .. } => "VALIDATION",
+            AppError::AlreadyExists(_) => "ALREADY_EXISTS",
+            AppError::Conflict { .. } => "CONFLICT",
+            AppError::Corrupt { .. } => "CORRUPT",
+            AppError::Unsupported(_) => "UNSUPPORTED",
+            AppError::Locked(_) => "LOCKED",
+        }
+    }
+
+    /// The human-readable description, without the kind prefix `Display` adds.
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::NotFound(msg)
+            | AppError::AlreadyExists(msg)
+            | AppError::Unsupported(msg)
+            | AppError::Locked(msg) => msg,
+            AppError::SqliteError { message, .. }
+            | AppError::IoError { message, .. }
+            | AppError::LockError { message, .. }
+            | AppError::Corrupt { message, .. }
+            | AppError::Conflict { message, .. } => message,
+            AppError::Validation { reason, .. } => reason,
+        }
+    }
+
+    /// The error followed by every cause in its source chain, for logs. The message
+    /// already describes the immediate source, so the chain starts below it.
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut source = std::error::Error::source(self).and_then(|s| s.source());
+        while let Some(cause) = source {
+            report.push_str(&format!(": {}", cause));
+            source = cause.source();
+        }
+        report
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound(msg) => write!(f, "Not Found: {}", msg),
+            AppError::SqliteError { message, .. } => write!(f, "Database Error: {}", message),
+            AppError::IoError { message, .. } => write!(f, "IO Error: {}", message),
+            AppError::LockError { message, .. } => write!(f, "Lock Error: {}", message),
+            AppError::Validation { field: Some(field), reason } => write!(f, "Validation Error: {}: {}", field, reason),
+            AppError::Validation { field: None, reason } => write!(f, "Validation Error: {}", reason),
+            AppError::AlreadyExists(msg) => write!(f, "Already Exists: {}", msg),
+            AppError::Conflict { message, .. } => write!(f, "Conflict: {}", message),
+            AppError::Corrupt { message, .. } => write!(f, "Database Corrupt: {}", message),
+            AppError::Unsupported(msg) => write!(f, "Unsupported: {}", msg),
+            AppError::Locked(msg) => write!(f, "Locked: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::SqliteError { source, .. }
+            | AppError::IoError { source, .. }
+            | AppError::LockError { source, .. }
+            | AppError::Corrupt { source, .. } => {
+                source.as_deref().map(|s| s as &(dyn std::error::Error + 'static))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// `{ "code": "VALIDATION", "message": "...", "field": "name" }`: every error has `code`
+/// and `message`; `field` and `current` are only present for the variants that carry them.
+/// Sources stay out of the payload.
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("code", self.code())?;
+        map.serialize_entry("message", self.message())?;
+        match self {
+            AppError::Validation { field: Some(field), .. } => map.serialize_entry("field", field)?,
+            AppError::Conflict { current: Some(current), .. } => map.serialize_entry("current", current)?,
+            _ => {}
+        }
+        map.end()
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(err: rusqlite::Error) -> Self {
+        use rusqlite::ErrorCode;
+
+        let Some(code) = err.sqlite_error() else {
+            return match err {
+                rusqlite::Error::QueryReturnedNoRows => AppError::NotFound("No matching record".to_string()),
+                err => AppError::SqliteError { message: err.to_string(), source: Some(Box::new(err)) },
+            };
+        };
+        let message = err.to_string();
+        match code.code {
+            ErrorCode::ConstraintViolation => constraint_error(code.extended_code, &message)
+                .unwrap_or_else(|| AppError::SqliteError { message, source: Some(Box::new(err)) }),
+            ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => AppError::LockError { message, source: Some(Box::new(err)) },
+            ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase => AppError::Corrupt { message, source: Some(Box::new(err)) },
+            _ => AppError::SqliteError { message, source: Some(Box::new(err)) },
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        AppError::IoError { message: err.to_string(), source: Some(Box::new(err)) }
+    }
+}
+
+/// Turns a SQLite constraint failure into the error a caller can act on. SQLite names
+/// the constraint as `table.column`, e.g. `UNIQUE constraint failed: tags.name`.
+fn constraint_error(extended_code: i32, message: &str) -> Option<AppError> {
+    let target = message.split_once(": ").map(|(_, t)| t).unwrap_or("");
+    let (table, column) = target.split(',').next().unwrap_or("").trim().split_once('.').unwrap_or(("", ""));
+    let thing = table_noun(table);
+
+    match extended_code {
+        rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE | rusqlite::ffi::SQLITE_CONSTRAINT_PRIMARYKEY => {
+            Some(AppError::AlreadyExists(format!("A {} with this {} already exists", thing, column.replace('_', " "))))
+        }
+        rusqlite::ffi::SQLITE_CONSTRAINT_FOREIGNKEY => {
+            Some(AppError::NotFound("The note, folder or tag it refers to does not exist".to_string()))
+        }
+        rusqlite::ffi::SQLITE_CONSTRAINT_NOTNULL => Some(AppError::invalid(column, "is required")),
+        rusqlite::ffi::SQLITE_CONSTRAINT_CHECK => Some(AppError::validation(message.to_string())),
+        _ => None,
+    }
+}
+
+/// What a row of `table` is called in messages.
+fn table_noun(table: &str) -> &'static str {
+    match table {
+        "notes" => "note",
+        "folders" => "folder",
+        "tags" => "tag",
+        "note_tags" => "tag assignment",
+        "backlinks" => "link",
+        "settings" => "setting",
+        "templates" => "template",
+        "periodic_notes" => "periodic note",
+        "tasks" => "task",
+        "reminders" => "reminder",
+        "note_properties" => "note property",
+        _ => "record",
+    }
+}
+
+pub type Result<T> = std::result::Result<T, AppError>;
@@ -0,0 +1,11 @@
+//! Everything MDNote knows about notes, folders, tags, search and vault storage,
+//! with no dependency on the desktop shell. Open a `DatabaseService` (or an in-memory
+//! one with `DatabaseService::open_in_memory`) and hand it to the services.
+
+pub mod errors;
+pub mod migrations;
+pub mod models;
+pub mod services;
+
+pub use errors::{AppError, Result};
+pub use services::database::{DatabaseService, DbPool, DbState};
@@ -10,8 +10,4 @@ CREATE TABLE IF NOT EXISTS backlinks (
 CREATE TABLE IF NOT EXISTS settings (
     key TEXT PRIMARY KEY,
     value TEXT NOT NULL
-);
-CREATE TABLE IF NOT EXISTS _migrations (
-    version INTEGER PRIMARY KEY,
-    applied_at INTEGER NOT NULL
 );
\ No newline at end of file
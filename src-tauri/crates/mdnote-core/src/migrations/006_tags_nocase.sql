@@ -0,0 +1,18 @@
+-- Migration 006: Case-insensitive tag names
+-- Fold tags whose names differ only by case into the oldest of them
+INSERT OR IGNORE INTO note_tags (note_id, tag_id)
+SELECT nt.note_id,
+    (
+        SELECT MIN(k.id)
+        FROM tags k
+        WHERE k.name = t.name COLLATE NOCASE
+    )
+FROM note_tags nt
+    JOIN tags t ON t.id = nt.tag_id;
+DELETE FROM tags
+WHERE id <> (
+        SELECT MIN(k.id)
+        FROM tags k
+        WHERE k.name = tags.name COLLATE NOCASE
+    );
+CREATE UNIQUE INDEX IF NOT EXISTS idx_tags_name_nocase ON tags(name COLLATE NOCASE);
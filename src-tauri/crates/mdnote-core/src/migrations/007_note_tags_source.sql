@@ -0,0 +1,3 @@
+-- Migration 007: Track whether a note tag was attached by hand or parsed from an inline #hashtag
+ALTER TABLE note_tags ADD COLUMN source TEXT NOT NULL DEFAULT 'manual';
+CREATE INDEX IF NOT EXISTS idx_note_tags_note_source ON note_tags(note_id, source);
@@ -0,0 +1,3 @@
+-- Migration 008: Optional icon and description for tags
+ALTER TABLE tags ADD COLUMN icon TEXT;
+ALTER TABLE tags ADD COLUMN description TEXT;
@@ -0,0 +1,24 @@
+-- Migration 010: Per-note encryption
+-- Encrypted notes keep their ciphertext in `content` and the Argon2 parameters
+-- needed to re-derive their key in `key_params`. They never enter the FTS index.
+ALTER TABLE notes ADD COLUMN encrypted INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE notes ADD COLUMN key_params TEXT;
+
+DROP TRIGGER IF EXISTS notes_ai;
+DROP TRIGGER IF EXISTS notes_au;
+
+CREATE TRIGGER notes_ai
+AFTER
+INSERT ON notes WHEN new.encrypted = 0 BEGIN
+INSERT INTO notes_fts(id, title, content)
+VALUES (new.id, new.title, new.content);
+END;
+CREATE TRIGGER notes_au
+AFTER
+UPDATE ON notes BEGIN
+DELETE FROM notes_fts
+WHERE id = old.id;
+INSERT INTO notes_fts(id, title, content)
+SELECT new.id, new.title, new.content
+WHERE new.encrypted = 0;
+END;
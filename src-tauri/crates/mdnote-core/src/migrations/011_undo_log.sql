@@ -0,0 +1,21 @@
+-- Migration 011: Undo history
+-- Each user-level action is a row in undo_actions; the TEMP triggers each connection
+-- installs when it opens log every row it changed in undo_log, with the row before and
+-- after as JSON.
+CREATE TABLE IF NOT EXISTS undo_actions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    label TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    undone INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS undo_log (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    action_id INTEGER NOT NULL,
+    table_name TEXT NOT NULL,
+    row_key TEXT NOT NULL,
+    old_row TEXT,
+    new_row TEXT,
+    FOREIGN KEY (action_id) REFERENCES undo_actions(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_undo_log_action ON undo_log(action_id);
+CREATE INDEX IF NOT EXISTS idx_undo_log_row ON undo_log(table_name, row_key);
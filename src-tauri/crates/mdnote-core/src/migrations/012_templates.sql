@@ -0,0 +1,15 @@
+-- Migration 012: Note templates
+-- title and content may contain {{placeholders}}; tags is a JSON array of tag names
+-- attached to every note created from the template.
+CREATE TABLE IF NOT EXISTS templates (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    title TEXT NOT NULL DEFAULT '',
+    content TEXT NOT NULL DEFAULT '',
+    folder_id TEXT,
+    tags TEXT NOT NULL DEFAULT '[]',
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (folder_id) REFERENCES folders(id) ON DELETE SET NULL
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_templates_name_nocase ON templates(name COLLATE NOCASE);
@@ -0,0 +1,11 @@
+-- Migration 013: Daily, weekly and monthly notes
+-- Maps each period to its note by the period's first day (YYYY-MM-DD), so renaming the
+-- note or changing the title format doesn't lose it.
+CREATE TABLE IF NOT EXISTS periodic_notes (
+    period TEXT NOT NULL,
+    start_date TEXT NOT NULL,
+    note_id TEXT NOT NULL,
+    PRIMARY KEY (period, start_date),
+    FOREIGN KEY (note_id) REFERENCES notes(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_periodic_notes_note ON periodic_notes(note_id);
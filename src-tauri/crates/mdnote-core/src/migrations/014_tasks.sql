@@ -0,0 +1,15 @@
+-- Migration 014: Tasks
+-- The `- [ ]` checkbox items of each unencrypted note, re-indexed whenever its content is
+-- saved. line is 1-based; tags is a JSON array of the hashtags in the item's text.
+CREATE TABLE IF NOT EXISTS tasks (
+    note_id TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    done INTEGER NOT NULL DEFAULT 0,
+    due_date TEXT,
+    priority TEXT CHECK (priority IN ('high', 'medium', 'low')),
+    tags TEXT NOT NULL DEFAULT '[]',
+    PRIMARY KEY (note_id, line),
+    FOREIGN KEY (note_id) REFERENCES notes(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_tasks_due ON tasks(done, due_date);
@@ -0,0 +1,17 @@
+-- Migration 016: Reminders
+-- A reminder belongs to a note, or to the task on task_line of it. Times are epoch
+-- milliseconds; fired_at stays NULL until the scheduler fires it, and is cleared by a snooze.
+-- The scheduler fires reminders even when no window is listening yet, e.g. while the app
+-- starts, so delivered_at is only set once the frontend has shown a fired reminder.
+CREATE TABLE IF NOT EXISTS reminders (
+    id TEXT PRIMARY KEY,
+    note_id TEXT NOT NULL,
+    task_line INTEGER,
+    message TEXT,
+    remind_at INTEGER NOT NULL,
+    fired_at INTEGER,
+    delivered_at INTEGER,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (note_id) REFERENCES notes(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_reminders_pending ON reminders(fired_at, remind_at);
@@ -0,0 +1,15 @@
+-- Migration 017: Note properties
+-- The top-level keys of each unencrypted note's YAML front matter, re-indexed whenever its
+-- content is saved. value holds text, YYYY-MM-DD dates, 'true'/'false' or a JSON array for
+-- lists; number holds numbers, and 1/0 for bools, so they compare and sort numerically.
+CREATE TABLE IF NOT EXISTS note_properties (
+    note_id TEXT NOT NULL,
+    key TEXT NOT NULL COLLATE NOCASE,
+    type TEXT NOT NULL CHECK (type IN ('text', 'number', 'date', 'bool', 'list')),
+    value TEXT NOT NULL,
+    number REAL,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (note_id, key),
+    FOREIGN KEY (note_id) REFERENCES notes(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_note_properties_key ON note_properties(key, value);
@@ -0,0 +1,146 @@
+//! Migration 009: indexes the inline `#hashtags` of notes written before they were
+//! parsed on save. The parser and tag rules are frozen copies of what the services did at
+//! schema version 9, so later changes to the services never change what this migration
+//! does to an old database. Don't edit them along with the services.
+
+use std::ops::Range;
+use std::sync::OnceLock;
+use regex::Regex;
+use rusqlite::{params, OptionalExtension};
+use ulid::Ulid;
+use crate::errors::Result;
+use crate::services::database::DatabaseService;
+
+const MAX_TAG_NAME_LEN: usize = 64;
+
+/// The tag palette's colors in order; new tags get the least used one.
+const PALETTE: &[&str] = &[
+    "#3b82f6", "#ef4444", "#22c55e", "#f59e0b", "#a855f7", "#ec4899",
+    "#14b8a6", "#f97316", "#6366f1", "#06b6d4", "#84cc16", "#64748b",
+];
+
+fn hashtag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"#([\p{L}\p{N}_\-]+(?:/[\p{L}\p{N}_\-]+)*)").unwrap())
+}
+
+fn inline_code_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"`[^`\n]*`").unwrap())
+}
+
+fn url_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"[A-Za-z][A-Za-z0-9+.\-]*://\S+").unwrap())
+}
+
+fn heading_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^ {0,3}#{1,6}(\s|$)").unwrap())
+}
+
+/// Fenced code blocks, inline code spans, heading lines and URLs.
+fn excluded_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut fence: Option<(&str, usize)> = None;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+        match (fence, marker) {
+            (None, Some(m)) => fence = Some((m, offset)),
+            (Some((open, start)), Some(m)) if m == open => {
+                ranges.push(start..offset + line.len());
+                fence = None;
+            }
+            (None, None) if heading_regex().is_match(line) => ranges.push(offset..offset + line.len()),
+            _ => {}
+        }
+        offset += line.len();
+    }
+    if let Some((_, start)) = fence {
+        ranges.push(start..content.len());
+    }
+
+    ranges.extend(inline_code_regex().find_iter(content).map(|m| m.range()));
+    ranges.extend(url_regex().find_iter(content).map(|m| m.range()));
+    ranges
+}
+
+fn starts_hashtag(content: &str, hash_pos: usize, excluded: &[Range<usize>]) -> bool {
+    if excluded.iter().any(|r| r.contains(&hash_pos)) {
+        return false;
+    }
+    match content[..hash_pos].chars().next_back() {
+        None => true,
+        Some(c) => !(c.is_alphanumeric() || matches!(c, '_' | '-' | '/' | '#' | '&')),
+    }
+}
+
+/// The distinct hashtags in `content`, without the `#`, skipping purely numeric ones.
+/// The task backfill of migration 015 parses task tags with this too.
+pub(super) fn extract_hashtags(content: &str) -> Vec<String> {
+    let excluded = excluded_ranges(content);
+    let mut tags: Vec<String> = Vec::new();
+    for caps in hashtag_regex().captures_iter(content) {
+        let whole = caps.get(0).unwrap();
+        let tag = caps[1].trim_end_matches(['/', '-']);
+        if !starts_hashtag(content, whole.start(), &excluded) || tag.chars().all(|c| c.is_numeric()) {
+            continue;
+        }
+        if !tags.iter().any(|t| t.to_lowercase() == tag.to_lowercase()) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// The tag name `hashtag` is stored as, or `None` if it wouldn't make a valid one.
+fn tag_name(hashtag: &str) -> Option<String> {
+    let trimmed = hashtag.trim();
+    if trimmed.is_empty() || trimmed.contains(',') || trimmed.split('/').any(|s| s.trim().is_empty()) {
+        return None;
+    }
+    let normalized = trimmed.split('/').map(str::trim).collect::<Vec<_>>().join("/");
+    (normalized.chars().count() <= MAX_TAG_NAME_LEN).then_some(normalized)
+}
+
+fn next_palette_color(db: &DatabaseService) -> Result<&'static str> {
+    let mut stmt = db.connection().prepare_cached("SELECT lower(color), COUNT(*) FROM tags GROUP BY lower(color)")?;
+    let usage: Vec<(Option<String>, i64)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    let count_for = |hex: &str| usage.iter().find(|(c, _)| c.as_deref() == Some(hex)).map_or(0, |(_, n)| *n);
+    Ok(PALETTE.iter().copied().min_by_key(|hex| count_for(hex)).unwrap_or(PALETTE[0]))
+}
+
+pub(super) fn run(db: &DatabaseService) -> Result<()> {
+    let conn = db.connection();
+    let mut stmt = conn.prepare("SELECT id, content FROM notes WHERE content LIKE ?")?;
+    let notes: Vec<(String, String)> = stmt
+        .query_map(params!["%#%"], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    for (note_id, content) in notes {
+        for name in extract_hashtags(&content).iter().filter_map(|h| tag_name(h)) {
+            let existing: Option<String> = conn
+                .query_row("SELECT id FROM tags WHERE name = ? COLLATE NOCASE", params![name], |row| row.get(0))
+                .optional()?;
+            let tag_id = match existing {
+                Some(id) => id,
+                None => {
+                    let id = Ulid::new().to_string();
+                    let color = next_palette_color(db)?;
+                    conn.execute("INSERT INTO tags (id, name, color) VALUES (?, ?, ?)", params![id, name, color])?;
+                    id
+                }
+            };
+            conn.execute(
+                "INSERT OR IGNORE INTO note_tags (note_id, tag_id, source) VALUES (?, ?, 'inline')",
+                params![note_id, tag_id],
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,256 @@
+//! Migration 018: indexes the front matter of notes imported before properties were
+//! tracked. The YAML subset parser is a frozen copy of the one the property service had
+//! at schema version 18, so later changes to the service never change what this migration
+//! does to an old database. Don't edit it along with the service.
+
+use std::sync::OnceLock;
+use chrono::NaiveDate;
+use regex::Regex;
+use rusqlite::params;
+use crate::errors::{AppError, Result};
+use crate::services::database::DatabaseService;
+
+fn key_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"^([^\s#:\-"'][^:]*?|"[^"]+"|'[^']+')\s*:(?:\s+(.*))?$"#).unwrap())
+}
+
+fn number_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^[-+]?(?:\d+(?:\.\d*)?|\.\d+)(?:[eE][-+]?\d+)?$").unwrap())
+}
+
+fn date_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap())
+}
+
+enum Value {
+    Text(String),
+    Number(f64),
+    Date(String),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+struct Entry<'a> {
+    key: &'a str,
+    rest: &'a str,
+    continuation: Vec<&'a str>,
+}
+
+/// The YAML between a `---` first line and the next `---` or `...` line.
+fn front_matter(content: &str) -> Option<&str> {
+    let mut lines = content.split_inclusive('\n');
+    let first = lines.next()?;
+    if first.trim_end() != "---" {
+        return None;
+    }
+    let mut offset = first.len();
+    for line in lines {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return Some(&content[first.len()..offset]);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn entries(body: &str) -> Vec<Entry<'_>> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut in_entry = false;
+    for line in body.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        if text.trim().is_empty() || text.starts_with('#') {
+            continue;
+        }
+        if text.starts_with([' ', '\t']) || text == "-" || text.starts_with("- ") {
+            if let Some(entry) = entries.last_mut().filter(|_| in_entry) {
+                entry.continuation.push(text);
+            }
+            continue;
+        }
+
+        in_entry = false;
+        if let Some(caps) = key_regex().captures(text) {
+            let key = caps.get(1).unwrap().as_str();
+            let key = key.strip_prefix(['"', '\'']).and_then(|k| k.strip_suffix(['"', '\''])).unwrap_or(key);
+            let rest = caps.get(2).map_or("", |m| m.as_str());
+            entries.push(Entry { key, rest, continuation: Vec::new() });
+            in_entry = true;
+        }
+    }
+    entries
+}
+
+fn unquote(raw: &str) -> Option<String> {
+    if let Some(inner) = raw.strip_prefix('\'') {
+        let end = inner.rfind('\'')?;
+        return Some(inner[..end].replace("''", "'"));
+    }
+    let inner = raw.strip_prefix('"')?;
+    let mut text = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(text),
+            '\\' => match chars.next()? {
+                'n' => text.push('\n'),
+                't' => text.push('\t'),
+                other => text.push(other),
+            },
+            c => text.push(c),
+        }
+    }
+    None
+}
+
+fn strip_comment(raw: &str) -> &str {
+    match raw.find(" #") {
+        Some(at) => raw[..at].trim_end(),
+        None => raw.trim_end(),
+    }
+}
+
+fn is_null(raw: &str) -> bool {
+    raw.is_empty() || raw == "~" || raw.eq_ignore_ascii_case("null")
+}
+
+fn scalar(raw: &str) -> Option<Value> {
+    if let Some(text) = unquote(raw) {
+        return Some(Value::Text(text));
+    }
+    let raw = strip_comment(raw);
+    if is_null(raw) {
+        return None;
+    }
+    if raw.eq_ignore_ascii_case("true") || raw.eq_ignore_ascii_case("false") {
+        return Some(Value::Bool(raw.eq_ignore_ascii_case("true")));
+    }
+    if date_regex().is_match(raw) && NaiveDate::parse_from_str(raw, "%Y-%m-%d").is_ok() {
+        return Some(Value::Date(raw.to_string()));
+    }
+    if number_regex().is_match(raw) {
+        if let Ok(number) = raw.parse::<f64>() {
+            return Some(Value::Number(number));
+        }
+    }
+    Some(Value::Text(raw.to_string()))
+}
+
+fn item(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    unquote(raw).or_else(|| {
+        let raw = strip_comment(raw);
+        (!is_null(raw)).then(|| raw.to_string())
+    })
+}
+
+fn flow_items(inner: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let (mut start, mut quote) = (0, None);
+    for (i, c) in inner.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, ',') => {
+                items.extend(item(&inner[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.extend(item(&inner[start..]));
+    items
+}
+
+fn entry_value(entry: &Entry) -> Option<Value> {
+    let rest = entry.rest.trim();
+    if rest.starts_with('|') || rest.starts_with('>') {
+        let indent = entry.continuation.iter()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let lines: Vec<&str> = entry.continuation.iter().map(|l| l.get(indent..).unwrap_or("").trim_end()).collect();
+        let joined = if rest.starts_with('|') { lines.join("\n") } else { lines.join(" ") };
+        return Some(Value::Text(joined.trim_end().to_string()));
+    }
+    if let Some(inner) = rest.strip_prefix('[') {
+        return strip_comment(inner).strip_suffix(']').map(|inner| Value::List(flow_items(inner)));
+    }
+    if rest.starts_with('{') {
+        return None;
+    }
+    if rest.is_empty() || rest.starts_with('#') {
+        let items: Option<Vec<&str>> = entry.continuation.iter()
+            .map(|l| l.trim_start())
+            .map(|l| if l == "-" { Some("") } else { l.strip_prefix("- ") })
+            .collect();
+        return match items {
+            Some(items) if !items.is_empty() => Some(Value::List(items.into_iter().filter_map(item).collect())),
+            _ => None,
+        };
+    }
+
+    let mut raw = rest.to_string();
+    for line in &entry.continuation {
+        raw.push(' ');
+        raw.push_str(line.trim());
+    }
+    scalar(&raw)
+}
+
+/// The front matter's keys in order; of keys repeated in a different case the first wins.
+fn extract_properties(content: &str) -> Vec<(&str, Value)> {
+    let Some(body) = front_matter(content) else { return Vec::new() };
+    let mut properties: Vec<(&str, Value)> = Vec::new();
+    for entry in entries(body) {
+        if properties.iter().any(|(key, _)| key.eq_ignore_ascii_case(entry.key)) {
+            continue;
+        }
+        if let Some(value) = entry_value(&entry) {
+            properties.push((entry.key, value));
+        }
+    }
+    properties
+}
+
+fn format_number(number: f64) -> String {
+    if number.fract() == 0.0 && number.abs() < 1e15 {
+        format!("{}", number as i64)
+    } else {
+        number.to_string()
+    }
+}
+
+/// The row's type, value and number columns.
+fn stored(value: &Value) -> Result<(&'static str, String, Option<f64>)> {
+    Ok(match value {
+        Value::Text(text) => ("text", text.clone(), None),
+        Value::Date(date) => ("date", date.clone(), None),
+        Value::Number(number) => ("number", format_number(*number), Some(*number)),
+        Value::Bool(flag) => ("bool", flag.to_string(), Some(*flag as i64 as f64)),
+        Value::List(items) => ("list", serde_json::to_string(items).map_err(|e| AppError::validation(e.to_string()))?, None),
+    })
+}
+
+pub(super) fn run(db: &DatabaseService) -> Result<()> {
+    let conn = db.connection();
+    let mut stmt = conn.prepare("SELECT id, content FROM notes WHERE encrypted = 0 AND content LIKE ?")?;
+    let notes: Vec<(String, String)> = stmt
+        .query_map(params!["---%"], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    for (note_id, content) in notes {
+        for (position, (key, value)) in extract_properties(&content).iter().enumerate() {
+            let (kind, value, number) = stored(value)?;
+            conn.execute(
+                "INSERT OR REPLACE INTO note_properties (note_id, key, type, value, number, position) VALUES (?, ?, ?, ?, ?, ?)",
+                params![note_id, key, kind, value, number, position as i64],
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! Migration 015: indexes the tasks of notes written before tasks were tracked. The
+//! checkbox parser is a frozen copy of the one the task service had at schema version 15,
+//! so later changes to the service never change what this migration does to an old
+//! database. Don't edit it along with the service.
+
+use std::sync::OnceLock;
+use chrono::NaiveDate;
+use regex::{Captures, Regex};
+use rusqlite::params;
+use crate::errors::{AppError, Result};
+use crate::services::database::DatabaseService;
+use super::backfill_inline_tags::extract_hashtags;
+
+fn checkbox_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^(\s*(?:[-*+]|\d+[.)])\s+\[)([ xX])\]\s+(\S.*)$").unwrap())
+}
+
+fn due_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?:\bdue:\s*|📅\s*)(\d{4}-\d{2}-\d{2})\b").unwrap())
+}
+
+fn priority_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)\bpriority:\s*(high|medium|low)\b|(⏫|🔺|🔼|🔽|⏬)").unwrap())
+}
+
+struct ParsedTask {
+    line: usize,
+    text: String,
+    done: bool,
+    due_date: Option<String>,
+    priority: Option<&'static str>,
+    tags: Vec<String>,
+}
+
+/// The checkbox items outside fenced code blocks, with 1-based lines.
+fn extract_tasks(content: &str) -> Vec<ParsedTask> {
+    let mut tasks = Vec::new();
+    let mut fence: Option<&str> = None;
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+        match (fence, marker) {
+            (None, Some(m)) => fence = Some(m),
+            (Some(open), Some(m)) if m == open => fence = None,
+            (None, None) => {
+                if let Some(caps) = checkbox_regex().captures(line) {
+                    tasks.push(parse_task(index + 1, &caps[2] != " ", &caps[3]));
+                }
+            }
+            _ => {}
+        }
+    }
+    tasks
+}
+
+/// Takes the first valid due date and priority marker out of the text.
+fn parse_task(line: usize, done: bool, raw: &str) -> ParsedTask {
+    let mut due_date = None;
+    let text = due_regex().replace_all(raw, |caps: &Captures| {
+        match NaiveDate::parse_from_str(&caps[1], "%Y-%m-%d") {
+            Ok(date) => {
+                due_date.get_or_insert(date.format("%Y-%m-%d").to_string());
+                String::new()
+            }
+            Err(_) => caps[0].to_string(),
+        }
+    });
+
+    let mut priority = None;
+    let text = priority_regex().replace_all(&text, |caps: &Captures| {
+        let parsed = match caps.get(1) {
+            Some(name) => match name.as_str().to_lowercase().as_str() {
+                "high" => "high",
+                "medium" => "medium",
+                _ => "low",
+            },
+            None => match &caps[2] {
+                "⏫" | "🔺" => "high",
+                "🔼" => "medium",
+                _ => "low",
+            },
+        };
+        priority.get_or_insert(parsed);
+        String::new()
+    });
+
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let tags = extract_hashtags(&text);
+    ParsedTask { line, text, done, due_date, priority, tags }
+}
+
+pub(super) fn run(db: &DatabaseService) -> Result<()> {
+    let conn = db.connection();
+    let mut stmt = conn.prepare("SELECT id, content FROM notes WHERE encrypted = 0 AND content LIKE ?")?;
+    let notes: Vec<(String, String)> = stmt
+        .query_map(params!["%[%]%"], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    for (note_id, content) in notes {
+        for task in extract_tasks(&content) {
+            let tags = serde_json::to_string(&task.tags).map_err(|e| AppError::validation(e.to_string()))?;
+            conn.execute(
+                "INSERT OR REPLACE INTO tasks (note_id, line, text, done, due_date, priority, tags) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![note_id, task.line as i64, task.text, task.done, task.due_date, task.priority, tags],
+            )?;
+        }
+    }
+    Ok(())
+}
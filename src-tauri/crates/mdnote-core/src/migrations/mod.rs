@@ -0,0 +1,59 @@
+use sha2::{Digest, Sha256};
+use crate::errors::Result;
+use crate::services::database::DatabaseService;
+
+mod backfill_inline_tags;
+mod backfill_note_properties;
+mod backfill_tasks;
+
+pub enum MigrationKind {
+    Sql(&'static str),
+    /// A data transform plain SQL can't express. It runs on the migrating connection
+    /// inside the same transaction that records the migration. It must not call the
+    /// services, which keep changing after the migration ships; keep a private copy of
+    /// whatever it needs in its own module instead.
+    Rust(fn(&DatabaseService) -> Result<()>),
+}
+
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub kind: MigrationKind,
+}
+
+impl Migration {
+    /// SHA-256 of the SQL text. Rust migrations hash only their version and name, so
+    /// renaming one is detected but edits to its body are not: once shipped, a Rust
+    /// migration's module is frozen like a SQL file, and a fix goes in a new version.
+    pub fn checksum(&self) -> String {
+        let mut hasher = Sha256::new();
+        match self.kind {
+            MigrationKind::Sql(sql) => hasher.update(sql.as_bytes()),
+            MigrationKind::Rust(_) => hasher.update(format!("rust:{}:{}", self.version, self.name).as_bytes()),
+        }
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Every schema migration, in ascending version order. Never edit one that has shipped;
+/// add a new version instead, or the checksum check refuses to open existing databases.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "notes_folders", kind: MigrationKind::Sql(include_str!("001_notes_folders.sql")) },
+    Migration { version: 2, name: "tags", kind: MigrationKind::Sql(include_str!("002_tags.sql")) },
+    Migration { version: 3, name: "fts5", kind: MigrationKind::Sql(include_str!("003_fts5.sql")) },
+    Migration { version: 4, name: "backlinks_settings", kind: MigrationKind::Sql(include_str!("004_backlinks_settings.sql")) },
+    Migration { version: 5, name: "tags_color", kind: MigrationKind::Sql(include_str!("005_tags_color.sql")) },
+    Migration { version: 6, name: "tags_nocase", kind: MigrationKind::Sql(include_str!("006_tags_nocase.sql")) },
+    Migration { version: 7, name: "note_tags_source", kind: MigrationKind::Sql(include_str!("007_note_tags_source.sql")) },
+    Migration { version: 8, name: "tags_icon_description", kind: MigrationKind::Sql(include_str!("008_tags_icon_description.sql")) },
+    Migration { version: 9, name: "backfill_inline_tags", kind: MigrationKind::Rust(backfill_inline_tags::run) },
+    Migration { version: 10, name: "note_encryption", kind: MigrationKind::Sql(include_str!("010_note_encryption.sql")) },
+    Migration { version: 11, name: "undo_log", kind: MigrationKind::Sql(include_str!("011_undo_log.sql")) },
+    Migration { version: 12, name: "templates", kind: MigrationKind::Sql(include_str!("012_templates.sql")) },
+    Migration { version: 13, name: "periodic_notes", kind: MigrationKind::Sql(include_str!("013_periodic_notes.sql")) },
+    Migration { version: 14, name: "tasks", kind: MigrationKind::Sql(include_str!("014_tasks.sql")) },
+    Migration { version: 15, name: "backfill_tasks", kind: MigrationKind::Rust(backfill_tasks::run) },
+    Migration { version: 16, name: "reminders", kind: MigrationKind::Sql(include_str!("016_reminders.sql")) },
+    Migration { version: 17, name: "note_properties", kind: MigrationKind::Sql(include_str!("017_note_properties.sql")) },
+    Migration { version: 18, name: "backfill_note_properties", kind: MigrationKind::Rust(backfill_note_properties::run) },
+];
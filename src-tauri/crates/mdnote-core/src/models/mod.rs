@@ -0,0 +1,9 @@
+pub mod note;
+pub mod folder;
+pub mod tag;
+pub mod settings;
+pub mod vault;
+pub mod template;
+pub mod task;
+pub mod reminder;
+pub mod property;
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use crate::models::property::NoteProperty;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Note {
@@ -8,6 +9,12 @@ pub struct Note {
     pub content: String,
     pub created_at: i64,
     pub updated_at: i64,
+    pub encrypted: bool,
+    /// True for an encrypted note whose key is not unlocked this session; `content` is empty.
+    pub locked: bool,
+    /// Parsed from the YAML front matter of `content`, in order.
+    #[serde(default)]
+    pub properties: Vec<NoteProperty>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,6 +22,8 @@ pub struct NoteSummary {
     pub id: String,
     pub folder_id: Option<String>,
     pub title: String,
+    /// Always empty for encrypted notes.
     pub excerpt: String,
     pub updated_at: i64,
+    pub encrypted: bool,
 }
@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use crate::errors::AppError;
+
+/// A front matter value, typed by how it is written: quoted values are always text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum PropertyValue {
+    Text(String),
+    Number(f64),
+    /// `YYYY-MM-DD`.
+    Date(String),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+impl PropertyValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            PropertyValue::Text(_) => "text",
+            PropertyValue::Number(_) => "number",
+            PropertyValue::Date(_) => "date",
+            PropertyValue::Bool(_) => "bool",
+            PropertyValue::List(_) => "list",
+        }
+    }
+}
+
+/// One top-level key of a note's YAML front matter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoteProperty {
+    pub key: String,
+    #[serde(flatten)]
+    pub value: PropertyValue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyOp {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    /// Substring of a text value, or of an item of a list.
+    Contains,
+    /// The note has the property, whatever its value.
+    Exists,
+}
+
+/// A condition on a note property, written like `status = draft`, `priority >= 2`,
+/// `aliases ~ roadmap` or just `author`. Keys and text compare case-insensitively, and
+/// `=` matches any item of a list.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct PropertyFilter {
+    pub key: String,
+    pub op: PropertyOp,
+    pub value: String,
+}
+
+impl std::str::FromStr for PropertyFilter {
+    type Err = AppError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let expression = expression.trim();
+        let split = expression.find(['=', '!', '<', '>', '~']);
+        let (key, rest) = match split {
+            Some(at) => expression.split_at(at),
+            None => (expression, ""),
+        };
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(AppError::invalid("filter", format!("{} is not a property filter like `status = draft`", expression)));
+        }
+
+        let (op, value) = [
+            ("!=", PropertyOp::Ne),
+            ("<=", PropertyOp::Lte),
+            (">=", PropertyOp::Gte),
+            ("=", PropertyOp::Eq),
+            ("<", PropertyOp::Lt),
+            (">", PropertyOp::Gt),
+            ("~", PropertyOp::Contains),
+        ]
+        .into_iter()
+        .find_map(|(token, op)| rest.strip_prefix(token).map(|value| (op, value)))
+        .unwrap_or((PropertyOp::Exists, rest));
+        if op == PropertyOp::Exists && !value.is_empty() {
+            return Err(AppError::invalid("filter", format!("{} is not a property filter like `status = draft`", expression)));
+        }
+
+        let value = value.trim();
+        let unquoted = ['"', '\'']
+            .into_iter()
+            .find_map(|q| value.strip_prefix(q).and_then(|v| v.strip_suffix(q)))
+            .unwrap_or(value);
+        Ok(PropertyFilter { key: key.to_string(), op, value: unquoted.to_string() })
+    }
+}
+
+impl TryFrom<String> for PropertyFilter {
+    type Error = AppError;
+
+    fn try_from(expression: String) -> Result<Self, Self::Error> {
+        expression.parse()
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PropertySort {
+    pub key: String,
+    pub descending: bool,
+}
+
+/// Property conditions and ordering for `list_notes` and search. Notes without the sort
+/// property come last.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PropertyQuery {
+    /// All of them must match.
+    pub filters: Vec<PropertyFilter>,
+    pub sort: Option<PropertySort>,
+}
+
+impl PropertyQuery {
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty() && self.sort.is_none()
+    }
+}
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Reminder {
+    pub id: String,
+    pub note_id: String,
+    pub note_title: String,
+    /// Set when the reminder is for the task on this line of the note. It moves with the
+    /// task when lines above it are added or removed.
+    pub task_line: Option<usize>,
+    /// The task's text, while that line still holds a task.
+    pub task_text: Option<String>,
+    pub message: Option<String>,
+    /// Milliseconds since the epoch.
+    pub remind_at: i64,
+    /// When it fired; `None` until then, and again after a snooze.
+    pub fired_at: Option<i64>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ReminderDraft {
+    pub note_id: String,
+    pub task_line: Option<usize>,
+    pub message: Option<String>,
+    pub remind_at: i64,
+}
+
+/// A reminder that just fired.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DueReminder {
+    #[serde(flatten)]
+    pub reminder: Reminder,
+    /// It was due well before it fired, usually because the app was closed.
+    pub missed: bool,
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: String,
+    pub font_size: i32,
+    pub font_family: String,
+    pub auto_save_delay: i32,
+    pub spell_check: bool,
+    pub backup_enabled: bool,
+    pub backup_interval_hours: i32,
+    /// Number of automatic backups kept; older ones are deleted.
+    pub backup_retention: i32,
+    /// Serve the local HTTP API on 127.0.0.1. Saving the settings starts or stops it.
+    pub api_enabled: bool,
+    pub api_port: u16,
+    /// Bearer token API requests must carry. Only changed through
+    /// `SettingsService::regenerate_api_token`, never by `update_settings`.
+    pub api_token: Option<String>,
+    /// Browser origins allowed to call the API; requests from any other origin are refused.
+    pub api_allowed_origins: Vec<String>,
+    /// Where daily, weekly and monthly notes go and how they are named.
+    pub periodic_notes: PeriodicNotesSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: "system".to_string(),
+            font_size: 16,
+            font_family: "Inter".to_string(),
+            auto_save_delay: 500,
+            spell_check: true,
+            backup_enabled: true,
+            backup_interval_hours: 24,
+            backup_retention: 7,
+            api_enabled: false,
+            api_port: 27_124,
+            api_token: None,
+            api_allowed_origins: Vec::new(),
+            periodic_notes: PeriodicNotesSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PeriodicNotesSettings {
+    pub daily: PeriodicNoteSettings,
+    pub weekly: PeriodicNoteSettings,
+    pub monthly: PeriodicNoteSettings,
+}
+
+impl Default for PeriodicNotesSettings {
+    fn default() -> Self {
+        Self {
+            daily: PeriodicNoteSettings::with_title_format("%Y-%m-%d"),
+            weekly: PeriodicNoteSettings::with_title_format("%G-W%V"),
+            monthly: PeriodicNoteSettings::with_title_format("%Y-%m"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PeriodicNoteSettings {
+    /// Folder new notes are created in; the root when unset.
+    pub folder_id: Option<String>,
+    /// chrono `strftime` format applied to the period's first day, e.g. `%Y-%m-%d`.
+    pub title_format: String,
+    /// Template new notes are created from, with `{{date}}` set to the period's first day.
+    pub template_id: Option<String>,
+}
+
+impl PeriodicNoteSettings {
+    fn with_title_format(format: &str) -> Self {
+        Self { title_format: format.to_string(), ..Default::default() }
+    }
+}
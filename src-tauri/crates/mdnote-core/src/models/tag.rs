@@ -5,4 +5,6 @@ pub struct Tag {
     pub id: String,
     pub name: String,
     pub color: String,
+    pub icon: Option<String>,
+    pub description: Option<String>,
 }
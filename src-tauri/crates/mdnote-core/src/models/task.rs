@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    High,
+    Medium,
+    Low,
+}
+
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        }
+    }
+
+    pub fn parse(priority: &str) -> Option<Self> {
+        match priority.to_lowercase().as_str() {
+            "high" => Some(Priority::High),
+            "medium" => Some(Priority::Medium),
+            "low" => Some(Priority::Low),
+            _ => None,
+        }
+    }
+}
+
+/// A `- [ ]` checkbox item found in a note.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Task {
+    pub note_id: String,
+    pub note_title: String,
+    /// 1-based line of the checkbox in the note's content.
+    pub line: usize,
+    /// The item's text without its due date and priority markers.
+    pub text: String,
+    pub done: bool,
+    /// `YYYY-MM-DD`, from `due:2026-03-02` or `📅 2026-03-02`.
+    pub due_date: Option<String>,
+    pub priority: Option<Priority>,
+    /// Hashtags in the item's text, without the `#`.
+    pub tags: Vec<String>,
+}
+
+/// Narrows `list_tasks`; every field left out matches all tasks.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TaskFilter {
+    pub done: Option<bool>,
+    /// Only tasks due on or after this `YYYY-MM-DD` date.
+    pub due_from: Option<String>,
+    /// Only tasks due on or before this `YYYY-MM-DD` date.
+    pub due_to: Option<String>,
+    /// Leave out tasks without a due date.
+    pub has_due_date: Option<bool>,
+    pub priority: Option<Priority>,
+    /// A tag name; tasks tagged with one of its descendants match too.
+    pub tag: Option<String>,
+    pub note_id: Option<String>,
+    pub folder_id: Option<String>,
+}
+
+/// The tasks due on one day, or without a due date when `due_date` is `None`.
+#[derive(Debug, Serialize)]
+pub struct AgendaGroup {
+    pub due_date: Option<String>,
+    pub tasks: Vec<Task>,
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Template {
+    pub id: String,
+    pub name: String,
+    /// Title of the notes it creates; may contain placeholders like the content.
+    pub title: String,
+    pub content: String,
+    /// Where its notes go when the caller doesn't pick a folder.
+    pub folder_id: Option<String>,
+    /// Tag names attached to every note created from it.
+    pub tags: Vec<String>,
+    /// Variables the title and content use besides the built-in ones; notes can only be
+    /// created from it when all of them are given.
+    pub variables: Vec<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// The editable part of a template, used to create one or replace an existing one.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TemplateDraft {
+    pub name: String,
+    pub title: String,
+    pub content: String,
+    pub folder_id: Option<String>,
+    pub tags: Vec<String>,
+}
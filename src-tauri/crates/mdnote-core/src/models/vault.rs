@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Vault {
+    pub id: String,
+    pub name: String,
+    /// Directory holding the vault's `mdnote.db` and its `backups`.
+    pub path: String,
+    pub created_at: i64,
+    pub last_opened_at: Option<i64>,
+}
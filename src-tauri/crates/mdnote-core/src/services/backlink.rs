@@ -1,6 +1,7 @@
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension};
 use crate::models::note::NoteSummary;
 use crate::services::database::DatabaseService;
+use crate::services::events::ChangeEvent;
 use crate::errors::Result;
 use serde::Serialize;
 
@@ -22,12 +23,8 @@ impl<'a> BacklinkService<'a> {
 
     /// Links two notes together. Directional: source mentions target.
     pub fn add_link(&self, source_id: String, target_id: String, context: Option<String>) -> Result<()> {
-        let conn = self.db.connection();
-        conn.execute(
-            "INSERT OR REPLACE INTO backlinks (source_id, target_id, context) VALUES (?, ?, ?)",
-            params![source_id, target_id, context],
-        )?;
-        Ok(())
+        self.insert_link(&source_id, &target_id, context)?;
+        self.record_links(source_id)
     }
 
     /// Removes a link between two notes.
@@ -37,20 +34,76 @@ impl<'a> BacklinkService<'a> {
             "DELETE FROM backlinks WHERE source_id = ? AND target_id = ?",
             params![source_id, target_id],
         )?;
-        Ok(())
+        self.record_links(source_id)
     }
 
     /// Clears allOutgoing links from a note (used before re-scanning content).
     pub fn clear_outgoing_links(&self, source_id: String) -> Result<()> {
+        self.delete_outgoing(&source_id)?;
+        self.record_links(source_id)
+    }
+
+    /// Replaces a note's outgoing links with the `[[note-id]]` references in `content`.
+    pub fn sync_links(&self, source_id: String, content: &str) -> Result<()> {
+        // Simple regex-based discovery for now: [[note-id]] or #tag-like-links
+        // In a real app, we'd use a markdown parser. 
+        // Here we'll look for [[ulid]] patterns.
+        self.delete_outgoing(&source_id)?;
+        // Context is plaintext, so encrypted notes only keep the link itself
+        let encrypted: bool = self.db.connection()
+            .query_row("SELECT encrypted FROM notes WHERE id = ?", params![source_id], |row| row.get(0))
+            .optional()?
+            .unwrap_or(false);
+
+        let re = regex::Regex::new(r"\[\[([0-9A-HJKMNP-TV-Z]{26})\]\]").unwrap();
+        for cap in re.captures_iter(content) {
+            let target_id = cap[1].to_string();
+            // Avoid self-links
+            if target_id != source_id {
+                // Extract a snippet of context (surrounding text), widened to
+                // whole characters so multibyte text never splits
+                let match_pos = cap.get(0).unwrap().start();
+                let start = content.floor_char_boundary(match_pos.saturating_sub(40));
+                let end = content.ceil_char_boundary((match_pos + 66).min(content.len()));
+                let context = (!encrypted).then(|| content[start..end].to_string());
+
+                self.insert_link(&source_id, &target_id, context)?;
+            }
+        }
+
+        self.record_links(source_id)
+    }
+
+    fn insert_link(&self, source_id: &str, target_id: &str, context: Option<String>) -> Result<()> {
+        let conn = self.db.connection();
+        conn.execute(
+            "INSERT OR REPLACE INTO backlinks (source_id, target_id, context) VALUES (?, ?, ?)",
+            params![source_id, target_id, context],
+        )?;
+        Ok(())
+    }
+
+    fn delete_outgoing(&self, source_id: &str) -> Result<()> {
         let conn = self.db.connection();
         conn.execute("DELETE FROM backlinks WHERE source_id = ?", params![source_id])?;
         Ok(())
     }
 
+    /// Records the note's outgoing links as they stand now.
+    fn record_links(&self, source_id: String) -> Result<()> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached("SELECT target_id FROM backlinks WHERE source_id = ? ORDER BY target_id")?;
+        let target_ids = stmt
+            .query_map(params![source_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        self.db.record(ChangeEvent::LinksChanged { source_id, target_ids });
+        Ok(())
+    }
+
     /// Gets all notes that point TO the specified note.
     pub fn get_backlinks(&self, target_id: String) -> Result<Vec<Backlink>> {
         let conn = self.db.connection();
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare_cached(
             "SELECT b.source_id, n.title, b.context 
              FROM backlinks b 
              JOIN notes n ON b.source_id = n.id 
@@ -76,8 +129,8 @@ impl<'a> BacklinkService<'a> {
     /// Gets all notes that the specified note points TO.
     pub fn get_outgoing_links(&self, source_id: String) -> Result<Vec<NoteSummary>> {
         let conn = self.db.connection();
-        let mut stmt = conn.prepare(
-            "SELECT n.id, n.folder_id, n.title, substr(n.content, 1, 200) as excerpt, n.updated_at 
+        let mut stmt = conn.prepare_cached(
+            "SELECT n.id, n.folder_id, n.title, CASE WHEN n.encrypted THEN '' ELSE substr(n.content, 1, 200) END as excerpt, n.updated_at, n.encrypted
              FROM notes n 
              JOIN backlinks b ON n.id = b.target_id 
              WHERE b.source_id = ?"
@@ -90,6 +143,7 @@ impl<'a> BacklinkService<'a> {
                 title: row.get(2)?,
                 excerpt: row.get(3)?,
                 updated_at: row.get(4)?,
+                encrypted: row.get(5)?,
             })
         })?;
 
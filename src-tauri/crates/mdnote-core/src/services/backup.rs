@@ -0,0 +1,367 @@
+use rusqlite::backup::Backup;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use ulid::Ulid;
+use crate::models::settings::Settings;
+use crate::services::database::{latest_schema_version, schema_version, DatabaseService, DbPool, DbState, DB_FILE_NAME, DEFAULT_READER_COUNT};
+use crate::services::encryption::{apply_key, DerivedKey};
+use crate::services::settings::SettingsService;
+use crate::errors::{AppError, Result};
+
+const BACKUP_DIR: &str = "backups";
+const PAGES_PER_STEP: i32 = 256;
+/// How many pre-restore and how many pre-migration snapshots are kept.
+const SAFETY_SNAPSHOT_RETENTION: usize = 5;
+/// How often the scheduler checks whether an automatic backup is due.
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupKind {
+    Manual,
+    Auto,
+    PreRestore,
+    PreMigration,
+}
+
+impl BackupKind {
+    fn prefix(self) -> &'static str {
+        match self {
+            BackupKind::Manual => "manual",
+            BackupKind::Auto => "auto",
+            BackupKind::PreRestore => "pre-restore",
+            BackupKind::PreMigration => "pre-migration",
+        }
+    }
+
+    fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix {
+            "manual" => Some(BackupKind::Manual),
+            "auto" => Some(BackupKind::Auto),
+            "pre-restore" => Some(BackupKind::PreRestore),
+            "pre-migration" => Some(BackupKind::PreMigration),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub id: String,
+    pub kind: BackupKind,
+    pub path: String,
+    pub created_at: i64,
+    pub size_bytes: u64,
+    pub schema_version: i64,
+}
+
+/// Consistent snapshots of the live database, written with SQLite's online backup API
+/// into a `backups` directory next to `mdnote.db`.
+pub struct BackupService<'a> {
+    db: &'a DatabaseService,
+}
+
+impl<'a> BackupService<'a> {
+    pub fn new(db: &'a DatabaseService) -> Self {
+        Self { db }
+    }
+
+    pub fn backup_dir(&self) -> PathBuf {
+        self.db
+            .path()
+            .parent()
+            .map(|dir| dir.join(BACKUP_DIR))
+            .unwrap_or_else(|| PathBuf::from(BACKUP_DIR))
+    }
+
+    /// Writes a snapshot to `path`, or into the backup directory when no path is given.
+    pub fn create_backup(&self, path: Option<String>) -> Result<BackupInfo> {
+        self.write_snapshot(BackupKind::Manual, path.map(PathBuf::from))
+    }
+
+    /// Snapshot taken automatically before pending schema migrations are applied.
+    pub fn create_pre_migration_backup(&self) -> Result<BackupInfo> {
+        let created = self.write_snapshot(BackupKind::PreMigration, None)?;
+        self.prune_backups(BackupKind::PreMigration, SAFETY_SNAPSHOT_RETENTION, None)?;
+        Ok(created)
+    }
+
+    /// Backups in the backup directory, newest first.
+    pub fn list_backups(&self) -> Result<Vec<BackupInfo>> {
+        if self.db.is_in_memory() {
+            return Ok(Vec::new());
+        }
+        list_backups_in(&self.backup_dir(), self.db.key())
+    }
+
+    /// Finds backup `id` and checks it can be restored into this binary's schema.
+    /// A safety snapshot of the current database is taken before the caller restores.
+    pub fn prepare_restore(&self, id: &str) -> Result<BackupInfo> {
+        let info = find_restorable_backup(&self.list_backups()?, id, self.db.key())?;
+        self.write_snapshot(BackupKind::PreRestore, None)?;
+        // Restoring an old pre-restore snapshot must not prune the very file being restored
+        self.prune_backups(BackupKind::PreRestore, SAFETY_SNAPSHOT_RETENTION, Some(&info.id))?;
+        Ok(info)
+    }
+
+    /// Takes an automatic backup when enabled and the newest one is older than the
+    /// configured interval, then prunes automatic backups beyond the retention count.
+    pub fn run_scheduled_backup(&self, settings: &Settings) -> Result<Option<BackupInfo>> {
+        if !settings.backup_enabled {
+            return Ok(None);
+        }
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let interval_ms = i64::from(settings.backup_interval_hours.max(1)) * 60 * 60 * 1000;
+        let backups = self.list_backups()?;
+        let last_auto = backups.iter().filter(|b| b.kind == BackupKind::Auto).map(|b| b.created_at).max();
+
+        let created = match last_auto {
+            Some(last) if now - last < interval_ms => None,
+            _ => Some(self.write_snapshot(BackupKind::Auto, None)?),
+        };
+
+        self.prune_backups(BackupKind::Auto, settings.backup_retention.max(1) as usize, None)?;
+        Ok(created)
+    }
+
+    /// Deletes the backups of `kind` beyond the newest `keep`, sparing backup `spare`.
+    fn prune_backups(&self, kind: BackupKind, keep: usize, spare: Option<&str>) -> Result<()> {
+        let stale = self
+            .list_backups()?
+            .into_iter()
+            .filter(|b| b.kind == kind)
+            .skip(keep)
+            .filter(|b| Some(b.id.as_str()) != spare);
+
+        for backup in stale {
+            fs::remove_file(&backup.path)?;
+        }
+        Ok(())
+    }
+
+    fn write_snapshot(&self, kind: BackupKind, path: Option<PathBuf>) -> Result<BackupInfo> {
+        if self.db.is_in_memory() {
+            return Err(AppError::Unsupported("In-memory databases have no backups".to_string()));
+        }
+        let id = Ulid::new();
+        let path = match path {
+            Some(p) => p,
+            None => {
+                let dir = self.backup_dir();
+                fs::create_dir_all(&dir)?;
+                dir.join(format!("{}-{}.db", kind.prefix(), id))
+            }
+        };
+        if path.exists() {
+            return Err(AppError::AlreadyExists(format!("Backup target {:?} already exists", path)));
+        }
+
+        let mut target = Connection::open(&path)?;
+        // Snapshots of an encrypted vault are encrypted with the same key
+        if let Some(key) = self.db.key() {
+            apply_key(&target, key)?;
+        }
+        {
+            let backup = Backup::new(self.db.connection(), &mut target)?;
+            backup.run_to_completion(PAGES_PER_STEP, Duration::from_millis(0), None)?;
+        }
+        // A standalone file should not depend on -wal/-shm companions
+        let _mode: String = target.query_row("PRAGMA journal_mode=DELETE", [], |row| row.get(0))?;
+        drop(target);
+
+        describe_snapshot(&path, kind, id.to_string(), self.db.key())
+    }
+}
+
+/// Starts a background thread that takes automatic backups of the open vault as configured
+/// in its `Settings`. Snapshots are read through a reader connection, so writes are never blocked.
+pub fn spawn_backup_scheduler(state: DbState) {
+    std::thread::spawn(move || loop {
+        let result = state.pool().and_then(|pool| {
+            pool.with_reader(|db| {
+                let settings = SettingsService::new(db).get_settings()?;
+                BackupService::new(db).run_scheduled_backup(&settings)
+            })
+        });
+
+        match result {
+            Ok(Some(backup)) => tracing::info!("Automatic backup written to {}", backup.path),
+            Ok(None) | Err(AppError::NotFound(_)) | Err(AppError::Locked(_)) => {}
+            Err(e) => tracing::warn!("Automatic backup failed: {}", e),
+        }
+        std::thread::sleep(SCHEDULE_CHECK_INTERVAL);
+    });
+}
+
+/// Backups of the vault in `vault_dir`, newest first, read without opening its database.
+/// Meant for a vault whose database is too damaged to open.
+pub fn list_vault_backups(vault_dir: &Path, key: Option<&DerivedKey>) -> Result<Vec<BackupInfo>> {
+    list_backups_in(&vault_dir.join(BACKUP_DIR), key)
+}
+
+/// Restores backup `id` of the open vault when its database failed to open, then opens it.
+/// Returns `None`, touching nothing, when the vault's database is open as usual.
+pub fn restore_failed_vault(state: &DbState, id: &str) -> Result<Option<BackupInfo>> {
+    let (Some(dir), Some(vault_id)) = (state.failed_vault_dir()?, state.current_vault_id()?) else {
+        return Ok(None);
+    };
+    let info = restore_vault_backup(&dir, id, None)?;
+    state.open(vault_id, DbPool::new(dir, DEFAULT_READER_COUNT)?)?;
+    Ok(Some(info))
+}
+
+/// Replaces the database of the vault in `vault_dir`, which must not be open, with backup
+/// `id`. The damaged file is kept beside it as `mdnote.db.damaged-<ulid>` rather than deleted.
+fn restore_vault_backup(vault_dir: &Path, id: &str, key: Option<&DerivedKey>) -> Result<BackupInfo> {
+    let info = find_restorable_backup(&list_vault_backups(vault_dir, key)?, id, key)?;
+
+    let db_path = vault_dir.join(DB_FILE_NAME);
+    if db_path.exists() {
+        let damaged = format!("{}.damaged-{}", DB_FILE_NAME, Ulid::new());
+        // The companions belong to the damaged file and would be replayed into the backup
+        for suffix in ["", "-wal", "-shm"] {
+            let file = vault_dir.join(format!("{}{}", DB_FILE_NAME, suffix));
+            if file.exists() {
+                fs::rename(&file, vault_dir.join(format!("{}{}", damaged, suffix)))?;
+            }
+        }
+    }
+    fs::copy(&info.path, &db_path)?;
+    Ok(info)
+}
+
+fn list_backups_in(dir: &Path, key: Option<&DerivedKey>) -> Result<Vec<BackupInfo>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some((kind, id)) = parse_backup_file_name(&path) else { continue };
+        match describe_snapshot(&path, kind, id, key) {
+            Ok(info) => backups.push(info),
+            Err(e) => tracing::warn!("Skipping unreadable backup {:?}: {}", path, e),
+        }
+    }
+
+    backups.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(backups)
+}
+
+/// Picks backup `id` out of `backups` and checks it can be restored into this binary's schema.
+fn find_restorable_backup(backups: &[BackupInfo], id: &str, key: Option<&DerivedKey>) -> Result<BackupInfo> {
+    let info = backups
+        .iter()
+        .find(|b| b.id == id)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("Backup with id {} not found", id)))?;
+
+    let latest = latest_schema_version();
+    if info.schema_version > latest {
+        return Err(AppError::Unsupported(format!(
+            "Backup {} has schema version {}, but this version of the app only supports up to {}",
+            id, info.schema_version, latest
+        )));
+    }
+
+    // FTS5 only validates its index through a writable handle, which may write to the
+    // file or leave a journal beside it, so the checks run on a scratch copy
+    let scratch = ScratchCopy::of(Path::new(&info.path))?;
+    let snapshot = Connection::open(&scratch.0)?;
+    if let Some(key) = key {
+        apply_key(&snapshot, key)?;
+    }
+    let check: String = snapshot.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+    if check != "ok" {
+        return Err(AppError::corrupt(format!("Backup {} failed its integrity check: {}", id, check)));
+    }
+    if has_table(&snapshot, "notes_fts")? {
+        snapshot
+            .execute("INSERT INTO notes_fts(notes_fts) VALUES ('integrity-check')", [])
+            .map_err(|e| AppError::corrupt(format!("Backup {} has a damaged search index: {}", id, e)))?;
+    }
+    Ok(info)
+}
+
+/// A copy of a backup beside it, removed with its journal files on drop. Its name doesn't
+/// end in `.db`, so it never lists as a backup even if a crash leaves it behind.
+struct ScratchCopy(PathBuf);
+
+impl ScratchCopy {
+    fn of(path: &Path) -> Result<Self> {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".check");
+        let copy = Self(PathBuf::from(name));
+        fs::copy(path, &copy.0)?;
+        Ok(copy)
+    }
+}
+
+impl Drop for ScratchCopy {
+    fn drop(&mut self) {
+        for suffix in ["", "-journal", "-wal", "-shm"] {
+            let mut name = self.0.as_os_str().to_owned();
+            name.push(suffix);
+            let _ = fs::remove_file(PathBuf::from(name));
+        }
+    }
+}
+
+fn parse_backup_file_name(path: &Path) -> Option<(BackupKind, String)> {
+    if path.extension()? != "db" {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    let (prefix, id) = stem.rsplit_once('-')?;
+    let kind = BackupKind::from_prefix(prefix)?;
+    Ulid::from_string(id).ok()?;
+    Some((kind, id.to_string()))
+}
+
+/// Paths of every backup file in the backup directory of the vault at `vault_dir`.
+pub fn backup_files(vault_dir: &Path) -> Result<Vec<PathBuf>> {
+    let dir = vault_dir.join(BACKUP_DIR);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if parse_backup_file_name(&path).is_some() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn describe_snapshot(path: &Path, kind: BackupKind, id: String, key: Option<&DerivedKey>) -> Result<BackupInfo> {
+    let size_bytes = fs::metadata(path)?.len();
+    let snapshot = DatabaseService::open_read_only(path, key.cloned())?;
+    let conn = snapshot.connection();
+    let created_at = Ulid::from_string(&id).map(|u| u.timestamp_ms() as i64).unwrap_or(0);
+
+    Ok(BackupInfo {
+        id,
+        kind,
+        path: path.to_string_lossy().into_owned(),
+        created_at,
+        size_bytes,
+        schema_version: schema_version(conn)?,
+    })
+}
+
+fn has_table(conn: &Connection, name: &str) -> Result<bool> {
+    let found = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?",
+        [name],
+        |row| row.get(0),
+    )?;
+    Ok(found)
+}
@@ -0,0 +1,543 @@
+use rusqlite::{params, Connection, OpenFlags};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use crate::errors::{AppError, Result};
+use crate::migrations::{MigrationKind, MIGRATIONS};
+use crate::services::backup::BackupService;
+use crate::services::encryption::{apply_key, DerivedKey};
+use crate::services::events::{ChangeBus, ChangeEvent};
+use crate::services::undo::{install_undo_triggers, UndoService};
+use tracing::{debug, error, info};
+
+/// How long a connection waits on a lock held by another connection (or process) before giving up.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+pub const DEFAULT_READER_COUNT: usize = 4;
+pub const DB_FILE_NAME: &str = "mdnote.db";
+
+/// The schema version this binary migrates databases to.
+pub fn latest_schema_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// The highest migration recorded in `conn`'s `_migrations` table.
+pub fn schema_version(conn: &Connection) -> Result<i64> {
+    Ok(conn.query_row("SELECT COALESCE(MAX(version), 0) FROM _migrations", [], |row| row.get(0))?)
+}
+
+pub struct DatabaseService {
+    conn: Connection,
+    path: PathBuf,
+    key: Option<DerivedKey>,
+    /// `None` until `track_changes`.
+    changes: Option<Arc<Mutex<ChangeLog>>>,
+}
+
+/// Change events recorded on a connection. Events recorded inside a transaction wait in
+/// `pending` until the commit hook moves them to `committed`, or the rollback hook drops them.
+#[derive(Default)]
+struct ChangeLog {
+    pending: Vec<ChangeEvent>,
+    committed: Vec<ChangeEvent>,
+}
+
+impl DatabaseService {
+    pub fn new(app_data_dir: PathBuf) -> Result<Self> {
+        Self::open(app_data_dir, None)
+    }
+
+    /// Opens (creating if needed) and migrates the database in `app_data_dir`,
+    /// keying the connection first when the vault is encrypted.
+    pub fn open(app_data_dir: PathBuf, key: Option<DerivedKey>) -> Result<Self> {
+        debug!("DatabaseService::open with dir: {:?}", app_data_dir);
+        if !app_data_dir.exists() {
+            debug!("Creating app data directory...");
+            fs::create_dir_all(&app_data_dir).map_err(|e| {
+                error!("Failed to create app data dir: {}", e);
+                AppError::from(e)
+            })?;
+        }
+        
+        let db_path = app_data_dir.join(DB_FILE_NAME);
+        debug!("Opening database at: {:?}", db_path);
+        let conn = Connection::open(&db_path).map_err(|e| {
+            error!("Failed to open database: {}", e);
+            e
+        })?;
+        if let Some(key) = &key {
+            Self::unlock(&conn, key)?;
+        }
+        
+        Self::startup_check(&conn).map_err(|e| {
+            error!("Startup integrity check failed: {}", e);
+            e
+        })?;
+
+        debug!("Database opened. Enabling WAL and Foreign Keys...");
+        // Enable WAL mode
+        let _mode: String = conn.query_row("PRAGMA journal_mode=WAL", [], |row| row.get(0)).map_err(|e| {
+            error!("WAL mode failed: {}", e);
+            e
+        })?;
+        conn.execute("PRAGMA foreign_keys=ON", []).map_err(|e| {
+            error!("Foreign keys failed: {}", e);
+            e
+        })?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+        
+        let mut service = Self { conn, path: db_path, key, changes: None };
+        debug!("Running migrations...");
+        service.run_migrations().map_err(|e| {
+            error!("Migrations failed: {}", e);
+            e
+        })?;
+        install_undo_triggers(&service)?;
+        
+        debug!("DatabaseService initialized successfully.");
+        Ok(service)
+    }
+
+    /// A fresh, fully migrated database that lives only as long as the service.
+    /// Meant for tests and tools that don't need a vault on disk; backups are unavailable.
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute("PRAGMA foreign_keys=ON", [])?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+
+        let mut service = Self { conn, path: PathBuf::from(":memory:"), key: None, changes: None };
+        service.run_migrations()?;
+        install_undo_triggers(&service)?;
+        Ok(service)
+    }
+
+    /// Keys `conn` and proves the key by reading the schema: SQLCipher only notices a
+    /// wrong key when the first page is decrypted.
+    fn unlock(conn: &Connection, key: &DerivedKey) -> Result<()> {
+        apply_key(conn, key)?;
+        conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))
+            .map_err(|e| match e.sqlite_error_code() {
+                Some(rusqlite::ErrorCode::NotADatabase) => AppError::Locked("Incorrect passphrase".to_string()),
+                _ => AppError::from(e),
+            })?;
+        Ok(())
+    }
+
+    /// A cheap health check before anything touches the schema: reads the header and the
+    /// schema table only, so SQLite reports a file that is not a database or has a damaged
+    /// schema as `AppError::Corrupt`. Walking every page is left to `integrity_check`.
+    fn startup_check(conn: &Connection) -> Result<()> {
+        let _version: i64 = conn.query_row("PRAGMA schema_version", [], |row| row.get(0))?;
+        let _objects: i64 = conn.query_row("SELECT COUNT(*) FROM sqlite_master WHERE sql IS NOT NULL", [], |row| row.get(0))?;
+        Ok(())
+    }
+
+    fn run_migrations(&mut self) -> Result<()> {
+        debug_assert!(
+            MIGRATIONS.windows(2).all(|w| w[0].version < w[1].version),
+            "migrations must be listed in ascending version order"
+        );
+
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS _migrations (version INTEGER PRIMARY KEY, applied_at INTEGER NOT NULL, name TEXT, checksum TEXT)",
+            [],
+        )?;
+        // Databases created before checksums were recorded lack the extra columns
+        let has_checksum: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('_migrations') WHERE name = 'checksum'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_checksum {
+            self.conn.execute_batch(
+                "ALTER TABLE _migrations ADD COLUMN name TEXT;
+                 ALTER TABLE _migrations ADD COLUMN checksum TEXT;",
+            )?;
+        }
+
+        let applied: Vec<(i64, Option<String>)> = {
+            let mut stmt = self.conn.prepare("SELECT version, checksum FROM _migrations ORDER BY version")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        let latest = latest_schema_version();
+        if let Some((newest, _)) = applied.iter().find(|(version, _)| *version > latest) {
+            return Err(AppError::Unsupported(format!(
+                "Database schema version {} is newer than this version of MDNote supports ({}). Please update the app.",
+                newest, latest
+            )));
+        }
+
+        for (version, recorded) in &applied {
+            let Some(migration) = MIGRATIONS.iter().find(|m| m.version == *version) else { continue };
+            let checksum = migration.checksum();
+            match recorded {
+                Some(recorded) if *recorded != checksum => {
+                    return Err(AppError::corrupt(format!(
+                        "Migration {} ({}) was changed after it was applied",
+                        version, migration.name
+                    )));
+                }
+                Some(_) => {}
+                // Recorded before checksums existed: trust what is there now
+                None => {
+                    self.conn.execute(
+                        "UPDATE _migrations SET name = ?, checksum = ? WHERE version = ?",
+                        params![migration.name, checksum, version],
+                    )?;
+                }
+            }
+        }
+
+        let pending: Vec<_> = MIGRATIONS
+            .iter()
+            .filter(|m| !applied.iter().any(|(version, _)| *version == m.version))
+            .collect();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        if !applied.is_empty() {
+            let backup = BackupService::new(self).create_pre_migration_backup()?;
+            info!("Backed up database to {} before migrating", backup.path);
+        }
+
+        for migration in pending {
+            info!("Applying migration version {} ({})", migration.version, migration.name);
+            let tx = self.conn.unchecked_transaction()?;
+
+            match migration.kind {
+                MigrationKind::Sql(sql) => tx.execute_batch(sql)?,
+                MigrationKind::Rust(transform) => transform(self)?,
+            }
+
+            tx.execute(
+                "INSERT INTO _migrations (version, applied_at, name, checksum) VALUES (?, ?, ?, ?)",
+                params![migration.version, chrono::Utc::now().timestamp_millis(), migration.name, migration.checksum()],
+            )?;
+
+            tx.commit()?;
+        }
+
+        Ok(())
+    }
+
+    /// Opens a read-only connection to an already migrated database.
+    pub fn open_read_only(db_path: &Path, key: Option<DerivedKey>) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX | OpenFlags::SQLITE_OPEN_URI,
+        )?;
+        if let Some(key) = &key {
+            Self::unlock(&conn, key)?;
+        }
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+        Ok(Self { conn, path: db_path.to_path_buf(), key, changes: None })
+    }
+
+    /// Replaces the whole database with the snapshot at `snapshot`, then migrates it
+    /// up to the current schema. Readers see the restored data on their next query.
+    /// Snapshots of an encrypted vault are encrypted with the same key.
+    pub fn restore_from(&mut self, snapshot: &Path) -> Result<()> {
+        let source = Connection::open_with_flags(snapshot, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        if let Some(key) = &self.key {
+            Self::unlock(&source, key)?;
+        }
+        {
+            let backup = rusqlite::backup::Backup::new(&source, &mut self.conn)?;
+            backup.run_to_completion(256, Duration::from_millis(0), None)?;
+        }
+        // The snapshot's header carries its own journal mode
+        let _mode: String = self.conn.query_row("PRAGMA journal_mode=WAL", [], |row| row.get(0))?;
+        self.conn.flush_prepared_statement_cache();
+        self.run_migrations()?;
+        // The restored tables may have other columns than the ones the triggers captured,
+        // and an action open before the restore refers to history the snapshot lacks
+        install_undo_triggers(self)?;
+        UndoService::new(self).abandon_recording()
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Runs `f` in a transaction, or as part of the one already open on this connection,
+    /// so a write that calls another commits or rolls back as one.
+    pub fn transaction<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        if !self.conn.is_autocommit() {
+            return f();
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        let value = f()?;
+        tx.commit()?;
+        Ok(value)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_in_memory(&self) -> bool {
+        self.path == Path::new(":memory:")
+    }
+
+    /// The key this connection was opened with, if the vault is encrypted.
+    pub fn key(&self) -> Option<&DerivedKey> {
+        self.key.as_ref()
+    }
+
+    /// Starts keeping the change events services record on this connection.
+    pub fn track_changes(&mut self) {
+        if self.changes.is_some() {
+            return;
+        }
+        let log = Arc::new(Mutex::new(ChangeLog::default()));
+
+        let on_commit = log.clone();
+        self.conn.commit_hook(Some(move || {
+            if let Ok(mut log) = on_commit.lock() {
+                let pending = std::mem::take(&mut log.pending);
+                log.committed.extend(pending);
+            }
+            // false lets the commit go ahead
+            false
+        }));
+        let on_rollback = log.clone();
+        self.conn.rollback_hook(Some(move || {
+            if let Ok(mut log) = on_rollback.lock() {
+                log.pending.clear();
+            }
+        }));
+        self.changes = Some(log);
+    }
+
+    /// The change events committed since the last call.
+    pub fn take_changes(&self) -> Vec<ChangeEvent> {
+        self.changes
+            .as_ref()
+            .and_then(|log| log.lock().ok())
+            .map(|mut log| std::mem::take(&mut log.committed))
+            .unwrap_or_default()
+    }
+
+    /// Notes a change the last statement made. Inside a transaction it only counts once
+    /// the transaction commits.
+    pub(crate) fn record(&self, event: ChangeEvent) {
+        let Some(Ok(mut log)) = self.changes.as_ref().map(|log| log.lock()) else { return };
+        if self.conn.is_autocommit() {
+            log.committed.push(event);
+        } else {
+            log.pending.push(event);
+        }
+    }
+}
+
+/// One writer plus a set of read-only connections to the same WAL database,
+/// so reads keep going while a write is in flight.
+pub struct DbPool {
+    writer: Mutex<DatabaseService>,
+    readers: Vec<Mutex<DatabaseService>>,
+    next_reader: AtomicUsize,
+    changes: ChangeBus,
+}
+
+impl DbPool {
+    pub fn new(app_data_dir: PathBuf, reader_count: usize) -> Result<Self> {
+        Self::open(app_data_dir, reader_count, None)
+    }
+
+    /// Like `new`, with every connection keyed for an encrypted vault.
+    pub fn open(app_data_dir: PathBuf, reader_count: usize, key: Option<DerivedKey>) -> Result<Self> {
+        let db_path = app_data_dir.join(DB_FILE_NAME);
+        // The writer creates the file and runs migrations before any reader opens it
+        let mut writer = DatabaseService::open(app_data_dir, key.clone())?;
+        writer.track_changes();
+        UndoService::new(&writer).abandon_recording()?;
+
+        let mut readers = Vec::with_capacity(reader_count.max(1));
+        for _ in 0..reader_count.max(1) {
+            readers.push(Mutex::new(DatabaseService::open_read_only(&db_path, key.clone())?));
+        }
+
+        Ok(Self {
+            writer: Mutex::new(writer),
+            readers,
+            next_reader: AtomicUsize::new(0),
+            changes: ChangeBus::new(),
+        })
+    }
+
+    /// Closes the readers and hands back the writer, for callers that need to be the
+    /// pool's only connection to the file.
+    pub fn into_writer(self) -> Result<DatabaseService> {
+        self.writer.into_inner().map_err(|e| AppError::lock(e.to_string()))
+    }
+
+    /// Runs `f` on the writer connection. Writes are serialized. Change events from the
+    /// transactions `f` committed are published once the writer is free again, even if
+    /// `f` fails afterwards, since those changes are already on disk.
+    pub fn with_writer<T>(&self, f: impl FnOnce(&mut DatabaseService) -> Result<T>) -> Result<T> {
+        let mut db = self.writer.lock().map_err(|e| AppError::lock(e.to_string()))?;
+        let result = f(&mut db);
+        let changes = db.take_changes();
+        drop(db);
+
+        self.changes.publish(changes);
+        result
+    }
+
+    /// Runs `f` on the first idle reader, or waits for one in round-robin order.
+    pub fn with_reader<T>(&self, f: impl FnOnce(&DatabaseService) -> Result<T>) -> Result<T> {
+        for reader in &self.readers {
+            if let Ok(db) = reader.try_lock() {
+                return f(&db);
+            }
+        }
+
+        let index = self.next_reader.fetch_add(1, Ordering::Relaxed) % self.readers.len();
+        let db = self.readers[index].lock().map_err(|e| AppError::lock(e.to_string()))?;
+        f(&db)
+    }
+}
+
+struct OpenVault {
+    id: String,
+    /// `None` while an encrypted vault is locked or its database failed to open.
+    pool: Option<Arc<DbPool>>,
+    failure: Option<OpenFailure>,
+}
+
+/// A vault whose database could not be opened, kept current so its backups stay reachable.
+struct OpenFailure {
+    dir: PathBuf,
+    message: String,
+}
+
+// The database of the currently open vault, shared by every caller. The pool behind it is
+// swapped when another vault is opened.
+#[derive(Clone, Default)]
+pub struct DbState {
+    current: Arc<RwLock<Option<OpenVault>>>,
+    changes: ChangeBus,
+}
+
+impl DbState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes `pool` the database every command acts on, closing the previous vault's pool
+    /// once in-flight queries holding it have finished.
+    pub fn open(&self, vault_id: String, mut pool: DbPool) -> Result<()> {
+        pool.changes = self.changes.clone();
+        let mut current = self.current.write().map_err(|e| AppError::lock(e.to_string()))?;
+        *current = Some(OpenVault { id: vault_id, pool: Some(Arc::new(pool)), failure: None });
+        Ok(())
+    }
+
+    /// Makes an encrypted vault current without opening its database. Commands fail
+    /// with `AppError::Locked` until it is unlocked with `open`.
+    pub fn open_locked(&self, vault_id: String) -> Result<()> {
+        let mut current = self.current.write().map_err(|e| AppError::lock(e.to_string()))?;
+        *current = Some(OpenVault { id: vault_id, pool: None, failure: None });
+        Ok(())
+    }
+
+    /// Makes a vault whose database in `dir` failed to open current anyway. Commands fail
+    /// with `AppError::Corrupt` until a backup is restored or the vault is opened again.
+    pub fn open_failed(&self, vault_id: String, dir: PathBuf, error: &AppError) -> Result<()> {
+        let mut current = self.current.write().map_err(|e| AppError::lock(e.to_string()))?;
+        let failure = OpenFailure { dir, message: error.message().to_string() };
+        *current = Some(OpenVault { id: vault_id, pool: None, failure: Some(failure) });
+        Ok(())
+    }
+
+    /// The directory of the open vault if its database failed to open.
+    pub fn failed_vault_dir(&self) -> Result<Option<PathBuf>> {
+        let current = self.current.read().map_err(|e| AppError::lock(e.to_string()))?;
+        Ok(current.as_ref().and_then(|vault| vault.failure.as_ref()).map(|f| f.dir.clone()))
+    }
+
+    /// Drops the open vault's pool, keeping the vault current but locked.
+    pub fn lock(&self) -> Result<()> {
+        let mut current = self.current.write().map_err(|e| AppError::lock(e.to_string()))?;
+        match current.as_mut() {
+            Some(vault) => {
+                vault.pool = None;
+                Ok(())
+            }
+            None => Err(AppError::NotFound("No vault is open".to_string())),
+        }
+    }
+
+    pub fn is_locked(&self) -> Result<bool> {
+        let current = self.current.read().map_err(|e| AppError::lock(e.to_string()))?;
+        Ok(current.as_ref().is_some_and(|vault| vault.pool.is_none() && vault.failure.is_none()))
+    }
+
+    /// Takes the open vault's pool out of service (new commands see it as locked) and
+    /// waits for in-flight queries to release it, so the caller owns every connection.
+    pub fn take_exclusive(&self) -> Result<DbPool> {
+        let mut pool = {
+            let mut current = self.current.write().map_err(|e| AppError::lock(e.to_string()))?;
+            let vault = current.as_mut().ok_or_else(|| AppError::NotFound("No vault is open".to_string()))?;
+            vault.pool.take().ok_or_else(|| AppError::Locked("The vault is locked".to_string()))?
+        };
+
+        let deadline = std::time::Instant::now() + BUSY_TIMEOUT;
+        loop {
+            match Arc::try_unwrap(pool) {
+                Ok(pool) => return Ok(pool),
+                Err(shared) if std::time::Instant::now() < deadline => {
+                    pool = shared;
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Err(shared) => {
+                    // Put it back rather than leave the vault unusable
+                    if let Ok(mut current) = self.current.write() {
+                        if let Some(vault) = current.as_mut() {
+                            vault.pool = Some(shared);
+                        }
+                    }
+                    return Err(AppError::lock("The database is still in use".to_string()));
+                }
+            }
+        }
+    }
+
+    /// Closes the open vault and returns its id.
+    pub fn close(&self) -> Result<Option<String>> {
+        let mut current = self.current.write().map_err(|e| AppError::lock(e.to_string()))?;
+        Ok(current.take().map(|vault| vault.id))
+    }
+
+    /// Where writes through any vault opened here publish their change events.
+    pub fn changes(&self) -> &ChangeBus {
+        &self.changes
+    }
+
+    pub fn current_vault_id(&self) -> Result<Option<String>> {
+        let current = self.current.read().map_err(|e| AppError::lock(e.to_string()))?;
+        Ok(current.as_ref().map(|vault| vault.id.clone()))
+    }
+
+    pub fn pool(&self) -> Result<Arc<DbPool>> {
+        let current = self.current.read().map_err(|e| AppError::lock(e.to_string()))?;
+        let vault = current.as_ref().ok_or_else(|| AppError::NotFound("No vault is open".to_string()))?;
+        if let Some(failure) = &vault.failure {
+            return Err(AppError::corrupt(format!(
+                "The vault's database could not be opened ({}). Restore it from a backup.",
+                failure.message
+            )));
+        }
+        vault
+            .pool
+            .clone()
+            .ok_or_else(|| AppError::Locked("The vault is locked. Unlock it with its passphrase.".to_string()))
+    }
+}
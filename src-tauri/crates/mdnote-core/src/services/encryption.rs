@@ -0,0 +1,393 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use crate::services::backup::backup_files;
+use crate::services::database::{DatabaseService, DbPool, DbState, DB_FILE_NAME, DEFAULT_READER_COUNT};
+use crate::services::maintenance::MaintenanceService;
+use crate::errors::{AppError, Result};
+
+/// Key derivation parameters of an encrypted vault, stored next to its database.
+/// Only the salt and cost parameters live here; the passphrase and key never touch disk.
+pub const KEY_PARAMS_FILE: &str = "mdnote.kdf.json";
+/// While a re-encryption swaps the database, the params it replaces are kept here and
+/// `REENCRYPT_MARKER` exists, so an interrupted swap can be finished or rolled back.
+pub const PREVIOUS_KEY_PARAMS_FILE: &str = "mdnote.kdf.json.previous";
+pub const REENCRYPT_MARKER: &str = "mdnote.reencrypting";
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+/// Whether this build was compiled with the `encryption` feature (SQLCipher).
+pub const SUPPORTED: bool = cfg!(feature = "encryption");
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// A 256-bit key derived from a passphrase.
+#[derive(Clone, PartialEq, Eq)]
+pub struct DerivedKey([u8; 32]);
+
+impl DerivedKey {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    #[cfg(feature = "encryption")]
+    fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+}
+
+impl fmt::Debug for DerivedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DerivedKey(..)")
+    }
+}
+
+/// Argon2id salt and cost parameters used to turn a passphrase into a `DerivedKey`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyParams {
+    pub salt: String,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl KeyParams {
+    /// Fresh parameters with a random salt and the Argon2 default costs.
+    pub fn generate() -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            salt: hex::encode(salt),
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+
+    pub fn derive(&self, passphrase: &str) -> Result<DerivedKey> {
+        let salt = hex::decode(&self.salt).map_err(|e| AppError::corrupt(format!("Invalid key salt: {}", e)))?;
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| AppError::corrupt(format!("Invalid key parameters: {}", e)))?;
+
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| AppError::validation(format!("Could not derive key: {}", e)))?;
+        Ok(DerivedKey(key))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| AppError::io("Could not encode the key parameters", e))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| AppError::corrupt(format!("Invalid key parameters: {}", e)))
+    }
+
+    pub fn load(vault_dir: &Path) -> Result<Option<Self>> {
+        let path = vault_dir.join(KEY_PARAMS_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(&path)?;
+        Self::from_json(&json).map(Some)
+    }
+
+    fn save(&self, vault_dir: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| AppError::io("Could not encode the key parameters", e))?;
+        write_atomically(&vault_dir.join(KEY_PARAMS_FILE), json.as_bytes())
+    }
+}
+
+/// Encrypts a note's content with XChaCha20-Poly1305. The note id is bound as associated
+/// data, so ciphertext copied onto another note fails to open. Returns hex of nonce || ciphertext.
+pub fn seal_note(key: &DerivedKey, note_id: &str, plaintext: &str) -> Result<String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let cipher = XChaCha20Poly1305::new(key.as_bytes().into());
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: plaintext.as_bytes(), aad: note_id.as_bytes() })
+        .map_err(|_| AppError::validation("Could not encrypt note"))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(hex::encode(sealed))
+}
+
+/// Reverses `seal_note`. A wrong key and tampered ciphertext both fail authentication.
+pub fn open_note(key: &DerivedKey, note_id: &str, sealed: &str) -> Result<String> {
+    let bytes = hex::decode(sealed).map_err(|e| AppError::corrupt(format!("Invalid ciphertext for note {}: {}", note_id, e)))?;
+    if bytes.len() < NONCE_LEN {
+        return Err(AppError::corrupt(format!("Invalid ciphertext for note {}", note_id)));
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+
+    let cipher = XChaCha20Poly1305::new(key.as_bytes().into());
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad: note_id.as_bytes() })
+        .map_err(|_| AppError::Locked("Incorrect passphrase".to_string()))?;
+    String::from_utf8(plaintext).map_err(|e| AppError::corrupt(e.to_string()))
+}
+
+/// Keys of encrypted notes unlocked during this session, by note id. Held in memory only.
+#[derive(Clone, Default)]
+pub struct NoteKeyring {
+    keys: Arc<Mutex<HashMap<String, DerivedKey>>>,
+}
+
+impl NoteKeyring {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, note_id: &str) -> Result<Option<DerivedKey>> {
+        let keys = self.keys.lock().map_err(|e| AppError::lock(e.to_string()))?;
+        Ok(keys.get(note_id).cloned())
+    }
+
+    pub fn insert(&self, note_id: String, key: DerivedKey) -> Result<()> {
+        let mut keys = self.keys.lock().map_err(|e| AppError::lock(e.to_string()))?;
+        keys.insert(note_id, key);
+        Ok(())
+    }
+
+    /// Forgets every unlocked note key.
+    pub fn clear(&self) -> Result<()> {
+        let mut keys = self.keys.lock().map_err(|e| AppError::lock(e.to_string()))?;
+        keys.clear();
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+pub struct EncryptionStatus {
+    /// Whether this build was compiled with the `encryption` feature (SQLCipher).
+    pub supported: bool,
+    pub encrypted: bool,
+    pub locked: bool,
+}
+
+/// Finishes or rolls back a re-encryption that was interrupted, e.g. by a crash, so the
+/// key params on disk match the database again. Call before `is_encrypted` when opening
+/// a vault.
+pub fn recover_reencryption(vault_dir: &Path) -> Result<()> {
+    let marker = vault_dir.join(REENCRYPT_MARKER);
+    let previous = vault_dir.join(PREVIOUS_KEY_PARAMS_FILE);
+    let tmp_path = encrypting_path(vault_dir);
+
+    // The rename of the new database is the commit point: while its file is still around,
+    // the old database is in place and needs the params it had before, or none
+    if marker.exists() && tmp_path.exists() {
+        if previous.exists() {
+            write_atomically(&vault_dir.join(KEY_PARAMS_FILE), &fs::read(&previous)?)?;
+        } else {
+            remove_if_exists(&vault_dir.join(KEY_PARAMS_FILE))?;
+        }
+        tracing::warn!("Rolled back an interrupted re-encryption of {}", vault_dir.display());
+    }
+    // The previous params go last, so recovery can run again if this is interrupted too
+    remove_if_exists(&tmp_path)?;
+    remove_if_exists(&marker)?;
+    remove_if_exists(&previous)?;
+    sync_dir(vault_dir)
+}
+
+pub fn is_encrypted(vault_dir: &Path) -> bool {
+    vault_dir.join(KEY_PARAMS_FILE).exists()
+}
+
+pub fn validate_passphrase(passphrase: &str) -> Result<()> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(AppError::invalid("passphrase", format!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// Keys a freshly opened connection. Must run before any other statement.
+#[cfg(feature = "encryption")]
+pub fn apply_key(conn: &Connection, key: &DerivedKey) -> Result<()> {
+    conn.execute_batch(&format!("PRAGMA key = \"x'{}'\";", key.to_hex()))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "encryption"))]
+pub fn apply_key(_conn: &Connection, _key: &DerivedKey) -> Result<()> {
+    Err(unsupported())
+}
+
+/// Copies the whole database behind `conn` into a new file at `target`, encrypted with `key`.
+#[cfg(feature = "encryption")]
+fn export_encrypted(conn: &Connection, target: &Path, key: &DerivedKey) -> Result<()> {
+    let target = target.to_string_lossy().replace('\'', "''");
+    conn.execute_batch(&format!("ATTACH DATABASE '{}' AS encrypted KEY \"x'{}'\";", target, key.to_hex()))?;
+    let exported = conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()));
+    conn.execute_batch("DETACH DATABASE encrypted;")?;
+    exported?;
+    Ok(())
+}
+
+#[cfg(not(feature = "encryption"))]
+fn export_encrypted(_conn: &Connection, _target: &Path, _key: &DerivedKey) -> Result<()> {
+    Err(unsupported())
+}
+
+#[cfg_attr(feature = "encryption", allow(dead_code))]
+fn unsupported() -> AppError {
+    AppError::Unsupported("This build of MDNote was compiled without encryption support".to_string())
+}
+
+/// Opens an encrypted vault's database with the key derived from `passphrase`.
+pub fn unlock_vault(state: &DbState, vault_id: String, vault_dir: PathBuf, passphrase: &str) -> Result<()> {
+    let params = KeyParams::load(&vault_dir)?
+        .ok_or_else(|| AppError::validation("This vault is not encrypted"))?;
+    let key = params.derive(passphrase)?;
+    let pool = DbPool::open(vault_dir, DEFAULT_READER_COUNT, Some(key))?;
+    state.open(vault_id, pool)
+}
+
+/// Closes the open vault's connections, leaving it locked until `unlock_vault`.
+pub fn lock_vault(state: &DbState, vault_dir: &Path) -> Result<()> {
+    if !is_encrypted(vault_dir) {
+        return Err(AppError::validation("Only encrypted vaults can be locked"));
+    }
+    state.lock()
+}
+
+/// Rewrites the open, unencrypted vault into an encrypted database. Existing backups
+/// stay plaintext; their paths are returned so the caller can offer to delete them.
+pub fn enable_encryption(state: &DbState, vault_id: String, vault_dir: PathBuf, passphrase: &str) -> Result<Vec<String>> {
+    if is_encrypted(&vault_dir) {
+        return Err(AppError::validation("This vault is already encrypted"));
+    }
+    validate_passphrase(passphrase)?;
+
+    let params = KeyParams::generate();
+    let key = params.derive(passphrase)?;
+    reencrypt(state, vault_id, vault_dir.clone(), &params, key)?;
+
+    let plaintext_backups = backup_files(&vault_dir)?;
+    Ok(plaintext_backups.into_iter().map(|p| p.to_string_lossy().into_owned()).collect())
+}
+
+/// Re-encrypts the open vault under a new passphrase after checking the current one.
+pub fn change_passphrase(state: &DbState, vault_id: String, vault_dir: PathBuf, current: &str, new: &str) -> Result<()> {
+    let params = KeyParams::load(&vault_dir)?
+        .ok_or_else(|| AppError::validation("This vault is not encrypted"))?;
+    let open_key = state.pool()?.with_writer(|db| Ok(db.key().cloned()))?;
+    if open_key != Some(params.derive(current)?) {
+        return Err(AppError::Locked("Incorrect passphrase".to_string()));
+    }
+    validate_passphrase(new)?;
+
+    let new_params = KeyParams::generate();
+    let new_key = new_params.derive(new)?;
+    reencrypt(state, vault_id, vault_dir, &new_params, new_key)
+}
+
+/// Exports the open vault into a new file encrypted with `key`, swaps it in for the
+/// current database and reopens the vault with the new key.
+fn reencrypt(state: &DbState, vault_id: String, vault_dir: PathBuf, params: &KeyParams, key: DerivedKey) -> Result<()> {
+    recover_reencryption(&vault_dir)?;
+    let tmp_path = encrypting_path(&vault_dir);
+
+    // Nothing else may write while the copy is taken, so the pool is held exclusively
+    let writer = state.take_exclusive()?.into_writer()?;
+    let old_key = writer.key().cloned();
+    let exported = export_exclusively(&writer, &tmp_path, &key);
+    // Closing the writer releases the lock; the WAL it checkpointed is empty
+    drop(writer);
+
+    let swapped = exported.and_then(|_| swap_database(&vault_dir, params));
+    // Whether the swap got as far as the rename decides if this finishes or undoes it
+    recover_reencryption(&vault_dir)?;
+    let key = if swapped.is_ok() { Some(key) } else { old_key };
+    state.open(vault_id, DbPool::open(vault_dir, DEFAULT_READER_COUNT, key)?)?;
+    swapped
+}
+
+/// Locks the database against every other connection, including other programs, copies
+/// it encrypted to `target` and checkpoints the WAL into the main file. The lock is held
+/// until `db` is closed.
+fn export_exclusively(db: &DatabaseService, target: &Path, key: &DerivedKey) -> Result<()> {
+    let conn = db.connection();
+    let _mode: String = conn.query_row("PRAGMA locking_mode = EXCLUSIVE", [], |row| row.get(0))?;
+    // The lock is taken by the first write and then kept
+    conn.execute_batch("BEGIN IMMEDIATE; COMMIT;")?;
+    export_encrypted(conn, target, key)?;
+    fs::File::open(target)?.sync_all()?;
+
+    let checkpoint = MaintenanceService::new(db).wal_checkpoint(true)?;
+    if checkpoint.busy || checkpoint.checkpointed_frames != checkpoint.wal_frames {
+        return Err(AppError::lock("Could not checkpoint the database".to_string()));
+    }
+    Ok(())
+}
+
+/// Puts the exported database in place of the current one. The new params are written
+/// first, with the previous ones and a marker kept alongside until `recover_reencryption`
+/// clears them, so the params on disk can always be made to match the database.
+fn swap_database(vault_dir: &Path, params: &KeyParams) -> Result<()> {
+    let params_path = vault_dir.join(KEY_PARAMS_FILE);
+    if params_path.exists() {
+        write_atomically(&vault_dir.join(PREVIOUS_KEY_PARAMS_FILE), &fs::read(&params_path)?)?;
+    }
+    write_atomically(&vault_dir.join(REENCRYPT_MARKER), b"")?;
+    params.save(vault_dir)?;
+
+    // Anything in the WAL now was written after the checkpoint and would be lost
+    let wal = vault_dir.join(format!("{}-wal", DB_FILE_NAME));
+    if fs::metadata(&wal).is_ok_and(|m| m.len() > 0) {
+        return Err(AppError::lock("The database was written to while being encrypted".to_string()));
+    }
+    remove_if_exists(&wal)?;
+    remove_if_exists(&vault_dir.join(format!("{}-shm", DB_FILE_NAME)))?;
+    fs::rename(encrypting_path(vault_dir), vault_dir.join(DB_FILE_NAME))?;
+    sync_dir(vault_dir)
+}
+
+fn encrypting_path(vault_dir: &Path) -> PathBuf {
+    vault_dir.join(format!("{}.encrypting", DB_FILE_NAME))
+}
+
+/// Replaces `path` with `contents` through a synced temp file, so after a crash it holds
+/// either the old contents or the new ones.
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp_path, path)?;
+    sync_dir(path.parent().unwrap_or(Path::new(".")))
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Makes renames in `dir` durable. Only possible on Unix; elsewhere renames are left to the OS.
+fn sync_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    fs::File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use std::sync::{Arc, RwLock};
+use serde::Serialize;
+use tracing::warn;
+
+/// Something a write changed. Services record these on the writer connection and
+/// `DbPool::with_writer` publishes the ones whose transaction committed, so listeners
+/// never hear about changes that were rolled back.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ChangeEvent {
+    NoteCreated { id: String, folder_id: Option<String> },
+    /// Title, content, folder or encryption changed.
+    NoteUpdated { id: String, folder_id: Option<String> },
+    NoteDeleted { id: String },
+    /// A folder was created, renamed or deleted. Deleting one also moves or deletes its notes.
+    FolderChanged { id: String },
+    FolderMoved { id: String, parent_id: Option<String> },
+    /// Tags were created, edited or deleted, or attached to or removed from `note_ids`.
+    TagChanged { tag_ids: Vec<String>, note_ids: Vec<String> },
+    /// `source_id`'s outgoing links were replaced; `target_ids` are the notes it links to now.
+    LinksChanged { source_id: String, target_ids: Vec<String> },
+    /// A reminder was created, snoozed, deleted or moved to another line of `note_id`.
+    ReminderChanged { id: String, note_id: String },
+}
+
+impl ChangeEvent {
+    /// The event name the desktop app emits this under.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChangeEvent::NoteCreated { .. } => "note:created",
+            ChangeEvent::NoteUpdated { .. } => "note:updated",
+            ChangeEvent::NoteDeleted { .. } => "note:deleted",
+            ChangeEvent::FolderChanged { .. } => "folder:changed",
+            ChangeEvent::FolderMoved { .. } => "folder:moved",
+            ChangeEvent::TagChanged { .. } => "tag:changed",
+            ChangeEvent::LinksChanged { .. } => "links:changed",
+            ChangeEvent::ReminderChanged { .. } => "reminder:changed",
+        }
+    }
+}
+
+/// Receives published change events, e.g. to forward them to the UI.
+pub trait EventSink: Send + Sync {
+    fn publish(&self, event: &ChangeEvent);
+}
+
+impl<F: Fn(&ChangeEvent) + Send + Sync> EventSink for F {
+    fn publish(&self, event: &ChangeEvent) {
+        self(event)
+    }
+}
+
+/// Fans published events out to every subscribed sink. Clones share subscribers.
+#[derive(Clone, Default)]
+pub struct ChangeBus {
+    sinks: Arc<RwLock<Vec<Arc<dyn EventSink>>>>,
+}
+
+impl ChangeBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&self, sink: Arc<dyn EventSink>) {
+        match self.sinks.write() {
+            Ok(mut sinks) => sinks.push(sink),
+            Err(e) => warn!("Change bus poisoned, subscriber dropped: {}", e),
+        }
+    }
+
+    /// Publishes a write's events in order, once each.
+    pub fn publish(&self, events: Vec<ChangeEvent>) {
+        if events.is_empty() {
+            return;
+        }
+        let Ok(sinks) = self.sinks.read() else { return };
+
+        let mut seen: Vec<&ChangeEvent> = Vec::with_capacity(events.len());
+        for event in &events {
+            if seen.contains(&event) {
+                continue;
+            }
+            seen.push(event);
+            for sink in sinks.iter() {
+                sink.publish(event);
+            }
+        }
+    }
+}
@@ -1,7 +1,8 @@
-use rusqlite::params;
+use rusqlite::{params, ToSql};
 use ulid::Ulid;
 use crate::models::folder::Folder;
 use crate::services::database::DatabaseService;
+use crate::services::events::ChangeEvent;
 use crate::errors::{AppError, Result};
 use serde::Serialize;
 
@@ -31,6 +32,7 @@ impl<'a> FolderService<'a> {
             "INSERT INTO folders (id, name, parent_id, created_at) VALUES (?, ?, ?, ?)",
             params![id, name, parent_id, now],
         )?;
+        self.db.record(ChangeEvent::FolderChanged { id: id.clone() });
 
         Ok(Folder {
             id,
@@ -42,7 +44,7 @@ impl<'a> FolderService<'a> {
 
     pub fn list_folders(&self) -> Result<Vec<FolderListItem>> {
         let conn = self.db.connection();
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare_cached(
             "SELECT f.id, f.name, f.parent_id, (SELECT COUNT(*) FROM notes WHERE folder_id = f.id) as note_count 
              FROM folders f"
         )?;
@@ -70,6 +72,8 @@ impl<'a> FolderService<'a> {
         let mut query = String::from("UPDATE folders SET id = id");
         let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![];
 
+        let renamed = name.is_some();
+        let moved = parent_id.is_some();
         if let Some(n) = name {
             query.push_str(", name = ?");
             params_vec.push(Box::new(n));
@@ -101,6 +105,12 @@ impl<'a> FolderService<'a> {
             })
         )?;
 
+        if renamed {
+            self.db.record(ChangeEvent::FolderChanged { id: folder.id.clone() });
+        }
+        if moved {
+            self.db.record(ChangeEvent::FolderMoved { id: folder.id.clone(), parent_id: folder.parent_id.clone() });
+        }
         Ok(folder)
     }
 
@@ -122,6 +132,18 @@ impl<'a> FolderService<'a> {
         }
 
         tx.commit()?;
+        self.db.record(ChangeEvent::FolderChanged { id });
         Ok(moved_notes)
     }
 }
+
+/// A SQL condition that the folder id in `folder_column` is one of `folders` (`None`
+/// being the root), with its parameters.
+pub(crate) fn folder_scope_sql(folders: &[Option<String>], folder_column: &str) -> (String, Vec<Box<dyn ToSql>>) {
+    let include_root = folders.iter().any(Option::is_none);
+    let ids = serde_json::Value::from(folders.iter().flatten().cloned().collect::<Vec<_>>()).to_string();
+    (
+        format!("({0} IN (SELECT value FROM json_each(?)) OR ({0} IS NULL AND ?))", folder_column),
+        vec![Box::new(ids), Box::new(include_root)],
+    )
+}
@@ -0,0 +1,125 @@
+use regex::{Captures, Regex};
+use std::ops::Range;
+use std::sync::OnceLock;
+
+fn hashtag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"#([\p{L}\p{N}_\-]+(?:/[\p{L}\p{N}_\-]+)*)").unwrap())
+}
+
+fn inline_code_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"`[^`\n]*`").unwrap())
+}
+
+fn url_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"[A-Za-z][A-Za-z0-9+.\-]*://\S+").unwrap())
+}
+
+fn heading_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^ {0,3}#{1,6}(\s|$)").unwrap())
+}
+
+/// Byte ranges of `content` where a `#` never starts a tag: fenced code blocks,
+/// inline code spans, heading lines and URLs.
+fn excluded_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut fence: Option<(&str, usize)> = None;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let marker = if trimmed.starts_with("```") {
+            Some("```")
+        } else if trimmed.starts_with("~~~") {
+            Some("~~~")
+        } else {
+            None
+        };
+
+        match (fence, marker) {
+            (None, Some(m)) => fence = Some((m, offset)),
+            (Some((open, start)), Some(m)) if m == open => {
+                ranges.push(start..offset + line.len());
+                fence = None;
+            }
+            (None, None) if heading_regex().is_match(line) => ranges.push(offset..offset + line.len()),
+            _ => {}
+        }
+        offset += line.len();
+    }
+    // An unclosed fence runs to the end of the note
+    if let Some((_, start)) = fence {
+        ranges.push(start..content.len());
+    }
+
+    ranges.extend(inline_code_regex().find_iter(content).map(|m| m.range()));
+    ranges.extend(url_regex().find_iter(content).map(|m| m.range()));
+    ranges
+}
+
+/// A `#` only starts a hashtag at the beginning of a word, so `C#`, `a#b`,
+/// `&#39;` and URL fragments like `/page#section` are left alone.
+fn starts_hashtag(content: &str, hash_pos: usize, excluded: &[Range<usize>]) -> bool {
+    if excluded.iter().any(|r| r.contains(&hash_pos)) {
+        return false;
+    }
+    match content[..hash_pos].chars().next_back() {
+        None => true,
+        Some(c) => !(c.is_alphanumeric() || matches!(c, '_' | '-' | '/' | '#' | '&')),
+    }
+}
+
+/// Returns the distinct inline hashtags in `content` (without the `#`), in order of
+/// first appearance. Purely numeric tags such as issue references (`#42`) are skipped.
+pub fn extract_hashtags(content: &str) -> Vec<String> {
+    let excluded = excluded_ranges(content);
+    let mut tags: Vec<String> = Vec::new();
+
+    for caps in hashtag_regex().captures_iter(content) {
+        let whole = caps.get(0).unwrap();
+        let tag = caps[1].trim_end_matches(['/', '-']);
+        if !starts_hashtag(content, whole.start(), &excluded) || tag.chars().all(|c| c.is_numeric()) {
+            continue;
+        }
+        if !tags.iter().any(|t| t.to_lowercase() == tag.to_lowercase()) {
+            tags.push(tag.to_string());
+        }
+    }
+
+    tags
+}
+
+/// Rewrites every `#old` hashtag in `content` to `#new`, matching case-insensitively.
+/// With `include_descendants`, nested tags such as `#old/child` become `#new/child` too.
+pub fn rewrite_hashtag(content: &str, old: &str, new: &str, include_descendants: bool) -> String {
+    let excluded = excluded_ranges(content);
+    let old_lower = old.to_lowercase();
+
+    hashtag_regex()
+        .replace_all(content, |caps: &Captures| {
+            let whole = caps.get(0).unwrap();
+            let tag = &caps[1];
+            if !starts_hashtag(content, whole.start(), &excluded) {
+                return whole.as_str().to_string();
+            }
+
+            // Split after as many characters as `old` has, so `#Old/child` keeps its `/child`
+            let split = tag
+                .char_indices()
+                .nth(old.chars().count())
+                .map(|(i, _)| i)
+                .unwrap_or(tag.len());
+            let (head, rest) = tag.split_at(split);
+            let matches_old = head.to_lowercase() == old_lower;
+
+            if matches_old && (rest.is_empty() || (include_descendants && rest.starts_with('/'))) {
+                format!("#{}{}", new, rest)
+            } else {
+                whole.as_str().to_string()
+            }
+        })
+        .into_owned()
+}
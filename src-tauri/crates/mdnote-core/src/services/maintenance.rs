@@ -0,0 +1,181 @@
+use rusqlite::params;
+use serde::Serialize;
+use std::fs;
+use crate::services::database::DatabaseService;
+use crate::errors::Result;
+
+#[derive(Serialize)]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    pub rowid: Option<i64>,
+    pub parent: String,
+}
+
+#[derive(Serialize)]
+pub struct IntegrityReport {
+    pub ok: bool,
+    /// Messages from `PRAGMA integrity_check` (or `quick_check`); empty when healthy.
+    pub errors: Vec<String>,
+    pub foreign_key_violations: Vec<ForeignKeyViolation>,
+}
+
+#[derive(Serialize)]
+pub struct CheckpointResult {
+    pub busy: bool,
+    pub wal_frames: i64,
+    pub checkpointed_frames: i64,
+}
+
+#[derive(Serialize)]
+pub struct TableStats {
+    pub name: String,
+    pub rows: i64,
+    pub size_bytes: i64,
+}
+
+#[derive(Serialize)]
+pub struct DbStats {
+    pub file_size_bytes: u64,
+    pub wal_size_bytes: u64,
+    pub page_size: i64,
+    pub page_count: i64,
+    pub freelist_count: i64,
+    pub tables: Vec<TableStats>,
+    /// Size of the `notes_fts` full-text index including its shadow tables.
+    pub fts_index_size_bytes: i64,
+}
+
+pub struct MaintenanceService<'a> {
+    db: &'a DatabaseService,
+}
+
+impl<'a> MaintenanceService<'a> {
+    pub fn new(db: &'a DatabaseService) -> Self {
+        Self { db }
+    }
+
+    /// Runs `PRAGMA integrity_check` (or the faster `quick_check`) and `foreign_key_check`.
+    pub fn integrity_check(&self, quick: bool) -> Result<IntegrityReport> {
+        let conn = self.db.connection();
+        let pragma = if quick { "PRAGMA quick_check" } else { "PRAGMA integrity_check" };
+
+        let mut stmt = conn.prepare(pragma)?;
+        let errors: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?
+            .into_iter()
+            .filter(|msg| msg != "ok")
+            .collect();
+
+        let foreign_key_violations = self.foreign_key_check()?;
+
+        Ok(IntegrityReport {
+            ok: errors.is_empty() && foreign_key_violations.is_empty(),
+            errors,
+            foreign_key_violations,
+        })
+    }
+
+    pub fn foreign_key_check(&self) -> Result<Vec<ForeignKeyViolation>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+        let violation_iter = stmt.query_map([], |row| {
+            Ok(ForeignKeyViolation {
+                table: row.get(0)?,
+                rowid: row.get(1)?,
+                parent: row.get(2)?,
+            })
+        })?;
+
+        let mut violations = Vec::new();
+        for violation in violation_iter {
+            violations.push(violation?);
+        }
+
+        Ok(violations)
+    }
+
+    /// Rebuilds the database file, reclaiming all free pages.
+    pub fn vacuum(&self) -> Result<()> {
+        self.db.connection().execute_batch("VACUUM")?;
+        Ok(())
+    }
+
+    /// Frees up to `pages` pages (all when `None`). Only has an effect on databases
+    /// using `auto_vacuum = INCREMENTAL`; returns the number of free pages left.
+    pub fn incremental_vacuum(&self, pages: Option<i64>) -> Result<i64> {
+        let conn = self.db.connection();
+        match pages {
+            Some(n) => conn.execute_batch(&format!("PRAGMA incremental_vacuum({})", n.max(0)))?,
+            None => conn.execute_batch("PRAGMA incremental_vacuum")?,
+        }
+        Ok(conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?)
+    }
+
+    /// Refreshes the statistics the query planner uses to pick indexes.
+    pub fn analyze(&self) -> Result<()> {
+        self.db.connection().execute_batch("ANALYZE")?;
+        Ok(())
+    }
+
+    /// Copies the write-ahead log into the main file. `truncate` also resets the WAL to zero bytes.
+    pub fn wal_checkpoint(&self, truncate: bool) -> Result<CheckpointResult> {
+        let pragma = if truncate { "PRAGMA wal_checkpoint(TRUNCATE)" } else { "PRAGMA wal_checkpoint(PASSIVE)" };
+        let result = self.db.connection().query_row(pragma, [], |row| {
+            Ok(CheckpointResult {
+                busy: row.get::<_, i64>(0)? != 0,
+                wal_frames: row.get(1)?,
+                checkpointed_frames: row.get(2)?,
+            })
+        })?;
+        Ok(result)
+    }
+
+    pub fn get_db_stats(&self) -> Result<DbStats> {
+        let conn = self.db.connection();
+        let path = self.db.path();
+        let file_size_bytes = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let wal_size_bytes = fs::metadata(path.with_extension("db-wal")).map(|m| m.len()).unwrap_or(0);
+
+        let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let freelist_count: i64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+
+        // FTS5 keeps its index in shadow tables (notes_fts_data, ...); they are summed below
+        let mut stmt = conn.prepare(
+            "SELECT name FROM sqlite_master
+             WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name NOT LIKE 'notes_fts_%'
+             ORDER BY name"
+        )?;
+        let names: Vec<String> = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut tables = Vec::new();
+        for name in names {
+            let rows: i64 = conn.query_row(&format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\"")), [], |row| row.get(0))?;
+            let size_bytes: i64 = conn.query_row(
+                "SELECT COALESCE(SUM(pgsize), 0) FROM dbstat WHERE name = ?",
+                params![name],
+                |row| row.get(0),
+            )?;
+            tables.push(TableStats { name, rows, size_bytes });
+        }
+
+        let fts_index_size_bytes: i64 = conn.query_row(
+            "SELECT COALESCE(SUM(pgsize), 0) FROM dbstat WHERE name LIKE 'notes_fts_%'",
+            [],
+            |row| row.get(0),
+        )?;
+
+        Ok(DbStats {
+            file_size_bytes,
+            wal_size_bytes,
+            page_size,
+            page_count,
+            freelist_count,
+            tables,
+            fts_index_size_bytes,
+        })
+    }
+}
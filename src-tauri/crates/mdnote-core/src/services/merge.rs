@@ -0,0 +1,126 @@
+use serde::Serialize;
+
+/// Above this many line pairs the differing middle of two texts is compared as one block
+/// instead of line by line, to bound the memory the diff takes.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+pub const CONFLICT_START: &str = "<<<<<<< yours";
+pub const CONFLICT_SEPARATOR: &str = "=======";
+pub const CONFLICT_END: &str = ">>>>>>> theirs";
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct MergeResult {
+    pub merged: String,
+    /// Regions both sides changed differently. Each is left in `merged` between conflict markers.
+    pub conflicts: usize,
+}
+
+/// Merges two edits of `base` line by line. Changes made on only one side are applied;
+/// where both sides changed the same lines differently, both versions are kept between
+/// `CONFLICT_START`, `CONFLICT_SEPARATOR` and `CONFLICT_END` marker lines.
+pub fn merge_text(base: &str, ours: &str, theirs: &str) -> MergeResult {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours: Vec<&str> = ours.split_inclusive('\n').collect();
+    let theirs: Vec<&str> = theirs.split_inclusive('\n').collect();
+    let in_ours = matching_lines(&base, &ours);
+    let in_theirs = matching_lines(&base, &theirs);
+
+    let mut result = MergeResult { merged: String::new(), conflicts: 0 };
+    let (mut b, mut o, mut t) = (0, 0, 0);
+    // Lines every version kept split the texts into chunks that merge independently
+    for i in 0..=base.len() {
+        let anchor = match (in_ours.get(i), in_theirs.get(i)) {
+            (Some(Some(oj)), Some(Some(tj))) => (*oj, *tj),
+            (None, None) => (ours.len(), theirs.len()),
+            _ => continue,
+        };
+        result.merge_chunk(&base[b..i], &ours[o..anchor.0], &theirs[t..anchor.1]);
+        if let Some(line) = base.get(i) {
+            result.merged.push_str(line);
+        }
+        (b, o, t) = (i + 1, anchor.0 + 1, anchor.1 + 1);
+    }
+    result
+}
+
+impl MergeResult {
+    fn merge_chunk(&mut self, base: &[&str], ours: &[&str], theirs: &[&str]) {
+        if ours == base || ours == theirs {
+            self.push_lines(theirs);
+        } else if theirs == base {
+            self.push_lines(ours);
+        } else {
+            self.conflicts += 1;
+            self.push_marker(CONFLICT_START);
+            self.push_lines(ours);
+            self.push_marker(CONFLICT_SEPARATOR);
+            self.push_lines(theirs);
+            self.push_marker(CONFLICT_END);
+        }
+    }
+
+    fn push_lines(&mut self, lines: &[&str]) {
+        for line in lines {
+            self.merged.push_str(line);
+        }
+    }
+
+    fn push_marker(&mut self, marker: &str) {
+        if !self.merged.is_empty() && !self.merged.ends_with('\n') {
+            self.merged.push('\n');
+        }
+        self.merged.push_str(marker);
+        self.merged.push('\n');
+    }
+}
+
+/// For each line of `a`, the index of the line it is paired with in a longest common
+/// subsequence of `a` and `b`.
+fn matching_lines(a: &[&str], b: &[&str]) -> Vec<Option<usize>> {
+    let mut pairs = vec![None; a.len()];
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    for (i, pair) in pairs.iter_mut().enumerate().take(prefix) {
+        *pair = Some(i);
+    }
+    for k in 0..suffix {
+        pairs[a.len() - 1 - k] = Some(b.len() - 1 - k);
+    }
+
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    let (n, m) = (a_mid.len(), b_mid.len());
+    if n == 0 || m == 0 || (n + 1) * (m + 1) > MAX_DIFF_CELLS {
+        return pairs;
+    }
+
+    // lcs[i * (m + 1) + j] is the LCS length of a_mid[i..] and b_mid[j..]
+    let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i * (m + 1) + j] = if a_mid[i] == b_mid[j] {
+                lcs[(i + 1) * (m + 1) + j + 1] + 1
+            } else {
+                lcs[(i + 1) * (m + 1) + j].max(lcs[i * (m + 1) + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a_mid[i] == b_mid[j] {
+            pairs[prefix + i] = Some(prefix + j);
+            i += 1;
+            j += 1;
+        } else if lcs[(i + 1) * (m + 1) + j] >= lcs[i * (m + 1) + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
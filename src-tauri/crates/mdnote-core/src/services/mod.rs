@@ -0,0 +1,21 @@
+pub mod database;
+pub mod note;
+pub mod folder;
+pub mod search;
+pub mod tag;
+pub mod hashtag;
+pub mod settings;
+pub mod backlink;
+pub mod suggestion;
+pub mod backup;
+pub mod maintenance;
+pub mod vault;
+pub mod encryption;
+pub mod events;
+pub mod undo;
+pub mod merge;
+pub mod template;
+pub mod periodic;
+pub mod task;
+pub mod reminder;
+pub mod property;
@@ -0,0 +1,382 @@
+use rusqlite::{params, OptionalExtension, Row};
+use ulid::Ulid;
+use crate::models::note::{Note, NoteSummary};
+use crate::models::property::{PropertyQuery, PropertyValue};
+use crate::services::database::DatabaseService;
+use crate::services::events::ChangeEvent;
+use crate::services::encryption::{open_note, seal_note, validate_passphrase, DerivedKey, KeyParams, NoteKeyring};
+use crate::services::property::{extract_properties, property_filter_sql, property_sort_sql, set_front_matter_property, PropertyService};
+use crate::services::tag::{TagService, TAG_AND_DESCENDANTS_SQL};
+use crate::services::task::TaskService;
+use crate::services::undo::UndoService;
+use crate::errors::{AppError, Result};
+
+pub struct NoteService<'a> {
+    db: &'a DatabaseService,
+    keyring: Option<&'a NoteKeyring>,
+}
+
+impl<'a> NoteService<'a> {
+    pub fn new(db: &'a DatabaseService) -> Self {
+        Self { db, keyring: None }
+    }
+
+    /// Lets the service read and write encrypted notes unlocked in `keyring`.
+    /// Without one, encrypted notes are always treated as locked.
+    pub fn with_keyring(mut self, keyring: &'a NoteKeyring) -> Self {
+        self.keyring = Some(keyring);
+        self
+    }
+
+    pub fn create_note(&self, folder_id: Option<String>, title: String, content: String) -> Result<Note> {
+        let id = Ulid::new().to_string();
+        let now = chrono::Utc::now().timestamp_millis();
+        
+        self.db.transaction(|| {
+            self.db.connection().execute(
+                "INSERT INTO notes (id, folder_id, title, content, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)",
+                params![id, folder_id, title, content, now, now],
+            )?;
+            self.db.record(ChangeEvent::NoteCreated { id: id.clone(), folder_id: folder_id.clone() });
+            TagService::new(self.db).sync_inline_tags(&id, &content)?;
+            TaskService::new(self.db).sync_tasks(&id, &content)?;
+            PropertyService::new(self.db).sync_properties(&id, &content)
+        })?;
+
+        Ok(Note {
+            id,
+            folder_id,
+            title,
+            properties: extract_properties(&content),
+            content,
+            created_at: now,
+            updated_at: now,
+            encrypted: false,
+            locked: false,
+        })
+    }
+
+    pub fn get_note(&self, id: String) -> Result<Note> {
+        let conn = self.db.connection();
+        let mut note = conn.query_row(
+            "SELECT id, folder_id, title, content, created_at, updated_at, encrypted FROM notes WHERE id = ?",
+            params![id],
+            |row| self.map_row_to_note(row),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Note with id {} not found", id)))?;
+
+        if note.encrypted {
+            match self.session_key(&note.id)? {
+                Some(key) => {
+                    note.content = open_note(&key, &note.id, &note.content)?;
+                    note.locked = false;
+                }
+                // Never hand out ciphertext
+                None => note.content = String::new(),
+            }
+        }
+        note.properties = extract_properties(&note.content);
+        Ok(note)
+    }
+
+    /// Applies the given changes. With `expected_updated_at`, the write only goes through if
+    /// the note is still at that version; otherwise it fails with `AppError::Conflict`
+    /// carrying the version stored now.
+    pub fn update_note(
+        &self,
+        id: String,
+        title: Option<String>,
+        content: Option<String>,
+        folder_id: Option<Option<String>>,
+        expected_updated_at: Option<i64>,
+    ) -> Result<Note> {
+        let conn = self.db.connection();
+
+        // Get current note
+        let mut note = self.get_note(id.clone())?;
+        if expected_updated_at.is_some_and(|expected| expected != note.updated_at) {
+            return Err(Self::conflict(note));
+        }
+        // Every write moves the version on, even two within the same millisecond
+        let now = chrono::Utc::now().timestamp_millis().max(note.updated_at + 1);
+
+        let mut query = String::from("UPDATE notes SET updated_at = ?");
+        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(now)];
+
+        if let Some(t) = title {
+            query.push_str(", title = ?");
+            params_vec.push(Box::new(t.clone()));
+            note.title = t;
+        }
+        let content_changed = content.is_some();
+        if let Some(c) = content {
+            let stored = if note.encrypted {
+                let key = self.session_key(&note.id)?
+                    .ok_or_else(|| AppError::Locked(format!("Note {} is locked", note.id)))?;
+                seal_note(&key, &note.id, &c)?
+            } else {
+                c.clone()
+            };
+            query.push_str(", content = ?");
+            params_vec.push(Box::new(stored));
+            note.properties = extract_properties(&c);
+            note.content = c;
+        }
+        if let Some(f) = folder_id {
+            query.push_str(", folder_id = ?");
+            params_vec.push(Box::new(f.clone()));
+            note.folder_id = f;
+        }
+
+        query.push_str(" WHERE id = ?");
+        params_vec.push(Box::new(id.clone()));
+        if let Some(expected) = expected_updated_at {
+            // Another connection may have written since the note was read above
+            query.push_str(" AND updated_at = ?");
+            params_vec.push(Box::new(expected));
+        }
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+        self.db.transaction(|| {
+            if conn.execute(&query, params_refs.as_slice())? == 0 {
+                return Err(Self::conflict(self.get_note(id.clone())?));
+            }
+            self.db.record(ChangeEvent::NoteUpdated { id: note.id.clone(), folder_id: note.folder_id.clone() });
+            // Hashtags inside encrypted content would leak into plaintext tag names
+            if content_changed && !note.encrypted {
+                TagService::new(self.db).sync_inline_tags(&note.id, &note.content)?;
+                TaskService::new(self.db).sync_tasks(&note.id, &note.content)?;
+                PropertyService::new(self.db).sync_properties(&note.id, &note.content)?;
+            }
+            Ok(())
+        })?;
+
+        note.updated_at = now;
+        Ok(note)
+    }
+
+    /// Sets `key` in the note's YAML front matter, or removes it when `value` is `None`,
+    /// leaving the rest of the note untouched. Fails with `AppError::Conflict` if the note
+    /// changes while this runs.
+    pub fn set_note_property(&self, id: String, key: String, value: Option<PropertyValue>) -> Result<Note> {
+        let note = self.get_note(id.clone())?;
+        if note.locked {
+            return Err(AppError::Locked(format!("Note {} is locked", note.id)));
+        }
+        let content = set_front_matter_property(&note.content, &key, value.as_ref())?;
+        if content == note.content {
+            return Ok(note);
+        }
+        self.update_note(id, None, Some(content), None, Some(note.updated_at))
+    }
+
+    pub fn delete_note(&self, id: String) -> Result<()> {
+        let conn = self.db.connection();
+        let affected = conn.execute("DELETE FROM notes WHERE id = ?", params![id])?;
+        
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Note with id {} not found", id)));
+        }
+        self.db.record(ChangeEvent::NoteDeleted { id });
+        Ok(())
+    }
+
+    pub fn list_notes(&self, folder_id: Option<String>, tag_id: Option<String>, include_descendant_tags: bool, limit: i32, offset: i32) -> Result<(Vec<NoteSummary>, i64)> {
+        self.list_notes_with_properties(folder_id, tag_id, include_descendant_tags, &PropertyQuery::default(), limit, offset)
+    }
+
+    /// Like `list_notes`, narrowed and ordered by front matter properties. Property
+    /// filters, like a tag, list notes from every folder unless one is given.
+    pub fn list_notes_with_properties(
+        &self,
+        folder_id: Option<String>,
+        tag_id: Option<String>,
+        include_descendant_tags: bool,
+        properties: &PropertyQuery,
+        limit: i32,
+        offset: i32,
+    ) -> Result<(Vec<NoteSummary>, i64)> {
+        let conn = self.db.connection();
+        
+        let mut query = String::from("SELECT n.id, n.folder_id, n.title, CASE WHEN n.encrypted THEN '' ELSE substr(n.content, 1, 200) END as excerpt, n.updated_at, n.encrypted FROM notes n");
+        let mut count_query = String::from("SELECT COUNT(*) FROM notes n");
+        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        let mut where_clauses: Vec<String> = Vec::new();
+
+        if let Some(t) = tag_id {
+            if include_descendant_tags {
+                // A subquery rather than a join, so a note carrying both a tag and one of its
+                // descendants is only listed once.
+                where_clauses.push(format!(
+                    "n.id IN (SELECT nt.note_id FROM note_tags nt WHERE nt.tag_id IN ({}))",
+                    TAG_AND_DESCENDANTS_SQL
+                ));
+            } else {
+                query.push_str(" JOIN note_tags nt ON n.id = nt.note_id");
+                count_query.push_str(" JOIN note_tags nt ON n.id = nt.note_id");
+                where_clauses.push("nt.tag_id = ?".to_string());
+            }
+            params_vec.push(Box::new(t));
+        }
+
+        for filter in &properties.filters {
+            let (clause, params) = property_filter_sql(filter, "n.id");
+            where_clauses.push(clause);
+            params_vec.extend(params);
+        }
+
+        if let Some(f) = folder_id {
+            where_clauses.push("n.folder_id = ?".to_string());
+            params_vec.push(Box::new(f));
+        } else if where_clauses.is_empty() {
+            // Only enforce root-only filtering if No Tag is selected.
+            // If a tag is selected but no folder, we want notes from ALL folders with that tag.
+            where_clauses.push("n.folder_id IS NULL".to_string());
+        }
+
+        if !where_clauses.is_empty() {
+            let where_str = format!(" WHERE {}", where_clauses.join(" AND "));
+            query.push_str(&where_str);
+            count_query.push_str(&where_str);
+        }
+
+        let params_refs_count: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+        let total: i64 = conn.query_row(&count_query, params_refs_count.as_slice(), |row| row.get(0))?;
+
+        query.push_str(" ORDER BY ");
+        if let Some(sort) = &properties.sort {
+            let (order, params) = property_sort_sql(sort, "n.id");
+            query.push_str(&order);
+            query.push_str(", ");
+            params_vec.extend(params);
+        }
+        query.push_str("n.updated_at DESC LIMIT ? OFFSET ?");
+
+        params_vec.push(Box::new(limit));
+        params_vec.push(Box::new(offset));
+
+        let params_refs_query: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+        let mut stmt = conn.prepare_cached(&query)?;
+        let note_iter = stmt.query_map(params_refs_query.as_slice(), |row| {
+            Ok(NoteSummary {
+                id: row.get(0)?,
+                folder_id: row.get(1)?,
+                title: row.get(2)?,
+                excerpt: row.get(3)?,
+                updated_at: row.get(4)?,
+                encrypted: row.get(5)?,
+            })
+        })?;
+
+        let mut notes = Vec::new();
+        for note in note_iter {
+            notes.push(note?);
+        }
+
+        Ok((notes, total))
+    }
+
+    /// Encrypts the note's content with a key derived from `passphrase`. The title,
+    /// folder and tags stay readable; the content leaves the search index. The note
+    /// stays unlocked for the rest of the session.
+    pub fn encrypt_note(&self, id: String, passphrase: &str) -> Result<Note> {
+        let mut note = self.get_note(id)?;
+        if note.encrypted {
+            return Err(AppError::validation(format!("Note {} is already encrypted", note.id)));
+        }
+        validate_passphrase(passphrase)?;
+
+        if self.keyring.is_none() {
+            return Err(AppError::validation("Encrypted notes need a session keyring"));
+        }
+
+        let params = KeyParams::generate();
+        let key = params.derive(passphrase)?;
+        let sealed = seal_note(&key, &note.id, &note.content)?;
+        let now = chrono::Utc::now().timestamp_millis();
+
+        // Nothing derived from the plaintext may outlive the switch to ciphertext
+        let conn = self.db.connection();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE notes SET encrypted = 1, key_params = ?, content = ?, updated_at = ? WHERE id = ?",
+            params![params.to_json()?, sealed, now, note.id],
+        )?;
+        tx.execute("UPDATE backlinks SET context = NULL WHERE source_id = ?", params![note.id])?;
+        let untagged: Vec<String> = tx
+            .prepare("DELETE FROM note_tags WHERE note_id = ? AND source = 'inline' RETURNING tag_id")?
+            .query_map(params![note.id], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        TaskService::new(self.db).clear_tasks(&note.id)?;
+        PropertyService::new(self.db).clear_properties(&note.id)?;
+        UndoService::new(self.db).forget_note(&note.id)?;
+        tx.commit()?;
+
+        self.remember_key(&note.id, key)?;
+        self.db.record(ChangeEvent::NoteUpdated { id: note.id.clone(), folder_id: note.folder_id.clone() });
+        if !untagged.is_empty() {
+            self.db.record(ChangeEvent::TagChanged { tag_ids: untagged, note_ids: vec![note.id.clone()] });
+        }
+
+        note.encrypted = true;
+        note.updated_at = now;
+        Ok(note)
+    }
+
+    /// Unlocks an encrypted note until `NoteKeyring::clear`, returning it decrypted.
+    pub fn decrypt_note_for_session(&self, id: String, passphrase: &str) -> Result<Note> {
+        let conn = self.db.connection();
+        let (content, key_params): (String, Option<String>) = conn.query_row(
+            "SELECT content, key_params FROM notes WHERE id = ? AND encrypted = 1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Encrypted note with id {} not found", id)))?;
+
+        let key_params = key_params
+            .ok_or_else(|| AppError::corrupt(format!("Note {} has no key parameters", id)))?;
+        let key = KeyParams::from_json(&key_params)?.derive(passphrase)?;
+        // Authenticates the passphrase before it is remembered
+        open_note(&key, &id, &content)?;
+        self.remember_key(&id, key)?;
+
+        self.get_note(id)
+    }
+
+    fn conflict(current: Note) -> AppError {
+        AppError::Conflict {
+            message: format!("Note {} was changed since it was loaded", current.id),
+            current: Some(Box::new(current)),
+        }
+    }
+
+    fn session_key(&self, note_id: &str) -> Result<Option<DerivedKey>> {
+        match self.keyring {
+            Some(keyring) => keyring.get(note_id),
+            None => Ok(None),
+        }
+    }
+
+    fn remember_key(&self, note_id: &str, key: DerivedKey) -> Result<()> {
+        let keyring = self.keyring
+            .ok_or_else(|| AppError::validation("Encrypted notes need a session keyring"))?;
+        keyring.insert(note_id.to_string(), key)
+    }
+
+    fn map_row_to_note(&self, row: &Row) -> rusqlite::Result<Note> {
+        let encrypted: bool = row.get(6)?;
+        Ok(Note {
+            id: row.get(0)?,
+            folder_id: row.get(1)?,
+            title: row.get(2)?,
+            content: row.get(3)?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+            encrypted,
+            locked: encrypted,
+            properties: Vec::new(),
+        })
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, Local, NaiveDate, TimeZone};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use crate::errors::{AppError, Result};
+use crate::models::note::{Note, NoteSummary};
+use crate::models::settings::{PeriodicNoteSettings, PeriodicNotesSettings};
+use crate::services::database::DatabaseService;
+use crate::services::note::NoteService;
+use crate::services::settings::SettingsService;
+use crate::services::template::TemplateService;
+
+/// The longest range `list_periodic_notes` covers, about a year of calendar.
+const MAX_CALENDAR_DAYS: i64 = 366;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Period {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Period::Daily => "daily",
+            Period::Weekly => "weekly",
+            Period::Monthly => "monthly",
+        }
+    }
+
+    /// The first day of the period `date` falls in: the day itself, the Monday of its
+    /// ISO week, or the first of its month.
+    pub fn start_of(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Daily => date,
+            Period::Weekly => date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64),
+            Period::Monthly => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    fn settings<'s>(&self, settings: &'s PeriodicNotesSettings) -> &'s PeriodicNoteSettings {
+        match self {
+            Period::Daily => &settings.daily,
+            Period::Weekly => &settings.weekly,
+            Period::Monthly => &settings.monthly,
+        }
+    }
+
+    fn parse(period: &str) -> Option<Self> {
+        match period {
+            "daily" => Some(Period::Daily),
+            "weekly" => Some(Period::Weekly),
+            "monthly" => Some(Period::Monthly),
+            _ => None,
+        }
+    }
+}
+
+/// The current date in local time.
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Parses a `YYYY-MM-DD` date argument.
+pub fn parse_date(field: &str, value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), DATE_FORMAT)
+        .map_err(|_| AppError::invalid(field, format!("{} is not a YYYY-MM-DD date", value)))
+}
+
+/// Rejects empty formats and ones chrono can't render, which would otherwise panic.
+pub fn validate_title_format(format: &str) -> Result<()> {
+    if format.trim().is_empty() {
+        return Err(AppError::invalid("title_format", "Title format must not be empty"));
+    }
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(AppError::invalid("title_format", format!("{} is not a valid date format", format)));
+    }
+    Ok(())
+}
+
+/// The note for one day, week or month.
+#[derive(Debug, Serialize)]
+pub struct PeriodicNote {
+    pub period: Period,
+    /// First day of the period, `YYYY-MM-DD`.
+    pub start_date: String,
+    pub note: Note,
+    /// False when the note already existed.
+    pub created: bool,
+    /// Where the template's `{{cursor}}` ended up, for a note just created from one.
+    pub cursor: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PeriodicNoteSummary {
+    pub period: Period,
+    pub start_date: String,
+    pub note_id: String,
+    pub title: String,
+    /// None for encrypted notes.
+    pub word_count: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CalendarDay {
+    pub date: String,
+    pub daily_note: Option<PeriodicNoteSummary>,
+    pub created: Vec<NoteSummary>,
+    /// Notes last changed that day, other than the ones created on it.
+    pub updated: Vec<NoteSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PeriodicCalendar {
+    /// Every day of the range, in order, including ones without notes.
+    pub days: Vec<CalendarDay>,
+    /// Weekly and monthly notes for the periods overlapping the range.
+    pub weeks: Vec<PeriodicNoteSummary>,
+    pub months: Vec<PeriodicNoteSummary>,
+}
+
+pub struct PeriodicService<'a> {
+    db: &'a DatabaseService,
+}
+
+impl<'a> PeriodicService<'a> {
+    pub fn new(db: &'a DatabaseService) -> Self {
+        Self { db }
+    }
+
+    /// Finds the note for the period containing `date`, or creates it in the configured
+    /// folder with the configured title and template. A note already titled for the
+    /// period in that folder, e.g. one written by hand, is adopted instead.
+    pub fn open_periodic_note(&self, period: Period, date: NaiveDate) -> Result<PeriodicNote> {
+        let start = period.start_of(date);
+        let start_date = start.format(DATE_FORMAT).to_string();
+        let notes = NoteService::new(self.db);
+        let conn = self.db.connection();
+
+        let mapped: Option<String> = conn.query_row(
+            "SELECT note_id FROM periodic_notes WHERE period = ? AND start_date = ?",
+            params![period.as_str(), start_date],
+            |row| row.get(0),
+        ).optional()?;
+        if let Some(note_id) = mapped {
+            let note = notes.get_note(note_id)?;
+            return Ok(PeriodicNote { period, start_date, note, created: false, cursor: None });
+        }
+
+        let settings = SettingsService::new(self.db).get_settings()?.periodic_notes;
+        let config = period.settings(&settings).clone();
+        validate_title_format(&config.title_format)?;
+        let title = start.format(&config.title_format).to_string();
+
+        let existing: Option<String> = conn.query_row(
+            "SELECT id FROM notes WHERE title = ? AND folder_id IS ? ORDER BY created_at LIMIT 1",
+            params![title, config.folder_id],
+            |row| row.get(0),
+        ).optional()?;
+        let (note, created, cursor) = match (existing, config.template_id) {
+            (Some(note_id), _) => (notes.get_note(note_id)?, false, None),
+            (None, Some(template_id)) => {
+                let vars = HashMap::from([("title".to_string(), title), ("date".to_string(), start_date.clone())]);
+                let from_template = TemplateService::new(self.db).create_note_from_template(template_id, config.folder_id, &vars)?;
+                (from_template.note, true, from_template.cursor)
+            }
+            (None, None) => (notes.create_note(config.folder_id, title, String::new())?, true, None),
+        };
+
+        conn.execute(
+            "INSERT INTO periodic_notes (period, start_date, note_id) VALUES (?, ?, ?)",
+            params![period.as_str(), start_date, note.id],
+        )?;
+        Ok(PeriodicNote { period, start_date, note, created, cursor })
+    }
+
+    /// Everything a calendar needs for the days `from` through `to`: the daily note of
+    /// each day with its word count, the notes created or changed on it in local time,
+    /// and the weekly and monthly notes of the range.
+    pub fn list_periodic_notes(&self, from: NaiveDate, to: NaiveDate) -> Result<PeriodicCalendar> {
+        let day_count = (to - from).num_days() + 1;
+        if day_count < 1 {
+            return Err(AppError::invalid("to", "The range must end on or after its start"));
+        }
+        if day_count > MAX_CALENDAR_DAYS {
+            return Err(AppError::invalid("to", format!("The range can cover at most {} days", MAX_CALENDAR_DAYS)));
+        }
+
+        let mut days: Vec<CalendarDay> = from
+            .iter_days()
+            .take(day_count as usize)
+            .map(|date| CalendarDay {
+                date: date.format(DATE_FORMAT).to_string(),
+                daily_note: None,
+                created: Vec::new(),
+                updated: Vec::new(),
+            })
+            .collect();
+        let day_index = |millis: i64| -> Option<usize> {
+            let date = Local.timestamp_millis_opt(millis).single()?.date_naive();
+            let index = (date - from).num_days();
+            (0..day_count).contains(&index).then_some(index as usize)
+        };
+
+        let conn = self.db.connection();
+        let (start, end) = (local_midnight_millis(from), local_midnight_millis(to + chrono::Duration::days(1)));
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, folder_id, title, CASE WHEN encrypted THEN '' ELSE substr(content, 1, 200) END, updated_at, encrypted, created_at
+             FROM notes
+             WHERE (created_at >= ?1 AND created_at < ?2) OR (updated_at >= ?1 AND updated_at < ?2)
+             ORDER BY updated_at DESC",
+        )?;
+        let rows = stmt.query_map(params![start, end], |row| {
+            let summary = NoteSummary {
+                id: row.get(0)?,
+                folder_id: row.get(1)?,
+                title: row.get(2)?,
+                excerpt: row.get(3)?,
+                updated_at: row.get(4)?,
+                encrypted: row.get(5)?,
+            };
+            Ok((summary, row.get::<_, i64>(6)?))
+        })?;
+        for row in rows {
+            let (summary, created_at) = row?;
+            let created = day_index(created_at);
+            let updated = day_index(summary.updated_at);
+            match (created, updated) {
+                (Some(c), _) => days[c].created.push(summary),
+                (None, Some(u)) => days[u].updated.push(summary),
+                (None, None) => {}
+            }
+        }
+
+        // Weeks and months that started before `from` still overlap the range
+        let earliest = Period::Weekly.start_of(from).min(Period::Monthly.start_of(from));
+        let mut stmt = conn.prepare_cached(
+            "SELECT p.period, p.start_date, n.id, n.title, n.content, n.encrypted
+             FROM periodic_notes p JOIN notes n ON n.id = p.note_id
+             WHERE p.start_date >= ? AND p.start_date <= ?
+             ORDER BY p.start_date",
+        )?;
+        let rows = stmt.query_map(
+            params![earliest.format(DATE_FORMAT).to_string(), to.format(DATE_FORMAT).to_string()],
+            |row| {
+                let content: String = row.get(4)?;
+                let encrypted: bool = row.get(5)?;
+                let word_count = (!encrypted).then(|| content.split_whitespace().count());
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, word_count))
+            },
+        )?;
+
+        let (mut weeks, mut months) = (Vec::new(), Vec::new());
+        for row in rows {
+            let (period, start_date, note_id, title, word_count) = row?;
+            let (Some(period), Ok(start)) = (Period::parse(&period), parse_date("start_date", &start_date)) else {
+                continue;
+            };
+            if start < period.start_of(from) {
+                continue;
+            }
+            let summary = PeriodicNoteSummary { period, start_date, note_id, title, word_count };
+            match period {
+                Period::Daily => {
+                    if let Some(day) = days.get_mut((start - from).num_days() as usize) {
+                        day.daily_note = Some(summary);
+                    }
+                }
+                Period::Weekly => weeks.push(summary),
+                Period::Monthly => months.push(summary),
+            }
+        }
+
+        Ok(PeriodicCalendar { days, weeks, months })
+    }
+}
+
+/// Milliseconds since the epoch at the local midnight starting `date`.
+fn local_midnight_millis(date: NaiveDate) -> i64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    match Local.from_local_datetime(&midnight).earliest() {
+        Some(local) => local.timestamp_millis(),
+        // Midnight skipped by a DST change: the day starts an hour later
+        None => (Local.from_local_datetime(&(midnight + chrono::Duration::hours(1))))
+            .earliest()
+            .map(|local| local.timestamp_millis())
+            .unwrap_or_else(|| midnight.and_utc().timestamp_millis()),
+    }
+}
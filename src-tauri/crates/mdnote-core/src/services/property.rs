@@ -0,0 +1,453 @@
+use std::ops::Range;
+use std::sync::OnceLock;
+use chrono::NaiveDate;
+use regex::Regex;
+use rusqlite::{params, ToSql};
+use crate::errors::{AppError, Result};
+use crate::models::property::{NoteProperty, PropertyFilter, PropertyOp, PropertySort, PropertyValue};
+use crate::services::database::DatabaseService;
+
+pub const MAX_PROPERTY_KEY_LEN: usize = 64;
+
+fn key_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"^([^\s#:\-"'][^:]*?|"[^"]+"|'[^']+')\s*:(?:\s+(.*))?$"#).unwrap())
+}
+
+fn number_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^[-+]?(?:\d+(?:\.\d*)?|\.\d+)(?:[eE][-+]?\d+)?$").unwrap())
+}
+
+fn date_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap())
+}
+
+/// Where the front matter sits in a note: `body` is the YAML between the `---` lines and
+/// `end` is just past the closing line.
+struct FrontMatter {
+    body: Range<usize>,
+    end: usize,
+}
+
+/// A top-level key of the front matter. `span` covers its line and any indented or list
+/// lines below it, line endings included.
+struct Entry<'a> {
+    key: &'a str,
+    span: Range<usize>,
+    rest: &'a str,
+    continuation: Vec<&'a str>,
+}
+
+fn front_matter(content: &str) -> Option<FrontMatter> {
+    let mut lines = content.split_inclusive('\n');
+    let first = lines.next()?;
+    if first.trim_end() != "---" {
+        return None;
+    }
+
+    let mut offset = first.len();
+    for line in lines {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return Some(FrontMatter { body: first.len()..offset, end: offset + line.len() });
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn entries<'a>(content: &'a str, front: &FrontMatter) -> Vec<Entry<'a>> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut in_entry = false;
+    let mut offset = front.body.start;
+
+    for line in content[front.body.clone()].split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        let start = offset;
+        offset += line.len();
+
+        if text.trim().is_empty() || text.starts_with('#') {
+            continue;
+        }
+        if text.starts_with([' ', '\t']) || text == "-" || text.starts_with("- ") {
+            if let Some(entry) = entries.last_mut().filter(|_| in_entry) {
+                entry.continuation.push(text);
+                entry.span.end = offset;
+            }
+            continue;
+        }
+
+        in_entry = false;
+        if let Some(caps) = key_regex().captures(text) {
+            let key = caps.get(1).unwrap().as_str();
+            let key = key.strip_prefix(['"', '\'']).and_then(|k| k.strip_suffix(['"', '\''])).unwrap_or(key);
+            let rest = caps.get(2).map_or("", |m| m.as_str());
+            entries.push(Entry { key, span: start..offset, rest, continuation: Vec::new() });
+            in_entry = true;
+        }
+    }
+    entries
+}
+
+/// The text of a quoted YAML scalar, or `None` if `raw` isn't one.
+fn unquote(raw: &str) -> Option<String> {
+    if let Some(inner) = raw.strip_prefix('\'') {
+        let end = inner.rfind('\'')?;
+        return Some(inner[..end].replace("''", "'"));
+    }
+    let inner = raw.strip_prefix('"')?;
+    let mut text = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(text),
+            '\\' => match chars.next()? {
+                'n' => text.push('\n'),
+                't' => text.push('\t'),
+                other => text.push(other),
+            },
+            c => text.push(c),
+        }
+    }
+    None
+}
+
+/// An unquoted scalar without its trailing ` # comment`.
+fn strip_comment(raw: &str) -> &str {
+    match raw.find(" #") {
+        Some(at) => raw[..at].trim_end(),
+        None => raw.trim_end(),
+    }
+}
+
+fn is_null(raw: &str) -> bool {
+    raw.is_empty() || raw == "~" || raw.eq_ignore_ascii_case("null")
+}
+
+fn scalar(raw: &str) -> Option<PropertyValue> {
+    if let Some(text) = unquote(raw) {
+        return Some(PropertyValue::Text(text));
+    }
+    let raw = strip_comment(raw);
+    if is_null(raw) {
+        return None;
+    }
+    if raw.eq_ignore_ascii_case("true") || raw.eq_ignore_ascii_case("false") {
+        return Some(PropertyValue::Bool(raw.eq_ignore_ascii_case("true")));
+    }
+    if date_regex().is_match(raw) && NaiveDate::parse_from_str(raw, "%Y-%m-%d").is_ok() {
+        return Some(PropertyValue::Date(raw.to_string()));
+    }
+    if number_regex().is_match(raw) {
+        if let Ok(number) = raw.parse::<f64>() {
+            return Some(PropertyValue::Number(number));
+        }
+    }
+    Some(PropertyValue::Text(raw.to_string()))
+}
+
+/// A list item as text, whatever type it would have on its own.
+fn item(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    unquote(raw).or_else(|| {
+        let raw = strip_comment(raw);
+        (!is_null(raw)).then(|| raw.to_string())
+    })
+}
+
+/// Splits a flow list's items on the commas outside quotes.
+fn flow_items(inner: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let (mut start, mut quote) = (0, None);
+    for (i, c) in inner.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, ',') => {
+                items.extend(item(&inner[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.extend(item(&inner[start..]));
+    items
+}
+
+fn entry_value(entry: &Entry) -> Option<PropertyValue> {
+    let rest = entry.rest.trim();
+    if rest.starts_with('|') || rest.starts_with('>') {
+        let indent = entry.continuation.iter()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let lines: Vec<&str> = entry.continuation.iter().map(|l| l.get(indent..).unwrap_or("").trim_end()).collect();
+        let joined = if rest.starts_with('|') { lines.join("\n") } else { lines.join(" ") };
+        return Some(PropertyValue::Text(joined.trim_end().to_string()));
+    }
+    if let Some(inner) = rest.strip_prefix('[') {
+        return strip_comment(inner).strip_suffix(']').map(|inner| PropertyValue::List(flow_items(inner)));
+    }
+    if rest.starts_with('{') {
+        // Nested maps aren't properties
+        return None;
+    }
+    if rest.is_empty() || rest.starts_with('#') {
+        let items: Option<Vec<&str>> = entry.continuation.iter()
+            .map(|l| l.trim_start())
+            .map(|l| if l == "-" { Some("") } else { l.strip_prefix("- ") })
+            .collect();
+        return match items {
+            Some(items) if !items.is_empty() => Some(PropertyValue::List(items.into_iter().filter_map(item).collect())),
+            _ => None,
+        };
+    }
+
+    // A plain scalar wrapped over several lines
+    let mut raw = rest.to_string();
+    for line in &entry.continuation {
+        raw.push(' ');
+        raw.push_str(line.trim());
+    }
+    scalar(&raw)
+}
+
+/// The properties in the note's YAML front matter: the block between a `---` first line
+/// and the next `---` or `...` line. Only a subset of YAML is understood: top-level keys
+/// with plain or quoted scalars, `[flow]` or `- block` lists, and `|` / `>` text blocks.
+/// Nulls and nested maps are skipped, and of keys repeated in a different case the first wins.
+pub fn extract_properties(content: &str) -> Vec<NoteProperty> {
+    let Some(front) = front_matter(content) else { return Vec::new() };
+    let mut properties: Vec<NoteProperty> = Vec::new();
+    for entry in entries(content, &front) {
+        if properties.iter().any(|p| p.key.eq_ignore_ascii_case(entry.key)) {
+            continue;
+        }
+        if let Some(value) = entry_value(&entry) {
+            properties.push(NoteProperty { key: entry.key.to_string(), value });
+        }
+    }
+    properties
+}
+
+pub fn validate_property_key(key: &str) -> Result<()> {
+    if key.trim().is_empty() {
+        return Err(AppError::invalid("key", "Property names must not be empty"));
+    }
+    if key.chars().count() > MAX_PROPERTY_KEY_LEN {
+        return Err(AppError::invalid("key", format!("Property names are limited to {} characters", MAX_PROPERTY_KEY_LEN)));
+    }
+    if key != key.trim() || key.contains([':', '#', '\n', '\r', '"', '\'']) || key.starts_with('-') {
+        return Err(AppError::invalid("key", format!("{} can't be used as a property name", key)));
+    }
+    Ok(())
+}
+
+fn format_number(number: f64) -> String {
+    if number.fract() == 0.0 && number.abs() < 1e15 {
+        format!("{}", number as i64)
+    } else {
+        number.to_string()
+    }
+}
+
+/// `text` as a YAML scalar that reads back as the same text.
+fn quote(text: &str) -> String {
+    let plain = !text.is_empty()
+        && text == text.trim()
+        && !text.starts_with(['-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%', '@', '`'])
+        && !text.contains(": ")
+        && !text.contains(" #")
+        && !text.ends_with(':')
+        && !text.contains(|c: char| c.is_control())
+        && scalar(text) == Some(PropertyValue::Text(text.to_string()));
+    if plain {
+        return text.to_string();
+    }
+    let escaped = text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+fn render(key: &str, value: &PropertyValue, eol: &str) -> Result<String> {
+    let rendered = match value {
+        PropertyValue::Text(text) => quote(text),
+        PropertyValue::Number(number) if number.is_finite() => format_number(*number),
+        PropertyValue::Number(_) => return Err(AppError::invalid("value", "Numbers must be finite")),
+        PropertyValue::Date(date) => {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| AppError::invalid("value", format!("{} is not a YYYY-MM-DD date", date)))?;
+            date.clone()
+        }
+        PropertyValue::Bool(flag) => flag.to_string(),
+        PropertyValue::List(items) if items.is_empty() => "[]".to_string(),
+        PropertyValue::List(items) => {
+            let lines: String = items.iter().map(|item| format!("  - {}{}", quote(item), eol)).collect();
+            return Ok(format!("{}:{}{}", key, eol, lines));
+        }
+    };
+    Ok(format!("{}: {}{}", key, rendered, eol))
+}
+
+/// Sets `key` in the note's front matter to `value`, or removes it when `value` is `None`,
+/// leaving every other line of the note as it was. A note without front matter gets a
+/// block at the top, and removing the last property removes the block.
+pub fn set_front_matter_property(content: &str, key: &str, value: Option<&PropertyValue>) -> Result<String> {
+    validate_property_key(key)?;
+    let eol = if content.split('\n').next().is_some_and(|l| l.ends_with('\r')) { "\r\n" } else { "\n" };
+    let rendered = value.map(|v| render(key, v, eol)).transpose()?.unwrap_or_default();
+
+    let Some(front) = front_matter(content) else {
+        if rendered.is_empty() {
+            return Ok(content.to_string());
+        }
+        return Ok(format!("---{eol}{rendered}---{eol}{content}"));
+    };
+
+    let span = match entries(content, &front).into_iter().find(|e| e.key.eq_ignore_ascii_case(key)) {
+        Some(entry) => entry.span,
+        None if rendered.is_empty() => return Ok(content.to_string()),
+        None => front.body.end..front.body.end,
+    };
+
+    let mut updated = String::with_capacity(content.len() + rendered.len());
+    updated.push_str(&content[..span.start]);
+    updated.push_str(&rendered);
+    updated.push_str(&content[span.end..]);
+
+    let body_end = span.start + rendered.len() + (front.body.end - span.end);
+    if updated[front.body.start..body_end].trim().is_empty() {
+        return Ok(updated[body_end + (front.end - front.body.end)..].to_string());
+    }
+    Ok(updated)
+}
+
+/// How a property is stored: its type, its value as text, and a number to compare and
+/// sort numbers and bools by.
+fn stored(value: &PropertyValue) -> Result<(String, Option<f64>)> {
+    Ok(match value {
+        PropertyValue::Text(text) | PropertyValue::Date(text) => (text.clone(), None),
+        PropertyValue::Number(number) => (format_number(*number), Some(*number)),
+        PropertyValue::Bool(flag) => (flag.to_string(), Some(*flag as i64 as f64)),
+        PropertyValue::List(items) => (serde_json::to_string(items).map_err(|e| AppError::validation(e.to_string()))?, None),
+    })
+}
+
+fn property_exists(note_id_column: &str, condition: &str) -> String {
+    format!(
+        "EXISTS (SELECT 1 FROM note_properties p WHERE p.note_id = {} AND p.key = ?{})",
+        note_id_column, condition
+    )
+}
+
+/// A SQL condition on the note whose id is in `note_id_column`, with its parameters.
+pub(crate) fn property_filter_sql(filter: &PropertyFilter, note_id_column: &str) -> (String, Vec<Box<dyn ToSql>>) {
+    let number = filter.value.trim().parse::<f64>().ok().filter(|n| n.is_finite());
+    let flag = ["true", "false"].into_iter().find(|f| filter.value.eq_ignore_ascii_case(f));
+    let value = filter.value.clone();
+    let key: Box<dyn ToSql> = Box::new(filter.key.clone());
+
+    let equals = " AND CASE p.type
+        WHEN 'number' THEN p.number = ?
+        WHEN 'bool' THEN p.value = ?
+        WHEN 'list' THEN EXISTS (SELECT 1 FROM json_each(p.value) j WHERE lower(j.value) = lower(?))
+        ELSE lower(p.value) = lower(?) END";
+    let compare = |op: &str| format!(
+        " AND CASE p.type
+            WHEN 'number' THEN p.number {op} ?
+            WHEN 'date' THEN p.value {op} ?
+            WHEN 'text' THEN lower(p.value) {op} lower(?)
+            ELSE 0 END"
+    );
+
+    match filter.op {
+        PropertyOp::Exists => (property_exists(note_id_column, ""), vec![key]),
+        PropertyOp::Eq | PropertyOp::Ne => {
+            let sql = property_exists(note_id_column, equals);
+            let sql = if filter.op == PropertyOp::Ne { format!("NOT {}", sql) } else { sql };
+            (sql, vec![key, Box::new(number), Box::new(flag), Box::new(value.clone()), Box::new(value)])
+        }
+        PropertyOp::Contains => (
+            property_exists(
+                note_id_column,
+                " AND CASE p.type
+                    WHEN 'list' THEN EXISTS (SELECT 1 FROM json_each(p.value) j WHERE instr(lower(j.value), lower(?)) > 0)
+                    ELSE instr(lower(p.value), lower(?)) > 0 END",
+            ),
+            vec![key, Box::new(value.clone()), Box::new(value)],
+        ),
+        PropertyOp::Lt | PropertyOp::Lte | PropertyOp::Gt | PropertyOp::Gte => {
+            let op = match filter.op {
+                PropertyOp::Lt => "<",
+                PropertyOp::Lte => "<=",
+                PropertyOp::Gt => ">",
+                _ => ">=",
+            };
+            (
+                property_exists(note_id_column, &compare(op)),
+                vec![key, Box::new(number), Box::new(value.clone()), Box::new(value)],
+            )
+        }
+    }
+}
+
+/// ORDER BY terms sorting notes by a property, those without it last, with their parameters.
+pub(crate) fn property_sort_sql(sort: &PropertySort, note_id_column: &str) -> (String, Vec<Box<dyn ToSql>>) {
+    let direction = if sort.descending { "DESC" } else { "ASC" };
+    let lookup = |column: &str| format!(
+        "(SELECT {} FROM note_properties p WHERE p.note_id = {} AND p.key = ?)",
+        column, note_id_column
+    );
+    let sql = format!(
+        "{} IS NULL, {} {direction}, {} {direction}",
+        lookup("p.type"),
+        lookup("p.number"),
+        lookup("lower(p.value)"),
+    );
+    let params: Vec<Box<dyn ToSql>> = (0..3).map(|_| Box::new(sort.key.clone()) as Box<dyn ToSql>).collect();
+    (sql, params)
+}
+
+pub struct PropertyService<'a> {
+    db: &'a DatabaseService,
+}
+
+impl<'a> PropertyService<'a> {
+    pub fn new(db: &'a DatabaseService) -> Self {
+        Self { db }
+    }
+
+    /// Mirrors the front matter of `content` into `note_properties`. Unchanged rows are
+    /// left alone, like the task index.
+    pub fn sync_properties(&self, note_id: &str, content: &str) -> Result<()> {
+        let conn = self.db.connection();
+        let properties = extract_properties(content);
+
+        for (position, property) in properties.iter().enumerate() {
+            let (value, number) = stored(&property.value)?;
+            conn.execute(
+                "INSERT INTO note_properties (note_id, key, type, value, number, position) VALUES (?, ?, ?, ?, ?, ?)
+                 ON CONFLICT (note_id, key) DO UPDATE SET
+                     key = excluded.key, type = excluded.type, value = excluded.value,
+                     number = excluded.number, position = excluded.position",
+                params![note_id, property.key, property.value.type_name(), value, number, position as i64],
+            )?;
+        }
+
+        let keys: Vec<&str> = properties.iter().map(|p| p.key.as_str()).collect();
+        let keys = serde_json::to_string(&keys).map_err(|e| AppError::validation(e.to_string()))?;
+        conn.execute(
+            "DELETE FROM note_properties WHERE note_id = ? AND key NOT IN (SELECT value FROM json_each(?))",
+            params![note_id, keys],
+        )?;
+        Ok(())
+    }
+
+    /// Drops the note's properties from the index, e.g. once its content is encrypted.
+    pub fn clear_properties(&self, note_id: &str) -> Result<()> {
+        self.db.connection().execute("DELETE FROM note_properties WHERE note_id = ?", params![note_id])?;
+        Ok(())
+    }
+}
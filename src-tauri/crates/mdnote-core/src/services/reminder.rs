@@ -0,0 +1,311 @@
+use std::sync::Arc;
+use std::time::Duration;
+use rusqlite::{params, OptionalExtension, Row};
+use ulid::Ulid;
+use crate::errors::{AppError, Result};
+use crate::models::reminder::{DueReminder, Reminder, ReminderDraft};
+use crate::services::database::{DatabaseService, DbState};
+use crate::services::events::ChangeEvent;
+
+/// The longest the scheduler sleeps between checks, so reminders created or snoozed
+/// meanwhile fire at most this late.
+pub const REMINDER_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Reminders firing later than this after their time are reported as missed.
+pub const MISSED_AFTER: Duration = Duration::from_secs(2 * 60);
+
+const MAX_REMINDER_MESSAGE_LEN: usize = 500;
+
+/// The reminder columns, then whether its task is done.
+const REMINDER_QUERY: &str =
+    "SELECT r.id, r.note_id, n.title, r.task_line, t.text, r.message, r.remind_at, r.fired_at, r.created_at, t.done
+     FROM reminders r
+     JOIN notes n ON n.id = r.note_id
+     LEFT JOIN tasks t ON t.note_id = r.note_id AND t.line = r.task_line";
+
+/// Where reminder times come from. Tests swap in a clock they move by hand.
+pub trait Clock: Send + Sync {
+    /// Milliseconds since the epoch.
+    fn now_millis(&self) -> i64;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> i64 {
+        chrono::Utc::now().timestamp_millis()
+    }
+}
+
+/// Receives each reminder as it fires, e.g. to show a notification.
+pub trait ReminderSink: Send + Sync {
+    fn notify(&self, reminder: &DueReminder);
+}
+
+impl<F: Fn(&DueReminder) + Send + Sync> ReminderSink for F {
+    fn notify(&self, reminder: &DueReminder) {
+        self(reminder)
+    }
+}
+
+pub struct ReminderService<'a> {
+    db: &'a DatabaseService,
+    clock: &'a dyn Clock,
+}
+
+impl<'a> ReminderService<'a> {
+    pub fn new(db: &'a DatabaseService) -> Self {
+        Self { db, clock: &SystemClock }
+    }
+
+    /// Uses `clock` instead of the system time.
+    pub fn with_clock(mut self, clock: &'a dyn Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Schedules a reminder for a note, or for one of its tasks when `task_line` is set.
+    pub fn create_reminder(&self, draft: ReminderDraft) -> Result<Reminder> {
+        let now = self.clock.now_millis();
+        if draft.remind_at <= now {
+            return Err(AppError::invalid("remind_at", "Reminders must be set for a time in the future"));
+        }
+        let message = draft.message.map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+        if message.as_ref().is_some_and(|m| m.chars().count() > MAX_REMINDER_MESSAGE_LEN) {
+            return Err(AppError::invalid(
+                "message",
+                format!("Reminder messages are limited to {} characters", MAX_REMINDER_MESSAGE_LEN),
+            ));
+        }
+
+        let conn = self.db.connection();
+        let note_exists: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM notes WHERE id = ?)",
+            params![draft.note_id],
+            |row| row.get(0),
+        )?;
+        if !note_exists {
+            return Err(AppError::NotFound(format!("Note with id {} not found", draft.note_id)));
+        }
+        if let Some(line) = draft.task_line {
+            let task_exists: bool = conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM tasks WHERE note_id = ? AND line = ?)",
+                params![draft.note_id, line as i64],
+                |row| row.get(0),
+            )?;
+            if !task_exists {
+                return Err(AppError::NotFound(format!("Note {} has no task on line {}", draft.note_id, line)));
+            }
+        }
+
+        let id = Ulid::new().to_string();
+        conn.execute(
+            "INSERT INTO reminders (id, note_id, task_line, message, remind_at, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+            params![id, draft.note_id, draft.task_line.map(|l| l as i64), message, draft.remind_at, now],
+        )?;
+        self.db.record(ChangeEvent::ReminderChanged { id: id.clone(), note_id: draft.note_id });
+        self.get_reminder(&id)
+    }
+
+    pub fn get_reminder(&self, id: &str) -> Result<Reminder> {
+        let conn = self.db.connection();
+        conn.query_row(
+            &format!("{} WHERE r.id = ?", REMINDER_QUERY),
+            params![id],
+            Self::map_row_to_reminder,
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Reminder with id {} not found", id)))
+    }
+
+    /// Moves the reminder to `until`, so it fires again even if it already did.
+    pub fn snooze_reminder(&self, id: String, until: i64) -> Result<Reminder> {
+        if until <= self.clock.now_millis() {
+            return Err(AppError::invalid("until", "Reminders can only be snoozed to a time in the future"));
+        }
+        let conn = self.db.connection();
+        let note_id: String = conn
+            .query_row(
+                "UPDATE reminders SET remind_at = ?, fired_at = NULL, delivered_at = NULL WHERE id = ? RETURNING note_id",
+                params![until, id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| AppError::NotFound(format!("Reminder with id {} not found", id)))?;
+        self.db.record(ChangeEvent::ReminderChanged { id: id.clone(), note_id });
+        self.get_reminder(&id)
+    }
+
+    pub fn delete_reminder(&self, id: String) -> Result<()> {
+        let conn = self.db.connection();
+        let note_id: String = conn
+            .query_row("DELETE FROM reminders WHERE id = ? RETURNING note_id", params![id], |row| row.get(0))
+            .optional()?
+            .ok_or_else(|| AppError::NotFound(format!("Reminder with id {} not found", id)))?;
+        self.db.record(ChangeEvent::ReminderChanged { id, note_id });
+        Ok(())
+    }
+
+    /// Reminders that haven't fired yet, soonest first, including overdue ones the
+    /// scheduler hasn't picked up.
+    pub fn list_upcoming_reminders(&self, limit: i64) -> Result<Vec<Reminder>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached(&format!(
+            "{} WHERE r.fired_at IS NULL ORDER BY r.remind_at, r.id LIMIT ?",
+            REMINDER_QUERY
+        ))?;
+        let reminders = stmt
+            .query_map(params![limit], Self::map_row_to_reminder)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(reminders)
+    }
+
+    /// Marks every reminder due by now as fired and returns them, oldest first. Reminders
+    /// for tasks that were checked off meanwhile are marked fired and delivered without
+    /// being returned.
+    pub fn take_due_reminders(&self) -> Result<Vec<DueReminder>> {
+        let now = self.clock.now_millis();
+        let conn = self.db.connection();
+        let tx = conn.unchecked_transaction()?;
+
+        let mut stmt = conn.prepare_cached(&format!(
+            "{} WHERE r.fired_at IS NULL AND r.remind_at <= ? ORDER BY r.remind_at, r.id",
+            REMINDER_QUERY
+        ))?;
+        let due = stmt
+            .query_map(params![now], |row| {
+                let done: Option<bool> = row.get(9)?;
+                Ok((Self::map_row_to_reminder(row)?, done.unwrap_or(false)))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut fired = Vec::with_capacity(due.len());
+        for (mut reminder, task_done) in due {
+            tx.execute(
+                "UPDATE reminders SET fired_at = ?, delivered_at = ? WHERE id = ?",
+                params![now, task_done.then_some(now), reminder.id],
+            )?;
+            if task_done {
+                continue;
+            }
+            let missed = now - reminder.remind_at > MISSED_AFTER.as_millis() as i64;
+            reminder.fired_at = Some(now);
+            fired.push(DueReminder { reminder, missed });
+        }
+        tx.commit()?;
+        Ok(fired)
+    }
+
+    /// Reminders that fired but were never acknowledged, oldest first, e.g. because they
+    /// fired before the frontend started listening. Reminders for tasks checked off since
+    /// are left out.
+    pub fn list_missed_reminders(&self) -> Result<Vec<DueReminder>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached(&format!(
+            "{} WHERE r.fired_at IS NOT NULL AND r.delivered_at IS NULL AND NOT IFNULL(t.done, 0)
+             ORDER BY r.remind_at, r.id",
+            REMINDER_QUERY
+        ))?;
+        let missed = stmt
+            .query_map([], Self::map_row_to_reminder)?
+            .map(|reminder| {
+                reminder.map(|reminder| {
+                    let late = reminder.fired_at.unwrap_or(reminder.remind_at) - reminder.remind_at;
+                    DueReminder { missed: late > MISSED_AFTER.as_millis() as i64, reminder }
+                })
+            })
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(missed)
+    }
+
+    /// Records that the frontend has shown these fired reminders, so
+    /// `list_missed_reminders` stops returning them.
+    pub fn acknowledge_reminders(&self, ids: &[String]) -> Result<()> {
+        let now = self.clock.now_millis();
+        let conn = self.db.connection();
+        let tx = conn.unchecked_transaction()?;
+        for id in ids {
+            tx.execute(
+                "UPDATE reminders SET delivered_at = ? WHERE id = ? AND fired_at IS NOT NULL AND delivered_at IS NULL",
+                params![now, id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// When the next pending reminder is due, if any.
+    pub fn next_reminder_at(&self) -> Result<Option<i64>> {
+        let next = self.db.connection().query_row(
+            "SELECT MIN(remind_at) FROM reminders WHERE fired_at IS NULL",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(next)
+    }
+
+    fn map_row_to_reminder(row: &Row) -> rusqlite::Result<Reminder> {
+        let task_line: Option<i64> = row.get(3)?;
+        Ok(Reminder {
+            id: row.get(0)?,
+            note_id: row.get(1)?,
+            note_title: row.get(2)?,
+            task_line: task_line.map(|l| l as usize),
+            task_text: row.get(4)?,
+            message: row.get(5)?,
+            remind_at: row.get(6)?,
+            fired_at: row.get(7)?,
+            created_at: row.get(8)?,
+        })
+    }
+}
+
+/// Fires due reminders against a clock. Split from the thread that drives it so tests
+/// can step through time.
+pub struct ReminderScheduler {
+    clock: Arc<dyn Clock>,
+    sink: Arc<dyn ReminderSink>,
+}
+
+impl ReminderScheduler {
+    pub fn new(clock: Arc<dyn Clock>, sink: Arc<dyn ReminderSink>) -> Self {
+        Self { clock, sink }
+    }
+
+    /// Fires everything due, including reminders missed while the app was closed, and
+    /// returns how long to wait before the next check. The reminders are marked fired
+    /// before the sink hears about them, so none fires twice; until the sink's listener
+    /// acknowledges them they are also listed by `list_missed_reminders`.
+    pub fn tick(&self, db: &DatabaseService) -> Result<Duration> {
+        let service = ReminderService::new(db).with_clock(self.clock.as_ref());
+        for reminder in service.take_due_reminders()? {
+            self.sink.notify(&reminder);
+        }
+
+        let wait = match service.next_reminder_at()? {
+            Some(at) => Duration::from_millis((at - self.clock.now_millis()).max(0) as u64),
+            None => REMINDER_CHECK_INTERVAL,
+        };
+        Ok(wait.min(REMINDER_CHECK_INTERVAL))
+    }
+}
+
+/// Starts a background thread that fires the open vault's reminders through `scheduler`.
+/// The first check runs right away, which catches up on reminders missed while closed,
+/// usually before any window listens; those stay listed by `list_missed_reminders`.
+pub fn spawn_reminder_scheduler(state: DbState, scheduler: ReminderScheduler) {
+    std::thread::spawn(move || loop {
+        let result = state.pool().and_then(|pool| pool.with_writer(|db| scheduler.tick(db)));
+        let wait = match result {
+            Ok(wait) => wait,
+            Err(AppError::NotFound(_)) | Err(AppError::Locked(_)) => REMINDER_CHECK_INTERVAL,
+            Err(e) => {
+                tracing::warn!("Checking reminders failed: {}", e);
+                REMINDER_CHECK_INTERVAL
+            }
+        };
+        // A reminder due right now was just fired; don't spin on one that is a few ms out
+        std::thread::sleep(wait.max(Duration::from_millis(200)));
+    });
+}
@@ -0,0 +1,91 @@
+use serde::Serialize;
+use crate::models::property::PropertyQuery;
+use crate::services::database::DatabaseService;
+use crate::services::folder::folder_scope_sql;
+use crate::services::property::{property_filter_sql, property_sort_sql};
+use crate::errors::Result;
+
+#[derive(Serialize)]
+pub struct SearchResult {
+    pub id: String,
+    pub title: String,
+    pub snippet: String,
+    pub rank: f64,
+}
+
+pub struct SearchService<'a> {
+    db: &'a DatabaseService,
+}
+
+impl<'a> SearchService<'a> {
+    pub fn new(db: &'a DatabaseService) -> Self {
+        Self { db }
+    }
+
+    pub fn search_notes(&self, query: String, limit: i32) -> Result<Vec<SearchResult>> {
+        self.search_notes_with_properties(query, &PropertyQuery::default(), limit)
+    }
+
+    /// Like `search_notes`, keeping only matches whose properties pass the filters. With a
+    /// property sort, results are ordered by it first and by rank after.
+    pub fn search_notes_with_properties(&self, query: String, properties: &PropertyQuery, limit: i32) -> Result<Vec<SearchResult>> {
+        self.search(query, properties, None, limit)
+    }
+
+    /// Like `search_notes`, limited to notes directly in one of `folders` (`None` being
+    /// the root). The limit applies after the folder filter.
+    pub fn search_notes_in_folders(&self, query: String, folders: &[Option<String>], limit: i32) -> Result<Vec<SearchResult>> {
+        self.search(query, &PropertyQuery::default(), Some(folders), limit)
+    }
+
+    fn search(&self, query: String, properties: &PropertyQuery, folders: Option<&[Option<String>]>, limit: i32) -> Result<Vec<SearchResult>> {
+        let conn = self.db.connection();
+
+        // FTS5 search with BM25 ranking and snippet generation
+        // We use highlight() to mark matches in the snippet
+        let mut sql = String::from(
+            "SELECT id, title, snippet(notes_fts, 2, '==', '==', '...', 64) as snippet, rank
+             FROM notes_fts
+             WHERE notes_fts MATCH ?"
+        );
+        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(query)];
+        if let Some(folders) = folders {
+            let (clause, params) = folder_scope_sql(folders, "folder_id");
+            sql.push_str(&format!(" AND notes_fts.id IN (SELECT id FROM notes WHERE {})", clause));
+            params_vec.extend(params);
+        }
+        for filter in &properties.filters {
+            let (clause, params) = property_filter_sql(filter, "notes_fts.id");
+            sql.push_str(" AND ");
+            sql.push_str(&clause);
+            params_vec.extend(params);
+        }
+        sql.push_str(" ORDER BY ");
+        if let Some(sort) = &properties.sort {
+            let (order, params) = property_sort_sql(sort, "notes_fts.id");
+            sql.push_str(&order);
+            sql.push_str(", ");
+            params_vec.extend(params);
+        }
+        sql.push_str("rank LIMIT ?");
+        params_vec.push(Box::new(limit));
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+        let mut stmt = conn.prepare_cached(&sql)?;
+        let search_results = stmt.query_map(params_refs.as_slice(), |row| {
+            Ok(SearchResult {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                snippet: row.get(2)?,
+                rank: row.get(3)?,
+            })
+        })?;
+
+        let mut results = Vec::new();
+        for result in search_results {
+            results.push(result?);
+        }
+
+        Ok(results)
+    }
+}
@@ -0,0 +1,105 @@
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rusqlite::params;
+use crate::models::settings::Settings;
+use crate::services::database::DatabaseService;
+use crate::services::periodic::validate_title_format;
+use crate::errors::{AppError, Result};
+
+pub struct SettingsService<'a> {
+    db: &'a DatabaseService,
+}
+
+impl<'a> SettingsService<'a> {
+    pub fn new(db: &'a DatabaseService) -> Self {
+        Self { db }
+    }
+
+    pub fn get_settings(&self) -> Result<Settings> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached("SELECT key, value FROM settings")?;
+        let rows = stmt.query_map([], |row| {
+            let key: String = row.get(0)?;
+            let value: String = row.get(1)?;
+            Ok((key, value))
+        })?;
+
+        let mut settings = Settings::default();
+        for row in rows {
+            let (key, value) = row?;
+            match key.as_str() {
+                "theme" => settings.theme = value,
+                "font_size" => if let Ok(v) = value.parse() { settings.font_size = v },
+                "font_family" => settings.font_family = value,
+                "auto_save_delay" => if let Ok(v) = value.parse() { settings.auto_save_delay = v },
+                "spell_check" => settings.spell_check = value == "true",
+                "backup_enabled" => settings.backup_enabled = value == "true",
+                "backup_interval_hours" => if let Ok(v) = value.parse() { settings.backup_interval_hours = v },
+                "backup_retention" => if let Ok(v) = value.parse() { settings.backup_retention = v },
+                "api_enabled" => settings.api_enabled = value == "true",
+                "api_port" => if let Ok(v) = value.parse() { settings.api_port = v },
+                "api_token" => settings.api_token = Some(value),
+                "api_allowed_origins" => if let Ok(v) = serde_json::from_str(&value) { settings.api_allowed_origins = v },
+                "periodic_notes" => if let Ok(v) = serde_json::from_str(&value) { settings.periodic_notes = v },
+                _ => {}
+            }
+        }
+
+        Ok(settings)
+    }
+
+    /// Saves every setting except `api_token`, which is returned as currently stored.
+    pub fn update_settings(&self, mut settings: Settings) -> Result<Settings> {
+        for period in [&settings.periodic_notes.daily, &settings.periodic_notes.weekly, &settings.periodic_notes.monthly] {
+            validate_title_format(&period.title_format)?;
+        }
+        let conn = self.db.connection();
+        
+        let tx = conn.unchecked_transaction()?;
+        
+        Self::save_setting(&tx, "theme", &settings.theme)?;
+        Self::save_setting(&tx, "font_size", &settings.font_size.to_string())?;
+        Self::save_setting(&tx, "font_family", &settings.font_family)?;
+        Self::save_setting(&tx, "auto_save_delay", &settings.auto_save_delay.to_string())?;
+        Self::save_setting(&tx, "spell_check", if settings.spell_check { "true" } else { "false" })?;
+        Self::save_setting(&tx, "backup_enabled", if settings.backup_enabled { "true" } else { "false" })?;
+        Self::save_setting(&tx, "backup_interval_hours", &settings.backup_interval_hours.to_string())?;
+        Self::save_setting(&tx, "backup_retention", &settings.backup_retention.to_string())?;
+        Self::save_setting(&tx, "api_enabled", if settings.api_enabled { "true" } else { "false" })?;
+        Self::save_setting(&tx, "api_port", &settings.api_port.to_string())?;
+        let origins = serde_json::to_string(&settings.api_allowed_origins).map_err(|e| AppError::validation(e.to_string()))?;
+        Self::save_setting(&tx, "api_allowed_origins", &origins)?;
+        let periodic = serde_json::to_string(&settings.periodic_notes).map_err(|e| AppError::validation(e.to_string()))?;
+        Self::save_setting(&tx, "periodic_notes", &periodic)?;
+        
+        tx.commit()?;
+
+        settings.api_token = self.get_settings()?.api_token;
+        Ok(settings)
+    }
+
+    /// The API bearer token, generating one the first time it is needed.
+    pub fn ensure_api_token(&self) -> Result<String> {
+        match self.get_settings()?.api_token {
+            Some(token) => Ok(token),
+            None => self.regenerate_api_token(),
+        }
+    }
+
+    /// Replaces the API bearer token with a new random one; the old one stops working at once.
+    pub fn regenerate_api_token(&self) -> Result<String> {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token = hex::encode(bytes);
+        Self::save_setting(self.db.connection(), "api_token", &token)?;
+        Ok(token)
+    }
+
+    fn save_setting(conn: &rusqlite::Connection, key: &str, value: &str) -> rusqlite::Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+}
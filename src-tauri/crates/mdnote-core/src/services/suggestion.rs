@@ -0,0 +1,249 @@
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashMap;
+use crate::services::database::DatabaseService;
+use crate::services::note::NoteService;
+use crate::errors::Result;
+
+/// How much each signal contributes to a suggestion's score.
+const COOCCURRENCE_WEIGHT: f64 = 0.5;
+const TERM_OVERLAP_WEIGHT: f64 = 0.3;
+const LINKED_WEIGHT: f64 = 0.2;
+
+/// Distinctive words taken from a note to find similar notes through `notes_fts`.
+const MAX_QUERY_TERMS: usize = 8;
+const MAX_SIMILAR_NOTES: i32 = 50;
+
+const STOPWORDS: &[&str] = &[
+    "about", "after", "again", "also", "been", "before", "being", "could", "does", "doing",
+    "from", "have", "having", "here", "into", "just", "more", "most", "much", "only", "other",
+    "over", "same", "should", "some", "such", "than", "that", "their", "them", "then", "there",
+    "these", "they", "this", "those", "through", "very", "were", "what", "when", "where",
+    "which", "while", "will", "with", "would", "your",
+];
+
+#[derive(Serialize)]
+pub struct TagSuggestion {
+    pub id: String,
+    pub name: String,
+    pub color: String,
+    pub score: f64,
+    /// Share of this tag's notes that also carry one of the note's current tags.
+    pub cooccurrence: f64,
+    /// Share of textually similar notes (via full-text search) that carry this tag.
+    pub term_overlap: f64,
+    /// Share of notes linked to or from this note that carry this tag.
+    pub linked: f64,
+}
+
+#[derive(Serialize)]
+pub struct TagCooccurrence {
+    pub tag_a_id: String,
+    pub tag_a_name: String,
+    pub tag_b_id: String,
+    pub tag_b_name: String,
+    pub shared_notes: i64,
+    /// Shared notes divided by the notes carrying either tag; close to 1.0 means redundant.
+    pub jaccard: f64,
+}
+
+#[derive(Default)]
+struct Signals {
+    cooccurrence: f64,
+    term_overlap: f64,
+    linked: f64,
+}
+
+pub struct SuggestionService<'a> {
+    db: &'a DatabaseService,
+}
+
+impl<'a> SuggestionService<'a> {
+    pub fn new(db: &'a DatabaseService) -> Self {
+        Self { db }
+    }
+
+    /// Ranks tags the note does not carry yet by co-occurrence with its current tags,
+    /// term overlap with notes carrying each tag and tags on linked notes.
+    pub fn suggest_tags(&self, note_id: String, limit: i32) -> Result<Vec<TagSuggestion>> {
+        let note = NoteService::new(self.db).get_note(note_id.clone())?;
+        let mut signals: HashMap<String, Signals> = HashMap::new();
+
+        for (tag_id, share) in self.cooccurring_tags(&note_id)? {
+            signals.entry(tag_id).or_default().cooccurrence = share;
+        }
+
+        let similar = self.similar_notes(&note_id, &format!("{} {}", note.title, note.content))?;
+        for (tag_id, share) in self.tag_shares(&similar)? {
+            signals.entry(tag_id).or_default().term_overlap = share;
+        }
+
+        let linked = self.linked_notes(&note_id)?;
+        for (tag_id, share) in self.tag_shares(&linked)? {
+            signals.entry(tag_id).or_default().linked = share;
+        }
+
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached(
+            "SELECT t.id, t.name, t.color FROM tags t
+             WHERE NOT EXISTS (SELECT 1 FROM note_tags nt WHERE nt.note_id = ? AND nt.tag_id = t.id)"
+        )?;
+        let candidates = stmt.query_map(params![note_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        })?;
+
+        let mut suggestions = Vec::new();
+        for candidate in candidates {
+            let (id, name, color) = candidate?;
+            let Some(s) = signals.get(&id) else { continue };
+            let score = COOCCURRENCE_WEIGHT * s.cooccurrence
+                + TERM_OVERLAP_WEIGHT * s.term_overlap
+                + LINKED_WEIGHT * s.linked;
+            if score > 0.0 {
+                suggestions.push(TagSuggestion {
+                    id,
+                    name,
+                    color,
+                    score,
+                    cooccurrence: s.cooccurrence,
+                    term_overlap: s.term_overlap,
+                    linked: s.linked,
+                });
+            }
+        }
+
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+        suggestions.truncate(limit.max(0) as usize);
+        Ok(suggestions)
+    }
+
+    /// Pairs of tags that appear on the same notes, most overlapping first.
+    pub fn get_tag_cooccurrence(&self, min_shared: i64, limit: i32) -> Result<Vec<TagCooccurrence>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached(
+            "WITH counts AS (SELECT tag_id, COUNT(*) AS total FROM note_tags GROUP BY tag_id),
+                  pairs AS (
+                      SELECT a.tag_id AS a_id, b.tag_id AS b_id, COUNT(*) AS shared
+                      FROM note_tags a
+                      JOIN note_tags b ON a.note_id = b.note_id AND a.tag_id < b.tag_id
+                      GROUP BY a.tag_id, b.tag_id
+                  )
+             SELECT p.a_id, ta.name, p.b_id, tb.name, p.shared,
+                    CAST(p.shared AS REAL) / (ca.total + cb.total - p.shared) AS jaccard
+             FROM pairs p
+             JOIN tags ta ON ta.id = p.a_id
+             JOIN tags tb ON tb.id = p.b_id
+             JOIN counts ca ON ca.tag_id = p.a_id
+             JOIN counts cb ON cb.tag_id = p.b_id
+             WHERE p.shared >= ?
+             ORDER BY jaccard DESC, p.shared DESC
+             LIMIT ?"
+        )?;
+
+        let pair_iter = stmt.query_map(params![min_shared, limit], |row| {
+            Ok(TagCooccurrence {
+                tag_a_id: row.get(0)?,
+                tag_a_name: row.get(1)?,
+                tag_b_id: row.get(2)?,
+                tag_b_name: row.get(3)?,
+                shared_notes: row.get(4)?,
+                jaccard: row.get(5)?,
+            })
+        })?;
+
+        let mut pairs = Vec::new();
+        for pair in pair_iter {
+            pairs.push(pair?);
+        }
+
+        Ok(pairs)
+    }
+
+    /// For every tag sharing a note with one of `note_id`'s tags, the share of that tag's
+    /// notes (other than this one) which also carry one of them.
+    fn cooccurring_tags(&self, note_id: &str) -> Result<Vec<(String, f64)>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached(
+            "SELECT other.tag_id,
+                    CAST(COUNT(DISTINCT other.note_id) AS REAL)
+                        / (SELECT COUNT(*) FROM note_tags c WHERE c.tag_id = other.tag_id AND c.note_id <> ?1)
+             FROM note_tags mine
+             JOIN note_tags shared ON shared.tag_id = mine.tag_id AND shared.note_id <> ?1
+             JOIN note_tags other ON other.note_id = shared.note_id AND other.tag_id <> mine.tag_id
+             WHERE mine.note_id = ?1
+             GROUP BY other.tag_id"
+        )?;
+
+        let rows = stmt.query_map(params![note_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Notes that match the most frequent distinctive words of `text` in `notes_fts`.
+    fn similar_notes(&self, note_id: &str, text: &str) -> Result<Vec<String>> {
+        let terms = distinctive_terms(text, MAX_QUERY_TERMS);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query = terms.iter().map(|t| format!("\"{}\"", t)).collect::<Vec<_>>().join(" OR ");
+
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached(
+            "SELECT id FROM notes_fts WHERE notes_fts MATCH ? AND id <> ? ORDER BY rank LIMIT ?"
+        )?;
+        let rows = stmt.query_map(params![query, note_id, MAX_SIMILAR_NOTES], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Notes linked from or to `note_id` through `backlinks`.
+    fn linked_notes(&self, note_id: &str) -> Result<Vec<String>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached(
+            "SELECT target_id FROM backlinks WHERE source_id = ?1
+             UNION
+             SELECT source_id FROM backlinks WHERE target_id = ?1"
+        )?;
+        let rows = stmt.query_map(params![note_id], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// For each tag on any of `note_ids`, the share of those notes that carry it.
+    fn tag_shares(&self, note_ids: &[String]) -> Result<Vec<(String, f64)>> {
+        if note_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.db.connection();
+        let placeholders = vec!["?"; note_ids.len()].join(", ");
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT tag_id, COUNT(DISTINCT note_id) FROM note_tags WHERE note_id IN ({}) GROUP BY tag_id",
+            placeholders
+        ))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(note_ids), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+
+        let total = note_ids.len() as f64;
+        let mut shares = Vec::new();
+        for row in rows {
+            let (tag_id, count) = row?;
+            shares.push((tag_id, count as f64 / total));
+        }
+        Ok(shares)
+    }
+}
+
+/// The `max` most frequent words of at least four letters, skipping common stopwords.
+fn distinctive_terms(text: &str, max: usize) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let word = word.to_lowercase();
+        if word.chars().count() < 4 || word.chars().all(|c| c.is_numeric()) || STOPWORDS.contains(&word.as_str()) {
+            continue;
+        }
+        *counts.entry(word).or_default() += 1;
+    }
+
+    let mut terms: Vec<(String, usize)> = counts.into_iter().collect();
+    terms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    terms.into_iter().take(max).map(|(term, _)| term).collect()
+}
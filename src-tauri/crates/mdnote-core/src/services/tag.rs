@@ -0,0 +1,645 @@
+use rusqlite::{params, OptionalExtension};
+use ulid::Ulid;
+use crate::models::tag::Tag;
+use crate::services::database::DatabaseService;
+use crate::services::events::ChangeEvent;
+use crate::services::folder::folder_scope_sql;
+use crate::services::hashtag::{extract_hashtags, rewrite_hashtag};
+use crate::services::property::PropertyService;
+use crate::services::task::TaskService;
+use crate::errors::{AppError, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// Selects the id of the tag bound to `?` plus the ids of every tag nested below it
+/// (`project` matches `project/alpha` and `Project/Alpha/backend`, but not `projects`).
+/// Names compare without case, like the unique index on `tags.name`.
+pub const TAG_AND_DESCENDANTS_SQL: &str =
+    "SELECT d.id FROM tags d JOIN tags p ON p.id = ?
+     WHERE d.id = p.id OR substr(d.name, 1, length(p.name) + 1) = p.name || '/' COLLATE NOCASE";
+
+pub const MAX_TAG_NAME_LEN: usize = 64;
+pub const MAX_TAG_ICON_LEN: usize = 32;
+pub const MAX_TAG_DESCRIPTION_LEN: usize = 500;
+
+/// Named colors a tag can use instead of a hex value. New tags without an explicit
+/// color are given the least used entry, so neighbouring tags stay distinguishable.
+pub const TAG_PALETTE: &[(&str, &str)] = &[
+    ("blue", "#3b82f6"),
+    ("red", "#ef4444"),
+    ("green", "#22c55e"),
+    ("amber", "#f59e0b"),
+    ("purple", "#a855f7"),
+    ("pink", "#ec4899"),
+    ("teal", "#14b8a6"),
+    ("orange", "#f97316"),
+    ("indigo", "#6366f1"),
+    ("cyan", "#06b6d4"),
+    ("lime", "#84cc16"),
+    ("slate", "#64748b"),
+];
+
+#[derive(Serialize)]
+pub struct PaletteColor {
+    pub name: String,
+    pub hex: String,
+}
+
+#[derive(Serialize)]
+pub struct TagWithCount {
+    pub id: String,
+    pub name: String,
+    pub color: String,
+    pub icon: Option<String>,
+    pub description: Option<String>,
+    pub note_count: i64,
+}
+
+/// A node in the hierarchy built from `/`-separated tag names.
+/// Intermediate segments that were never created as tags have no `id`.
+#[derive(Serialize)]
+pub struct TagTreeNode {
+    pub id: Option<String>,
+    pub name: String,
+    pub path: String,
+    pub color: Option<String>,
+    /// Notes tagged with exactly this tag.
+    pub note_count: i64,
+    /// Distinct notes tagged with this tag or any of its descendants.
+    pub total_count: i64,
+    pub children: Vec<TagTreeNode>,
+}
+
+#[derive(Default)]
+struct TagTreeEntry {
+    /// The path as spelled by the tag itself, or by the first descendant seen.
+    path: String,
+    id: Option<String>,
+    color: Option<String>,
+    direct_notes: HashSet<String>,
+    rollup_notes: HashSet<String>,
+}
+
+/// Splits a tag name into its trimmed, non-empty path segments.
+pub fn tag_path_segments(name: &str) -> Vec<&str> {
+    name.split('/').map(str::trim).filter(|s| !s.is_empty()).collect()
+}
+
+/// Trims a tag name and its `/` segments, rejecting empty, over-long and comma-containing names.
+pub fn validate_tag_name(name: &str) -> Result<String> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err(AppError::invalid("name", "Tag name must not be empty"));
+    }
+    if trimmed.contains(',') {
+        return Err(AppError::invalid("name", "Tag name must not contain commas"));
+    }
+    if trimmed.split('/').any(|segment| segment.trim().is_empty()) {
+        return Err(AppError::invalid("name", format!("Tag name {} has an empty path segment", trimmed)));
+    }
+
+    let normalized = tag_path_segments(trimmed).join("/");
+    if normalized.chars().count() > MAX_TAG_NAME_LEN {
+        return Err(AppError::invalid("name", format!(
+            "Tag name must be at most {} characters",
+            MAX_TAG_NAME_LEN
+        )));
+    }
+    Ok(normalized)
+}
+
+/// Accepts `#rgb`, `#rrggbb` or a palette name and returns the lowercase `#rrggbb` value.
+pub fn validate_tag_color(color: &str) -> Result<String> {
+    let trimmed = color.trim().to_lowercase();
+    if let Some((_, hex)) = TAG_PALETTE.iter().find(|(name, _)| *name == trimmed) {
+        return Ok(hex.to_string());
+    }
+
+    let digits = trimmed.strip_prefix('#').unwrap_or("");
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::invalid("color", format!(
+            "Tag color {} is neither a hex color nor a palette name",
+            color
+        )));
+    }
+    // The UI appends alpha digits to the stored color, so short forms are expanded
+    if digits.len() == 3 {
+        Ok(format!("#{}", digits.chars().flat_map(|c| [c, c]).collect::<String>()))
+    } else if digits.len() == 6 {
+        Ok(trimmed)
+    } else {
+        Err(AppError::invalid("color", format!(
+            "Tag color {} is neither a hex color nor a palette name",
+            color
+        )))
+    }
+}
+
+pub fn list_palette() -> Vec<PaletteColor> {
+    TAG_PALETTE
+        .iter()
+        .map(|(name, hex)| PaletteColor { name: name.to_string(), hex: hex.to_string() })
+        .collect()
+}
+
+fn validate_optional_text(value: Option<String>, field: &str, max_len: usize) -> Result<Option<String>> {
+    match value.map(|v| v.trim().to_string()) {
+        Some(v) if v.is_empty() => Ok(None),
+        Some(v) if v.chars().count() > max_len => Err(AppError::invalid(field, format!(
+            "Tag {} must be at most {} characters",
+            field, max_len
+        ))),
+        other => Ok(other),
+    }
+}
+
+pub struct TagService<'a> {
+    db: &'a DatabaseService,
+}
+
+impl<'a> TagService<'a> {
+    pub fn new(db: &'a DatabaseService) -> Self {
+        Self { db }
+    }
+
+    pub fn create_tag(&self, name: String, color: Option<String>) -> Result<Tag> {
+        let id = Ulid::new().to_string();
+        let name = validate_tag_name(&name)?;
+        let color = match color {
+            Some(c) => validate_tag_color(&c)?,
+            None => self.next_palette_color()?,
+        };
+
+        let conn = self.db.connection();
+        conn.execute(
+            "INSERT INTO tags (id, name, color) VALUES (?, ?, ?)",
+            params![id, name, color],
+        )?;
+        self.db.record(ChangeEvent::TagChanged { tag_ids: vec![id.clone()], note_ids: Vec::new() });
+
+        Ok(Tag { id, name, color, icon: None, description: None })
+    }
+
+    /// Updates a tag's name, color, icon and description. A new name is applied like
+    /// `rename_tag`, so descendants and inline `#hashtags` follow along.
+    pub fn update_tag(
+        &self,
+        tag_id: String,
+        name: Option<String>,
+        color: Option<String>,
+        icon: Option<Option<String>>,
+        description: Option<Option<String>>,
+    ) -> Result<Tag> {
+        let mut tag = self.get_tag(tag_id.clone())?;
+        let name = name.map(|n| validate_tag_name(&n)).transpose()?;
+        let color = color.map(|c| validate_tag_color(&c)).transpose()?;
+        let icon = icon.map(|i| validate_optional_text(i, "icon", MAX_TAG_ICON_LEN)).transpose()?;
+        let description = description
+            .map(|d| validate_optional_text(d, "description", MAX_TAG_DESCRIPTION_LEN))
+            .transpose()?;
+
+        let conn = self.db.connection();
+        let tx = conn.unchecked_transaction()?;
+
+        let mut rewritten = Vec::new();
+        if let Some(n) = name.filter(|n| *n != tag.name) {
+            rewritten = self.apply_rename(&tx, &tag_id, &tag.name, &n)?;
+            tag.name = n;
+        }
+        if let Some(c) = color {
+            tx.execute("UPDATE tags SET color = ? WHERE id = ?", params![c, tag_id])?;
+            tag.color = c;
+        }
+        if let Some(i) = icon {
+            tx.execute("UPDATE tags SET icon = ? WHERE id = ?", params![i, tag_id])?;
+            tag.icon = i;
+        }
+        if let Some(d) = description {
+            tx.execute("UPDATE tags SET description = ? WHERE id = ?", params![d, tag_id])?;
+            tag.description = d;
+        }
+
+        tx.commit()?;
+        self.record_tag_change(vec![tag_id], Vec::new(), rewritten);
+        Ok(tag)
+    }
+
+    /// Picks the palette color used by the fewest tags, preferring earlier entries on ties.
+    fn next_palette_color(&self) -> Result<String> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached("SELECT lower(color), COUNT(*) FROM tags GROUP BY lower(color)")?;
+        let usage: Vec<(Option<String>, i64)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let count_for = |hex: &str| {
+            usage
+                .iter()
+                .find(|(c, _)| c.as_deref() == Some(hex))
+                .map(|(_, n)| *n)
+                .unwrap_or(0)
+        };
+
+        let (_, hex) = TAG_PALETTE
+            .iter()
+            .min_by_key(|(_, hex)| count_for(hex))
+            .unwrap_or(&TAG_PALETTE[0]);
+        Ok(hex.to_string())
+    }
+
+    pub fn list_tags(&self) -> Result<Vec<TagWithCount>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached(
+            "SELECT t.id, t.name, t.color, t.icon, t.description, (SELECT COUNT(*) FROM note_tags WHERE tag_id = t.id) as note_count 
+             FROM tags t"
+        )?;
+
+        let tag_iter = stmt.query_map([], |row| {
+            Ok(TagWithCount {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                icon: row.get(3)?,
+                description: row.get(4)?,
+                note_count: row.get(5)?,
+            })
+        })?;
+
+        let mut tags = Vec::new();
+        for tag in tag_iter {
+            tags.push(tag?);
+        }
+
+        Ok(tags)
+    }
+
+    /// Tags on notes directly in one of `folders` (`None` being the root), counting only
+    /// those notes. Tags with no such note are left out.
+    pub fn list_tags_in_folders(&self, folders: &[Option<String>]) -> Result<Vec<TagWithCount>> {
+        let conn = self.db.connection();
+        let (scope, params_vec) = folder_scope_sql(folders, "n.folder_id");
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT t.id, t.name, t.color, t.icon, t.description, COUNT(DISTINCT nt.note_id) as note_count
+             FROM tags t
+             JOIN note_tags nt ON nt.tag_id = t.id
+             JOIN notes n ON n.id = nt.note_id
+             WHERE {}
+             GROUP BY t.id",
+            scope
+        ))?;
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+        let tags = stmt
+            .query_map(params_refs.as_slice(), |row| {
+                Ok(TagWithCount {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    color: row.get(2)?,
+                    icon: row.get(3)?,
+                    description: row.get(4)?,
+                    note_count: row.get(5)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(tags)
+    }
+
+    pub fn get_tag_tree(&self) -> Result<Vec<TagTreeNode>> {
+        let conn = self.db.connection();
+        // Keyed on the lowercased path so `Project` and `project/alpha` share a branch
+        let mut entries: BTreeMap<String, TagTreeEntry> = BTreeMap::new();
+        let mut tag_paths: BTreeMap<String, String> = BTreeMap::new();
+
+        let mut stmt = conn.prepare_cached("SELECT id, name, color FROM tags ORDER BY name")?;
+        let tag_iter = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+        })?;
+
+        for tag in tag_iter {
+            let (id, name, color) = tag?;
+            let segments = tag_path_segments(&name);
+            if segments.is_empty() {
+                continue;
+            }
+            // Make sure every ancestor exists so the tree has no gaps
+            for depth in 1..=segments.len() {
+                let path = segments[..depth].join("/");
+                entries
+                    .entry(path.to_lowercase())
+                    .or_insert_with(|| TagTreeEntry { path, ..TagTreeEntry::default() });
+            }
+            let path = segments.join("/");
+            let key = path.to_lowercase();
+            let entry = entries.get_mut(&key).expect("inserted above");
+            if entry.id.is_none() {
+                entry.id = Some(id.clone());
+                entry.color = color;
+                entry.path = path;
+            }
+            tag_paths.insert(id, key);
+        }
+
+        let mut stmt = conn.prepare_cached("SELECT tag_id, note_id FROM note_tags")?;
+        let assignment_iter = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        for assignment in assignment_iter {
+            let (tag_id, note_id) = assignment?;
+            let Some(path) = tag_paths.get(&tag_id) else { continue };
+
+            if let Some(entry) = entries.get_mut(path) {
+                entry.direct_notes.insert(note_id.clone());
+            }
+            let segments: Vec<&str> = path.split('/').collect();
+            for depth in 1..=segments.len() {
+                if let Some(entry) = entries.get_mut(&segments[..depth].join("/")) {
+                    entry.rollup_notes.insert(note_id.clone());
+                }
+            }
+        }
+
+        Ok(Self::build_tag_subtree(&entries, None))
+    }
+
+    fn build_tag_subtree(entries: &BTreeMap<String, TagTreeEntry>, parent: Option<&str>) -> Vec<TagTreeNode> {
+        entries
+            .iter()
+            .filter(|(key, _)| match (parent, key.rsplit_once('/')) {
+                (None, None) => true,
+                (Some(p), Some((prefix, _))) => prefix == p,
+                _ => false,
+            })
+            .map(|(key, entry)| TagTreeNode {
+                id: entry.id.clone(),
+                name: entry.path.rsplit('/').next().unwrap_or(&entry.path).to_string(),
+                path: entry.path.clone(),
+                color: entry.color.clone(),
+                note_count: entry.direct_notes.len() as i64,
+                total_count: entry.rollup_notes.len() as i64,
+                children: Self::build_tag_subtree(entries, Some(key)),
+            })
+            .collect()
+    }
+
+    pub fn add_tag_to_note(&self, note_id: String, tag_id: String) -> Result<()> {
+        let conn = self.db.connection();
+        // Attaching by hand pins an inline tag, so deleting the #hashtag no longer removes it
+        conn.execute(
+            "INSERT INTO note_tags (note_id, tag_id, source) VALUES (?, ?, 'manual')
+             ON CONFLICT (note_id, tag_id) DO UPDATE SET source = 'manual'",
+            params![note_id, tag_id],
+        )?;
+        self.db.record(ChangeEvent::TagChanged { tag_ids: vec![tag_id], note_ids: vec![note_id] });
+        Ok(())
+    }
+
+    /// Mirrors the `#hashtags` in `content` into `note_tags`, creating missing tags.
+    /// Only rows with the `inline` source are removed; manually attached tags are kept.
+    pub fn sync_inline_tags(&self, note_id: &str, content: &str) -> Result<()> {
+        let conn = self.db.connection();
+        let mut tag_ids = Vec::new();
+        let mut changed = Vec::new();
+
+        // Hashtags that would not make a valid tag name are ignored instead of failing the save
+        for name in extract_hashtags(content).into_iter().filter(|n| validate_tag_name(n).is_ok()) {
+            let existing: Option<String> = conn.query_row(
+                "SELECT id FROM tags WHERE name = ? COLLATE NOCASE",
+                params![name],
+                |row| row.get(0),
+            ).optional()?;
+
+            let tag_id = match existing {
+                Some(id) => id,
+                None => self.create_tag(name, None)?.id,
+            };
+
+            let inserted = conn.execute(
+                "INSERT OR IGNORE INTO note_tags (note_id, tag_id, source) VALUES (?, ?, 'inline')",
+                params![note_id, tag_id],
+            )?;
+            if inserted > 0 {
+                changed.push(tag_id.clone());
+            }
+            tag_ids.push(tag_id);
+        }
+
+        let mut stmt = conn.prepare_cached("SELECT tag_id FROM note_tags WHERE note_id = ? AND source = 'inline'")?;
+        let stale: Vec<String> = stmt
+            .query_map(params![note_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?
+            .into_iter()
+            .filter(|id| !tag_ids.contains(id))
+            .collect();
+
+        for tag_id in stale {
+            conn.execute(
+                "DELETE FROM note_tags WHERE note_id = ? AND tag_id = ? AND source = 'inline'",
+                params![note_id, tag_id],
+            )?;
+            changed.push(tag_id);
+        }
+
+        if !changed.is_empty() {
+            self.db.record(ChangeEvent::TagChanged { tag_ids: changed, note_ids: vec![note_id.to_string()] });
+        }
+        Ok(())
+    }
+
+    pub fn remove_tag_from_note(&self, note_id: String, tag_id: String) -> Result<()> {
+        let conn = self.db.connection();
+        conn.execute(
+            "DELETE FROM note_tags WHERE note_id = ? AND tag_id = ?",
+            params![note_id, tag_id],
+        )?;
+        self.db.record(ChangeEvent::TagChanged { tag_ids: vec![tag_id], note_ids: vec![note_id] });
+        Ok(())
+    }
+
+    pub fn get_note_tags(&self, note_id: String) -> Result<Vec<Tag>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached(
+            "SELECT t.id, t.name, t.color, t.icon, t.description
+             FROM tags t
+             JOIN note_tags nt ON t.id = nt.tag_id
+             WHERE nt.note_id = ?"
+        )?;
+
+        let tag_iter = stmt.query_map(params![note_id], Self::map_row_to_tag)?;
+
+        let mut tags = Vec::new();
+        for tag in tag_iter {
+            tags.push(tag?);
+        }
+
+        Ok(tags)
+    }
+
+    pub fn get_tag(&self, tag_id: String) -> Result<Tag> {
+        let conn = self.db.connection();
+        conn.query_row(
+            "SELECT id, name, color, icon, description FROM tags WHERE id = ?",
+            params![tag_id],
+            Self::map_row_to_tag,
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Tag with id {} not found", tag_id)))
+    }
+
+    /// Looks a tag up by its full name, ignoring case.
+    pub fn find_tag_by_name(&self, name: &str) -> Result<Option<Tag>> {
+        let conn = self.db.connection();
+        conn.query_row(
+            "SELECT id, name, color, icon, description FROM tags WHERE name = ? COLLATE NOCASE",
+            params![name.trim()],
+            Self::map_row_to_tag,
+        ).optional().map_err(AppError::from)
+    }
+
+    fn map_row_to_tag(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
+        Ok(Tag {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            color: row.get(2)?,
+            icon: row.get(3)?,
+            description: row.get(4)?,
+        })
+    }
+
+    /// Renames a tag together with its descendants (`project` -> `work` also turns
+    /// `project/alpha` into `work/alpha`) and rewrites matching inline `#hashtags`.
+    pub fn rename_tag(&self, tag_id: String, new_name: String) -> Result<Tag> {
+        let new_name = validate_tag_name(&new_name)?;
+        let mut tag = self.get_tag(tag_id.clone())?;
+        let old_name = tag.name.clone();
+
+        let conn = self.db.connection();
+        let tx = conn.unchecked_transaction()?;
+        let rewritten = self.apply_rename(&tx, &tag_id, &old_name, &new_name)?;
+        tx.commit()?;
+        self.record_tag_change(vec![tag_id], Vec::new(), rewritten);
+
+        tag.name = new_name;
+        Ok(tag)
+    }
+
+    /// Returns the notes whose inline hashtags were rewritten.
+    fn apply_rename(&self, conn: &rusqlite::Connection, tag_id: &str, old_name: &str, new_name: &str) -> Result<Vec<RewrittenNote>> {
+        conn.execute(
+            "UPDATE tags SET name = ? || substr(name, length(?) + 1)
+             WHERE id = ? OR substr(name, 1, length(?) + 1) = ? || '/' COLLATE NOCASE",
+            params![new_name, old_name, tag_id, old_name, old_name],
+        )?;
+        self.rewrite_inline_tags(conn, old_name, new_name, true)
+    }
+
+    /// Folds the source tags into `target_id`. Notes that already carry the target keep a
+    /// single assignment, the source tags are deleted and their inline `#hashtags` rewritten.
+    pub fn merge_tags(&self, source_ids: Vec<String>, target_id: String) -> Result<Tag> {
+        let target = self.get_tag(target_id.clone())?;
+
+        let conn = self.db.connection();
+        let tx = conn.unchecked_transaction()?;
+        let mut merged = vec![target_id.clone()];
+        let mut tagged = Vec::new();
+        let mut rewritten = Vec::new();
+
+        for source_id in source_ids.into_iter().filter(|id| *id != target_id) {
+            let source = self.get_tag(source_id.clone())?;
+            tagged.extend(Self::tagged_notes(&tx, &source_id)?);
+
+            // A manual pin on either side survives the merge
+            tx.execute(
+                "INSERT INTO note_tags (note_id, tag_id, source)
+                 SELECT note_id, ?, source FROM note_tags WHERE tag_id = ?
+                 ON CONFLICT (note_id, tag_id) DO UPDATE SET source = 'manual' WHERE excluded.source = 'manual'",
+                params![target_id, source_id],
+            )?;
+            tx.execute("DELETE FROM note_tags WHERE tag_id = ?", params![source_id])?;
+            tx.execute("DELETE FROM tags WHERE id = ?", params![source_id])?;
+
+            rewritten.extend(self.rewrite_inline_tags(&tx, &source.name, &target.name, false)?);
+            merged.push(source_id);
+        }
+
+        tx.commit()?;
+        self.record_tag_change(merged, tagged, rewritten);
+        Ok(target)
+    }
+
+    /// Returns the notes whose content changed. Their tasks and properties are re-indexed
+    /// on `conn`, so they commit together with the rewrite.
+    fn rewrite_inline_tags(&self, conn: &rusqlite::Connection, old_name: &str, new_name: &str, include_descendants: bool) -> Result<Vec<RewrittenNote>> {
+        let now = chrono::Utc::now().timestamp_millis();
+
+        // LIKE is only a cheap pre-filter; rewrite_hashtag does the exact matching
+        let mut stmt = conn.prepare_cached("SELECT id, content, folder_id FROM notes WHERE encrypted = 0 AND content LIKE '%#' || ? || '%'")?;
+        let rows = stmt.query_map(params![old_name], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+        })?;
+
+        let mut changed = Vec::new();
+        for row in rows {
+            let (id, content, folder_id) = row?;
+            let rewritten = rewrite_hashtag(&content, old_name, new_name, include_descendants);
+            if rewritten != content {
+                changed.push((id, rewritten, folder_id));
+            }
+        }
+
+        let mut notes = Vec::with_capacity(changed.len());
+        for (id, content, folder_id) in changed {
+            conn.execute(
+                "UPDATE notes SET content = ?, updated_at = ? WHERE id = ?",
+                params![content, now, id],
+            )?;
+            TaskService::new(self.db).sync_tasks(&id, &content)?;
+            PropertyService::new(self.db).sync_properties(&id, &content)?;
+            notes.push(RewrittenNote { id, folder_id });
+        }
+        Ok(notes)
+    }
+
+    fn tagged_notes(conn: &rusqlite::Connection, tag_id: &str) -> Result<Vec<String>> {
+        let mut stmt = conn.prepare_cached("SELECT note_id FROM note_tags WHERE tag_id = ?")?;
+        let notes = stmt
+            .query_map(params![tag_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(notes)
+    }
+
+    /// Records a change to `tag_ids` affecting `tagged` notes, plus the notes whose
+    /// inline hashtags were rewritten along the way.
+    fn record_tag_change(&self, tag_ids: Vec<String>, mut tagged: Vec<String>, rewritten: Vec<RewrittenNote>) {
+        for note in rewritten {
+            if !tagged.contains(&note.id) {
+                tagged.push(note.id.clone());
+            }
+            self.db.record(ChangeEvent::NoteUpdated { id: note.id, folder_id: note.folder_id });
+        }
+        self.db.record(ChangeEvent::TagChanged { tag_ids, note_ids: tagged });
+    }
+
+    pub fn delete_tag(&self, tag_id: String) -> Result<()> {
+        let conn = self.db.connection();
+        let tagged = Self::tagged_notes(conn, &tag_id)?;
+        // First remove all note-tag associations
+        conn.execute(
+            "DELETE FROM note_tags WHERE tag_id = ?",
+            params![tag_id],
+        )?;
+        // Then delete the tag itself
+        conn.execute(
+            "DELETE FROM tags WHERE id = ?",
+            params![tag_id],
+        )?;
+        self.db.record(ChangeEvent::TagChanged { tag_ids: vec![tag_id], note_ids: tagged });
+        Ok(())
+    }
+}
+
+/// A note whose inline hashtags were rewritten by a tag rename or merge.
+struct RewrittenNote {
+    id: String,
+    folder_id: Option<String>,
+}
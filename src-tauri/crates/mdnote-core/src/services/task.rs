@@ -0,0 +1,323 @@
+use std::sync::OnceLock;
+use chrono::NaiveDate;
+use regex::{Captures, Regex};
+use rusqlite::{params, OptionalExtension, Row};
+use crate::errors::{AppError, Result};
+use crate::models::task::{AgendaGroup, Priority, Task, TaskFilter};
+use crate::services::database::DatabaseService;
+use crate::services::events::ChangeEvent;
+use crate::services::hashtag::extract_hashtags;
+use crate::services::note::NoteService;
+use crate::services::periodic::parse_date;
+
+fn checkbox_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^(\s*(?:[-*+]|\d+[.)])\s+\[)([ xX])\]\s+(\S.*)$").unwrap())
+}
+
+fn due_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?:\bdue:\s*|📅\s*)(\d{4}-\d{2}-\d{2})\b").unwrap())
+}
+
+fn priority_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)\bpriority:\s*(high|medium|low)\b|(⏫|🔺|🔼|🔽|⏬)").unwrap())
+}
+
+/// A checkbox item as written in a note's content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedTask {
+    /// 1-based.
+    pub line: usize,
+    pub text: String,
+    pub done: bool,
+    pub due_date: Option<NaiveDate>,
+    pub priority: Option<Priority>,
+    pub tags: Vec<String>,
+}
+
+/// The `- [ ]` / `- [x]` items in `content`, outside fenced code blocks. Any of `-`, `*`,
+/// `+` or an ordered list marker works, and items without text are skipped. The first
+/// `due:YYYY-MM-DD` or `📅 YYYY-MM-DD` sets the due date, and `priority:high` or one of
+/// the `⏫ 🔼 🔽` markers the priority; both are removed from the text.
+pub fn extract_tasks(content: &str) -> Vec<ParsedTask> {
+    let mut tasks = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+        match (fence, marker) {
+            (None, Some(m)) => fence = Some(m),
+            (Some(open), Some(m)) if m == open => fence = None,
+            (None, None) => {
+                if let Some(caps) = checkbox_regex().captures(line) {
+                    tasks.push(parse_task(index + 1, &caps[2] != " ", &caps[3]));
+                }
+            }
+            _ => {}
+        }
+    }
+    tasks
+}
+
+fn parse_task(line: usize, done: bool, raw: &str) -> ParsedTask {
+    let mut due_date = None;
+    let text = due_regex().replace_all(raw, |caps: &Captures| {
+        match NaiveDate::parse_from_str(&caps[1], "%Y-%m-%d") {
+            Ok(date) => {
+                due_date.get_or_insert(date);
+                String::new()
+            }
+            // Not a real date, e.g. 2026-02-30: leave it in the text
+            Err(_) => caps[0].to_string(),
+        }
+    });
+
+    let mut priority = None;
+    let text = priority_regex().replace_all(&text, |caps: &Captures| {
+        let parsed = match caps.get(1) {
+            Some(name) => Priority::parse(name.as_str()),
+            None => match &caps[2] {
+                "⏫" | "🔺" => Some(Priority::High),
+                "🔼" => Some(Priority::Medium),
+                _ => Some(Priority::Low),
+            },
+        };
+        if priority.is_none() {
+            priority = parsed;
+        }
+        String::new()
+    });
+
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let tags = extract_hashtags(&text);
+    ParsedTask { line, text, done, due_date, priority, tags }
+}
+
+/// `content` with the checkbox on `line` flipped, or `None` if that line holds no task.
+fn toggle_checkbox(content: &str, line: usize) -> Option<String> {
+    if !extract_tasks(content).iter().any(|task| task.line == line) {
+        return None;
+    }
+
+    let mut toggled = String::with_capacity(content.len());
+    for (index, source) in content.split_inclusive('\n').enumerate() {
+        if index + 1 != line {
+            toggled.push_str(source);
+            continue;
+        }
+        let body = source.trim_end_matches(['\n', '\r']);
+        let caps = checkbox_regex().captures(body)?;
+        let mark = caps.get(2)?;
+        let checked = if mark.as_str() == " " { "x" } else { " " };
+        toggled.push_str(&source[..mark.start()]);
+        toggled.push_str(checked);
+        toggled.push_str(&source[mark.end()..]);
+    }
+    Some(toggled)
+}
+
+pub struct TaskService<'a> {
+    db: &'a DatabaseService,
+}
+
+impl<'a> TaskService<'a> {
+    pub fn new(db: &'a DatabaseService) -> Self {
+        Self { db }
+    }
+
+    /// Re-indexes the tasks in `content` for the note. Rows that didn't change are left
+    /// alone, so saving a note only logs the tasks it touched for undo.
+    pub fn sync_tasks(&self, note_id: &str, content: &str) -> Result<()> {
+        let conn = self.db.connection();
+        let tasks = extract_tasks(content);
+        self.reanchor_reminders(note_id, &tasks)?;
+
+        for task in &tasks {
+            let tags = serde_json::to_string(&task.tags).map_err(|e| AppError::validation(e.to_string()))?;
+            conn.execute(
+                "INSERT INTO tasks (note_id, line, text, done, due_date, priority, tags) VALUES (?, ?, ?, ?, ?, ?, ?)
+                 ON CONFLICT (note_id, line) DO UPDATE SET
+                     text = excluded.text, done = excluded.done, due_date = excluded.due_date,
+                     priority = excluded.priority, tags = excluded.tags",
+                params![
+                    note_id,
+                    task.line as i64,
+                    task.text,
+                    task.done,
+                    task.due_date.map(|d| d.format("%Y-%m-%d").to_string()),
+                    task.priority.map(|p| p.as_str()),
+                    tags,
+                ],
+            )?;
+        }
+
+        let lines: Vec<usize> = tasks.iter().map(|task| task.line).collect();
+        let lines = serde_json::to_string(&lines).map_err(|e| AppError::validation(e.to_string()))?;
+        conn.execute(
+            "DELETE FROM tasks WHERE note_id = ? AND line NOT IN (SELECT value FROM json_each(?))",
+            params![note_id, lines],
+        )?;
+        Ok(())
+    }
+
+    /// Moves reminders for the note's tasks to the lines those tasks are on in `tasks`, so
+    /// they follow them when lines are added or removed above. A reminder follows the task
+    /// with the same text, the n-th of several identical ones to the n-th; one whose task
+    /// was edited stays on its line.
+    fn reanchor_reminders(&self, note_id: &str, tasks: &[ParsedTask]) -> Result<()> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached("SELECT line, text FROM tasks WHERE note_id = ? ORDER BY line")?;
+        let indexed: Vec<(usize, String)> = stmt
+            .query_map(params![note_id], |row| Ok((row.get::<_, i64>(0)? as usize, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        if indexed.is_empty() {
+            return Ok(());
+        }
+
+        let mut stmt = conn.prepare_cached("SELECT id, task_line FROM reminders WHERE note_id = ? AND task_line IS NOT NULL")?;
+        let reminders: Vec<(String, usize)> = stmt
+            .query_map(params![note_id], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        for (id, line) in reminders {
+            let Some((_, text)) = indexed.iter().find(|(l, _)| *l == line) else {
+                continue;
+            };
+            let occurrence = indexed.iter().filter(|(l, t)| *l < line && t == text).count();
+            let moved_to = tasks.iter().filter(|task| &task.text == text).nth(occurrence).map(|task| task.line);
+            if let Some(moved_to) = moved_to.filter(|&l| l != line) {
+                conn.execute("UPDATE reminders SET task_line = ? WHERE id = ?", params![moved_to as i64, id])?;
+                self.db.record(ChangeEvent::ReminderChanged { id, note_id: note_id.to_string() });
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops the note's tasks from the index, e.g. once its content is encrypted.
+    pub fn clear_tasks(&self, note_id: &str) -> Result<()> {
+        self.db.connection().execute("DELETE FROM tasks WHERE note_id = ?", params![note_id])?;
+        Ok(())
+    }
+
+    /// The tasks matching `filter` as an agenda: one group per due date, earliest first,
+    /// then the tasks without one. Within a day, higher priorities come first.
+    pub fn list_tasks(&self, filter: TaskFilter) -> Result<Vec<AgendaGroup>> {
+        let mut query = String::from(
+            "SELECT t.note_id, n.title, t.line, t.text, t.done, t.due_date, t.priority, t.tags
+             FROM tasks t JOIN notes n ON n.id = t.note_id",
+        );
+        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        let mut where_clauses: Vec<String> = Vec::new();
+
+        if let Some(done) = filter.done {
+            where_clauses.push("t.done = ?".to_string());
+            params_vec.push(Box::new(done));
+        }
+        if let Some(from) = filter.due_from {
+            where_clauses.push("t.due_date >= ?".to_string());
+            params_vec.push(Box::new(parse_date("due_from", &from)?.format("%Y-%m-%d").to_string()));
+        }
+        if let Some(to) = filter.due_to {
+            where_clauses.push("t.due_date <= ?".to_string());
+            params_vec.push(Box::new(parse_date("due_to", &to)?.format("%Y-%m-%d").to_string()));
+        }
+        if let Some(has_due_date) = filter.has_due_date {
+            where_clauses.push(if has_due_date { "t.due_date IS NOT NULL" } else { "t.due_date IS NULL" }.to_string());
+        }
+        if let Some(priority) = filter.priority {
+            where_clauses.push("t.priority = ?".to_string());
+            params_vec.push(Box::new(priority.as_str()));
+        }
+        if let Some(tag) = filter.tag {
+            let tag = tag.trim().trim_start_matches('#').to_lowercase();
+            where_clauses.push(
+                "EXISTS (SELECT 1 FROM json_each(t.tags) j
+                         WHERE lower(j.value) = ? OR substr(lower(j.value), 1, length(?) + 1) = ? || '/')"
+                    .to_string(),
+            );
+            params_vec.push(Box::new(tag.clone()));
+            params_vec.push(Box::new(tag.clone()));
+            params_vec.push(Box::new(tag));
+        }
+        if let Some(note_id) = filter.note_id {
+            where_clauses.push("t.note_id = ?".to_string());
+            params_vec.push(Box::new(note_id));
+        }
+        if let Some(folder_id) = filter.folder_id {
+            where_clauses.push("n.folder_id = ?".to_string());
+            params_vec.push(Box::new(folder_id));
+        }
+
+        if !where_clauses.is_empty() {
+            query.push_str(&format!(" WHERE {}", where_clauses.join(" AND ")));
+        }
+        query.push_str(
+            " ORDER BY t.due_date IS NULL, t.due_date,
+                 CASE t.priority WHEN 'high' THEN 0 WHEN 'medium' THEN 1 WHEN 'low' THEN 3 ELSE 2 END,
+                 n.title COLLATE NOCASE, t.note_id, t.line",
+        );
+
+        let conn = self.db.connection();
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+        let mut stmt = conn.prepare_cached(&query)?;
+        let rows = stmt.query_map(params_refs.as_slice(), Self::map_row_to_task)?;
+
+        let mut groups: Vec<AgendaGroup> = Vec::new();
+        for task in rows {
+            let task = task?;
+            match groups.last_mut() {
+                Some(group) if group.due_date == task.due_date => group.tasks.push(task),
+                _ => groups.push(AgendaGroup { due_date: task.due_date.clone(), tasks: vec![task] }),
+            }
+        }
+        Ok(groups)
+    }
+
+    pub fn get_task(&self, note_id: &str, line: usize) -> Result<Task> {
+        let conn = self.db.connection();
+        conn.query_row(
+            "SELECT t.note_id, n.title, t.line, t.text, t.done, t.due_date, t.priority, t.tags
+             FROM tasks t JOIN notes n ON n.id = t.note_id
+             WHERE t.note_id = ? AND t.line = ?",
+            params![note_id, line as i64],
+            Self::map_row_to_task,
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Note {} has no task on line {}", note_id, line)))
+    }
+
+    /// Checks or unchecks the task on `line` by rewriting its checkbox in the note, and
+    /// returns the task as it is now. The note is only written if it hasn't changed since
+    /// it was read; otherwise this fails with `AppError::Conflict`.
+    pub fn toggle_task(&self, note_id: String, line: usize) -> Result<Task> {
+        let notes = NoteService::new(self.db);
+        let note = notes.get_note(note_id.clone())?;
+        if note.encrypted {
+            return Err(AppError::validation("Tasks in encrypted notes can't be toggled from the agenda"));
+        }
+        let content = toggle_checkbox(&note.content, line)
+            .ok_or_else(|| AppError::NotFound(format!("Note {} has no task on line {}", note_id, line)))?;
+
+        notes.update_note(note_id.clone(), None, Some(content), None, Some(note.updated_at))?;
+        self.get_task(&note_id, line)
+    }
+
+    fn map_row_to_task(row: &Row) -> rusqlite::Result<Task> {
+        let line: i64 = row.get(2)?;
+        let priority: Option<String> = row.get(6)?;
+        let tags: String = row.get(7)?;
+        Ok(Task {
+            note_id: row.get(0)?,
+            note_title: row.get(1)?,
+            line: line as usize,
+            text: row.get(3)?,
+            done: row.get(4)?,
+            due_date: row.get(5)?,
+            priority: priority.as_deref().and_then(Priority::parse),
+            tags: serde_json::from_str(&tags).unwrap_or_default(),
+        })
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use regex::Regex;
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use ulid::Ulid;
+use crate::errors::{AppError, Result};
+use crate::models::note::Note;
+use crate::models::template::{Template, TemplateDraft};
+use crate::services::backlink::BacklinkService;
+use crate::services::database::DatabaseService;
+use crate::services::note::NoteService;
+use crate::services::tag::{validate_tag_name, TagService};
+
+const MAX_TEMPLATE_NAME_LEN: usize = 100;
+
+/// Placeholders every template can use without the caller supplying them. `date` and
+/// `time` are the local date and time, `title` the new note's title, and `cursor` marks
+/// where the editor should put the caret.
+pub const BUILTIN_VARIABLES: &[&str] = &["date", "time", "title", "cursor"];
+
+fn placeholder_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_\-]*)\s*\}\}").unwrap())
+}
+
+/// A template filled in with its variables, as a note created from it would look.
+#[derive(Debug, Serialize)]
+pub struct RenderedTemplate {
+    pub title: String,
+    pub content: String,
+    /// Where `{{cursor}}` was, in UTF-16 code units into `content` like editors count.
+    pub cursor: Option<usize>,
+    pub folder_id: Option<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateNote {
+    pub note: Note,
+    pub cursor: Option<usize>,
+}
+
+/// The variables `text` uses that callers have to supply, in order of first use.
+pub fn template_variables(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for caps in placeholder_regex().captures_iter(text) {
+        let name = &caps[1];
+        if !BUILTIN_VARIABLES.contains(&name) && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Replaces every placeholder with its value, dropping `{{cursor}}` and returning where
+/// the first one was. Unknown names render empty, so check them first.
+fn fill(text: &str, values: &HashMap<&str, String>) -> (String, Option<usize>) {
+    let mut out = String::with_capacity(text.len());
+    let mut cursor = None;
+    let mut last = 0;
+    for caps in placeholder_regex().captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        out.push_str(&text[last..whole.start()]);
+        match &caps[1] {
+            "cursor" => {
+                if cursor.is_none() {
+                    cursor = Some(out.encode_utf16().count());
+                }
+            }
+            name => out.push_str(values.get(name).map(String::as_str).unwrap_or_default()),
+        }
+        last = whole.end();
+    }
+    out.push_str(&text[last..]);
+    (out, cursor)
+}
+
+/// Fills in `template` with `vars`. A `title` var replaces the template's title, and
+/// `date` or `time` vars override the built-in ones. Using a variable that is neither
+/// built in nor in `vars` is an error naming every missing one.
+pub fn render_template(template: &Template, vars: &HashMap<String, String>) -> Result<RenderedTemplate> {
+    if vars.contains_key("cursor") {
+        return Err(AppError::invalid("vars", "cursor is a reserved variable name"));
+    }
+    let missing: Vec<String> = template
+        .variables
+        .iter()
+        .filter(|name| !vars.contains_key(*name))
+        .cloned()
+        .collect();
+    if !missing.is_empty() {
+        return Err(AppError::invalid("vars", format!(
+            "Template {} uses undefined variables: {}",
+            template.name,
+            missing.join(", ")
+        )));
+    }
+
+    let now = chrono::Local::now();
+    let mut values: HashMap<&str, String> = HashMap::new();
+    values.insert("date", now.format("%Y-%m-%d").to_string());
+    values.insert("time", now.format("%H:%M").to_string());
+    for (name, value) in vars {
+        values.insert(name, value.clone());
+    }
+
+    let title = match vars.get("title") {
+        Some(title) => title.clone(),
+        None => fill(&template.title, &values).0.trim().to_string(),
+    };
+    let title = if title.is_empty() { template.name.clone() } else { title };
+    values.insert("title", title.clone());
+    let (content, cursor) = fill(&template.content, &values);
+
+    Ok(RenderedTemplate {
+        title,
+        content,
+        cursor,
+        folder_id: template.folder_id.clone(),
+        tags: template.tags.clone(),
+    })
+}
+
+/// Trims the name and normalizes the tags, rejecting placeholders a title can't use.
+fn validate_draft(mut draft: TemplateDraft) -> Result<TemplateDraft> {
+    draft.name = draft.name.trim().to_string();
+    if draft.name.is_empty() {
+        return Err(AppError::invalid("name", "Template name must not be empty"));
+    }
+    if draft.name.chars().count() > MAX_TEMPLATE_NAME_LEN {
+        return Err(AppError::invalid("name", format!(
+            "Template name must be at most {} characters",
+            MAX_TEMPLATE_NAME_LEN
+        )));
+    }
+    if placeholder_regex().captures_iter(&draft.title).any(|caps| matches!(&caps[1], "title" | "cursor")) {
+        return Err(AppError::invalid("title", "A template title can't use {{title}} or {{cursor}}"));
+    }
+
+    let mut tags: Vec<String> = Vec::new();
+    for name in &draft.tags {
+        let name = validate_tag_name(name).map_err(|e| AppError::invalid("tags", e.message()))?;
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(&name)) {
+            tags.push(name);
+        }
+    }
+    draft.tags = tags;
+    Ok(draft)
+}
+
+pub struct TemplateService<'a> {
+    db: &'a DatabaseService,
+}
+
+impl<'a> TemplateService<'a> {
+    pub fn new(db: &'a DatabaseService) -> Self {
+        Self { db }
+    }
+
+    pub fn create_template(&self, draft: TemplateDraft) -> Result<Template> {
+        let draft = validate_draft(draft)?;
+        let id = Ulid::new().to_string();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let conn = self.db.connection();
+        conn.execute(
+            "INSERT INTO templates (id, name, title, content, folder_id, tags, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![id, draft.name, draft.title, draft.content, draft.folder_id, encode_tags(&draft.tags)?, now, now],
+        )?;
+        self.get_template(id)
+    }
+
+    /// Replaces everything but the id and creation time.
+    pub fn update_template(&self, id: String, draft: TemplateDraft) -> Result<Template> {
+        let draft = validate_draft(draft)?;
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let conn = self.db.connection();
+        let affected = conn.execute(
+            "UPDATE templates SET name = ?, title = ?, content = ?, folder_id = ?, tags = ?, updated_at = ? WHERE id = ?",
+            params![draft.name, draft.title, draft.content, draft.folder_id, encode_tags(&draft.tags)?, now, id],
+        )?;
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Template with id {} not found", id)));
+        }
+        self.get_template(id)
+    }
+
+    pub fn get_template(&self, id: String) -> Result<Template> {
+        let conn = self.db.connection();
+        conn.query_row(
+            "SELECT id, name, title, content, folder_id, tags, created_at, updated_at FROM templates WHERE id = ?",
+            params![id],
+            Self::map_row_to_template,
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Template with id {} not found", id)))
+    }
+
+    pub fn list_templates(&self) -> Result<Vec<Template>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, name, title, content, folder_id, tags, created_at, updated_at
+             FROM templates ORDER BY name COLLATE NOCASE",
+        )?;
+        let templates = stmt
+            .query_map([], Self::map_row_to_template)?
+            .collect::<rusqlite::Result<Vec<Template>>>()?;
+        Ok(templates)
+    }
+
+    pub fn delete_template(&self, id: String) -> Result<()> {
+        let conn = self.db.connection();
+        let affected = conn.execute("DELETE FROM templates WHERE id = ?", params![id])?;
+        if affected == 0 {
+            return Err(AppError::NotFound(format!("Template with id {} not found", id)));
+        }
+        Ok(())
+    }
+
+    /// Renders the template without creating anything.
+    pub fn preview_template(&self, id: String, vars: &HashMap<String, String>) -> Result<RenderedTemplate> {
+        render_template(&self.get_template(id)?, vars)
+    }
+
+    /// Creates a note from the rendered template in `folder_id`, or the template's own
+    /// folder when that is `None`, and attaches its tags, creating missing ones.
+    pub fn create_note_from_template(
+        &self,
+        template_id: String,
+        folder_id: Option<String>,
+        vars: &HashMap<String, String>,
+    ) -> Result<TemplateNote> {
+        let rendered = self.preview_template(template_id, vars)?;
+        let folder_id = folder_id.or(rendered.folder_id);
+
+        let note = NoteService::new(self.db).create_note(folder_id, rendered.title, rendered.content)?;
+        let tags = TagService::new(self.db);
+        for name in rendered.tags {
+            let tag = match tags.find_tag_by_name(&name)? {
+                Some(tag) => tag,
+                None => tags.create_tag(name, None)?,
+            };
+            tags.add_tag_to_note(note.id.clone(), tag.id)?;
+        }
+        BacklinkService::new(self.db).sync_links(note.id.clone(), &note.content)?;
+
+        Ok(TemplateNote { note, cursor: rendered.cursor })
+    }
+
+    fn map_row_to_template(row: &rusqlite::Row) -> rusqlite::Result<Template> {
+        let title: String = row.get(2)?;
+        let content: String = row.get(3)?;
+        let tags: String = row.get(5)?;
+        let mut variables = template_variables(&title);
+        for name in template_variables(&content) {
+            if !variables.contains(&name) {
+                variables.push(name);
+            }
+        }
+        Ok(Template {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            title,
+            content,
+            folder_id: row.get(4)?,
+            // A hand-edited, unreadable list only loses the default tags
+            tags: serde_json::from_str(&tags).unwrap_or_default(),
+            variables,
+            created_at: row.get(6)?,
+            updated_at: row.get(7)?,
+        })
+    }
+}
+
+fn encode_tags(tags: &[String]) -> Result<String> {
+    serde_json::to_string(tags).map_err(|e| AppError::validation(e.to_string()))
+}
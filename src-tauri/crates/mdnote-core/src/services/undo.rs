@@ -0,0 +1,463 @@
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use crate::errors::{AppError, Result};
+use crate::services::database::DatabaseService;
+use crate::services::events::ChangeEvent;
+
+/// Older actions are forgotten once a new one would exceed this many.
+pub const MAX_UNDO_ACTIONS: i64 = 100;
+
+/// Tables whose changes can be undone, with the columns that identify a row.
+const TRACKED_TABLES: &[(&str, &[&str])] = &[
+    ("folders", &["id"]),
+    ("notes", &["id"]),
+    ("tags", &["id"]),
+    ("note_tags", &["note_id", "tag_id"]),
+    ("backlinks", &["source_id", "target_id"]),
+    ("tasks", &["note_id", "line"]),
+    ("note_properties", &["note_id", "key"]),
+    ("reminders", &["id"]),
+];
+
+type Row = Map<String, Value>;
+
+/// A user-level action in the undo history.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UndoAction {
+    pub id: i64,
+    pub label: String,
+    pub created_at: i64,
+    /// True once undone; the action can then be redone.
+    pub undone: bool,
+}
+
+/// A row an action changed: `old_row` is `None` for an insert and `new_row` for a delete.
+struct LoggedChange {
+    table: String,
+    old_row: Option<Row>,
+    new_row: Option<Row>,
+}
+
+/// (Re)creates the triggers that log changes to the tracked tables while an action is
+/// being recorded on this connection. They are TEMP objects, so writes from other
+/// connections and processes never land in this connection's action. They capture the
+/// columns each table has now, so they are installed again whenever the connection opens
+/// or migrates. Tables a later migration creates are skipped until it has run.
+pub fn install_undo_triggers(db: &DatabaseService) -> Result<()> {
+    let conn = db.connection();
+    conn.execute_batch(
+        "CREATE TEMP TABLE IF NOT EXISTS undo_recording (
+             id INTEGER PRIMARY KEY CHECK (id = 1),
+             action_id INTEGER
+         );
+         INSERT OR IGNORE INTO temp.undo_recording (id, action_id) VALUES (1, NULL);",
+    )?;
+    for (table, keys) in TRACKED_TABLES {
+        let columns = table_columns(conn, table)?;
+        if columns.is_empty() {
+            continue;
+        }
+        let key = |prefix: &str| {
+            let values: Vec<String> = keys.iter().map(|k| format!("{}\"{}\"", prefix, k)).collect();
+            format!("json_array({})", values.join(", "))
+        };
+        let old_row = row_json(&columns, "old.");
+        let new_row = row_json(&columns, "new.");
+        let recording = "(SELECT action_id FROM temp.undo_recording)";
+
+        conn.execute_batch(&format!(
+            "DROP TRIGGER IF EXISTS temp.undo_{table}_ai;
+             DROP TRIGGER IF EXISTS temp.undo_{table}_au;
+             DROP TRIGGER IF EXISTS temp.undo_{table}_ad;
+             CREATE TEMP TRIGGER undo_{table}_ai AFTER INSERT ON main.{table} WHEN {recording} IS NOT NULL BEGIN
+                 INSERT INTO undo_log (action_id, table_name, row_key, old_row, new_row)
+                 VALUES ({recording}, '{table}', {new_key}, NULL, {new_row});
+             END;
+             CREATE TEMP TRIGGER undo_{table}_au AFTER UPDATE ON main.{table} WHEN {recording} IS NOT NULL AND {old_row} IS NOT {new_row} BEGIN
+                 INSERT INTO undo_log (action_id, table_name, row_key, old_row, new_row)
+                 VALUES ({recording}, '{table}', {old_key}, {old_row}, {new_row});
+             END;
+             CREATE TEMP TRIGGER undo_{table}_ad AFTER DELETE ON main.{table} WHEN {recording} IS NOT NULL BEGIN
+                 INSERT INTO undo_log (action_id, table_name, row_key, old_row, new_row)
+                 VALUES ({recording}, '{table}', {old_key}, {old_row}, NULL);
+             END;",
+            new_key = key("new."),
+            old_key = key("old."),
+        ))?;
+    }
+    Ok(())
+}
+
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare_cached("SELECT name FROM pragma_table_info(?) ORDER BY cid")?;
+    let columns = stmt
+        .query_map(params![table], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(columns)
+}
+
+/// A `json_object(...)` expression over `columns`, each read as `<prefix>"column"`.
+fn row_json(columns: &[String], prefix: &str) -> String {
+    let pairs: Vec<String> = columns.iter().map(|c| format!("'{}', {}\"{}\"", c, prefix, c)).collect();
+    format!("json_object({})", pairs.join(", "))
+}
+
+fn sql_value(value: Option<&Value>) -> SqlValue {
+    match value {
+        None | Some(Value::Null) => SqlValue::Null,
+        Some(Value::Bool(b)) => SqlValue::Integer(*b as i64),
+        Some(Value::Number(n)) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Some(Value::String(s)) => SqlValue::Text(s.clone()),
+        Some(other) => SqlValue::Text(other.to_string()),
+    }
+}
+
+/// Whether `current` is the row `expected` describes. Columns added after the change was
+/// logged are not compared.
+fn rows_match(current: Option<&Row>, expected: Option<&Row>) -> bool {
+    match (current, expected) {
+        (None, None) => true,
+        (Some(current), Some(expected)) => expected.iter().all(|(column, value)| current.get(column) == Some(value)),
+        _ => false,
+    }
+}
+
+fn row_str(row: Option<&Row>, column: &str) -> Option<String> {
+    row.and_then(|r| r.get(column)).and_then(Value::as_str).map(str::to_string)
+}
+
+fn describe(table: &str) -> &'static str {
+    match table {
+        "folders" => "A folder",
+        "notes" => "A note",
+        "tags" => "A tag",
+        "note_tags" => "A tag assignment",
+        "tasks" => "A task",
+        "note_properties" => "A note property",
+        "reminders" => "A reminder",
+        _ => "A link",
+    }
+}
+
+pub struct UndoService<'a> {
+    db: &'a DatabaseService,
+}
+
+impl<'a> UndoService<'a> {
+    pub fn new(db: &'a DatabaseService) -> Self {
+        Self { db }
+    }
+
+    /// Runs `f` as one undoable action called `label`: every row it changes in the tracked
+    /// tables is logged so `undo` can put it back. Calls nested inside another action become
+    /// part of it. Recording a new action discards the ones that were undone.
+    pub fn record<T>(&self, label: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        if self.recording()?.is_some() {
+            return f();
+        }
+
+        let conn = self.db.connection();
+        conn.execute(
+            "INSERT INTO undo_actions (label, created_at) VALUES (?, ?)",
+            params![label, chrono::Utc::now().timestamp_millis()],
+        )?;
+        let action_id = conn.last_insert_rowid();
+        conn.execute("UPDATE temp.undo_recording SET action_id = ?", params![action_id])?;
+
+        // Whatever `f` committed before failing is kept in the action, so it can be undone too
+        let result = f();
+        let finished = self.finish(action_id);
+        let value = result?;
+        finished?;
+        Ok(value)
+    }
+
+    fn finish(&self, action_id: i64) -> Result<()> {
+        let conn = self.db.connection();
+        conn.execute("UPDATE temp.undo_recording SET action_id = NULL", [])?;
+
+        let changed: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM undo_log WHERE action_id = ?)",
+            params![action_id],
+            |row| row.get(0),
+        )?;
+        if !changed {
+            conn.execute("DELETE FROM undo_actions WHERE id = ?", params![action_id])?;
+            return Ok(());
+        }
+
+        conn.execute("DELETE FROM undo_actions WHERE undone = 1", [])?;
+        conn.execute(
+            "DELETE FROM undo_actions WHERE id NOT IN (SELECT id FROM undo_actions ORDER BY id DESC LIMIT ?)",
+            params![MAX_UNDO_ACTIONS],
+        )?;
+        Ok(())
+    }
+
+    /// The action this connection is recording right now, if any.
+    fn recording(&self) -> Result<Option<i64>> {
+        let recording = self.db.connection().query_row(
+            "SELECT action_id FROM temp.undo_recording WHERE id = 1",
+            [],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(recording.flatten())
+    }
+
+    /// Stops recording an action that was interrupted, e.g. by a crash. What it logged
+    /// until then stays undoable.
+    pub fn abandon_recording(&self) -> Result<()> {
+        self.db.connection().execute("UPDATE temp.undo_recording SET action_id = NULL", [])?;
+        Ok(())
+    }
+
+    /// Reverts the most recent action that hasn't been undone and returns it, or `None`
+    /// when there is nothing to undo. Fails without changing anything if a row it touched
+    /// was changed since by something outside the history.
+    pub fn undo(&self) -> Result<Option<UndoAction>> {
+        let Some(mut action) = self.next_action(false)? else { return Ok(None) };
+        self.replay(action.id, true)?;
+        action.undone = true;
+        Ok(Some(action))
+    }
+
+    /// Re-applies the earliest undone action and returns it, or `None` when there is
+    /// nothing to redo.
+    pub fn redo(&self) -> Result<Option<UndoAction>> {
+        let Some(mut action) = self.next_action(true)? else { return Ok(None) };
+        self.replay(action.id, false)?;
+        action.undone = false;
+        Ok(Some(action))
+    }
+
+    /// The newest `limit` actions, newest first, undone ones included.
+    pub fn list_history(&self, limit: i64) -> Result<Vec<UndoAction>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, label, created_at, undone FROM undo_actions ORDER BY id DESC LIMIT ?",
+        )?;
+        let actions = stmt
+            .query_map(params![limit], Self::map_row_to_action)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(actions)
+    }
+
+    /// Drops every action that touched the note, so no earlier plaintext copy of it is
+    /// left in the history once it is encrypted.
+    pub fn forget_note(&self, note_id: &str) -> Result<()> {
+        self.db.connection().execute(
+            "DELETE FROM undo_actions WHERE id IN
+             (SELECT action_id FROM undo_log WHERE table_name = 'notes' AND row_key = json_array(?))",
+            params![note_id],
+        )?;
+        Ok(())
+    }
+
+    fn next_action(&self, undone: bool) -> Result<Option<UndoAction>> {
+        // Undo walks back from the newest action; redo forward from the oldest undone one
+        let order = if undone { "ASC" } else { "DESC" };
+        let action = self.db.connection().query_row(
+            &format!("SELECT id, label, created_at, undone FROM undo_actions WHERE undone = ? ORDER BY id {} LIMIT 1", order),
+            params![undone],
+            Self::map_row_to_action,
+        ).optional()?;
+        Ok(action)
+    }
+
+    fn map_row_to_action(row: &rusqlite::Row) -> rusqlite::Result<UndoAction> {
+        Ok(UndoAction {
+            id: row.get(0)?,
+            label: row.get(1)?,
+            created_at: row.get(2)?,
+            undone: row.get(3)?,
+        })
+    }
+
+    fn logged_changes(&self, action_id: i64) -> Result<Vec<LoggedChange>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare_cached(
+            "SELECT table_name, old_row, new_row FROM undo_log WHERE action_id = ? ORDER BY seq",
+        )?;
+        let rows = stmt
+            .query_map(params![action_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, Option<String>>(2)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let parse = |json: Option<String>| -> Result<Option<Row>> {
+            json.map(|j| serde_json::from_str(&j).map_err(|e| AppError::corrupt(format!("Unreadable undo log entry: {}", e))))
+                .transpose()
+        };
+        rows.into_iter()
+            .map(|(table, old_row, new_row)| Ok(LoggedChange { table, old_row: parse(old_row)?, new_row: parse(new_row)? }))
+            .collect()
+    }
+
+    /// Puts every row the action changed back the way it was (`undo`) or the way the
+    /// action left it, in one transaction.
+    fn replay(&self, action_id: i64, undo: bool) -> Result<()> {
+        if self.recording()?.is_some() {
+            return Err(AppError::validation("Cannot undo or redo while an action is being recorded"));
+        }
+        let mut changes = self.logged_changes(action_id)?;
+        if undo {
+            changes.reverse();
+        }
+
+        let conn = self.db.connection();
+        let tx = conn.unchecked_transaction()?;
+        // Rows come back one at a time, so parents and children only line up by the commit
+        tx.execute_batch("PRAGMA defer_foreign_keys = ON")?;
+
+        let mut touched = Touched::default();
+        for change in &changes {
+            let (from, to) = if undo {
+                (change.new_row.as_ref(), change.old_row.as_ref())
+            } else {
+                (change.old_row.as_ref(), change.new_row.as_ref())
+            };
+            self.apply(&change.table, from, to)?;
+            touched.add(&change.table, from, to);
+        }
+
+        tx.execute("UPDATE undo_actions SET undone = ? WHERE id = ?", params![undo, action_id])?;
+        touched.record(self.db)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Turns the row that is `from` into `to`. A row already in its `to` state is left
+    /// alone, e.g. one a cascade has already removed.
+    fn apply(&self, table: &str, from: Option<&Row>, to: Option<&Row>) -> Result<()> {
+        let (_, keys) = TRACKED_TABLES
+            .iter()
+            .find(|(name, _)| *name == table)
+            .ok_or_else(|| AppError::corrupt(format!("Undo log names an unknown table {}", table)))?;
+        let identity = to.or(from).ok_or_else(|| AppError::corrupt("Undo log entry has no row".to_string()))?;
+
+        let conn = self.db.connection();
+        let columns = table_columns(conn, table)?;
+        let where_clause = keys.iter().map(|k| format!("\"{}\" = ?", k)).collect::<Vec<_>>().join(" AND ");
+        let key_values: Vec<SqlValue> = keys.iter().map(|k| sql_value(identity.get(*k))).collect();
+
+        let current: Option<String> = conn.query_row(
+            &format!("SELECT {} FROM {} WHERE {}", row_json(&columns, ""), table, where_clause),
+            rusqlite::params_from_iter(&key_values),
+            |row| row.get(0),
+        ).optional()?;
+        let current: Option<Row> = current
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|e| AppError::corrupt(e.to_string()))?;
+
+        if rows_match(current.as_ref(), to) {
+            return Ok(());
+        }
+        if !rows_match(current.as_ref(), from) {
+            return Err(AppError::Conflict {
+                message: format!("{} was changed after this action, so it can't be undone or redone", describe(table)),
+                current: None,
+            });
+        }
+
+        match to {
+            None => {
+                conn.execute(&format!("DELETE FROM {} WHERE {}", table, where_clause), rusqlite::params_from_iter(&key_values))?;
+            }
+            Some(row) => {
+                let present: Vec<&String> = columns.iter().filter(|c| row.contains_key(*c)).collect();
+                let values = present.iter().map(|c| sql_value(row.get(*c)));
+                if current.is_none() {
+                    let names = present.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<_>>().join(", ");
+                    let placeholders = vec!["?"; present.len()].join(", ");
+                    conn.execute(
+                        &format!("INSERT INTO {} ({}) VALUES ({})", table, names, placeholders),
+                        rusqlite::params_from_iter(values),
+                    )?;
+                } else {
+                    let assignments = present.iter().map(|c| format!("\"{}\" = ?", c)).collect::<Vec<_>>().join(", ");
+                    conn.execute(
+                        &format!("UPDATE {} SET {} WHERE {}", table, assignments, where_clause),
+                        rusqlite::params_from_iter(values.chain(key_values)),
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What an undo or redo changed, turned into the change events the services would have
+/// recorded for the same edits.
+#[derive(Default)]
+struct Touched {
+    events: Vec<ChangeEvent>,
+    tag_ids: Vec<String>,
+    tagged_notes: Vec<String>,
+    link_sources: Vec<String>,
+}
+
+impl Touched {
+    fn add(&mut self, table: &str, from: Option<&Row>, to: Option<&Row>) {
+        let row = to.or(from);
+        let push = |list: &mut Vec<String>, value: Option<String>| {
+            if let Some(value) = value.filter(|v| !list.contains(v)) {
+                list.push(value);
+            }
+        };
+
+        match table {
+            "notes" => {
+                let Some(id) = row_str(row, "id") else { return };
+                let folder_id = row_str(to, "folder_id");
+                self.events.push(match (from, to) {
+                    (None, _) => ChangeEvent::NoteCreated { id, folder_id },
+                    (_, None) => ChangeEvent::NoteDeleted { id },
+                    _ => ChangeEvent::NoteUpdated { id, folder_id },
+                });
+            }
+            "folders" => {
+                let Some(id) = row_str(row, "id") else { return };
+                let parent_id = row_str(to, "parent_id");
+                self.events.push(match (from, to) {
+                    (Some(_), Some(_)) if row_str(from, "parent_id") != parent_id => ChangeEvent::FolderMoved { id, parent_id },
+                    _ => ChangeEvent::FolderChanged { id },
+                });
+            }
+            "tags" => push(&mut self.tag_ids, row_str(row, "id")),
+            "note_tags" => {
+                push(&mut self.tag_ids, row_str(row, "tag_id"));
+                push(&mut self.tagged_notes, row_str(row, "note_id"));
+            }
+            "reminders" => {
+                let (Some(id), Some(note_id)) = (row_str(row, "id"), row_str(row, "note_id")) else { return };
+                self.events.push(ChangeEvent::ReminderChanged { id, note_id });
+            }
+            // Restored along with the note they come from, which has its own event
+            "tasks" | "note_properties" => {}
+            _ => push(&mut self.link_sources, row_str(row, "source_id")),
+        }
+    }
+
+    fn record(self, db: &DatabaseService) -> Result<()> {
+        for event in self.events {
+            db.record(event);
+        }
+        if !self.tag_ids.is_empty() {
+            db.record(ChangeEvent::TagChanged { tag_ids: self.tag_ids, note_ids: self.tagged_notes });
+        }
+        for source_id in self.link_sources {
+            let mut stmt = db.connection().prepare_cached("SELECT target_id FROM backlinks WHERE source_id = ?")?;
+            let target_ids = stmt
+                .query_map(params![source_id], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            db.record(ChangeEvent::LinksChanged { source_id, target_ids });
+        }
+        Ok(())
+    }
+}
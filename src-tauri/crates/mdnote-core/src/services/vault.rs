@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use ulid::Ulid;
+use crate::models::vault::Vault;
+use crate::errors::{AppError, Result};
+
+const REGISTRY_FILE: &str = "vaults.json";
+pub const DEFAULT_VAULT_NAME: &str = "Default";
+pub const MAX_VAULT_NAME_LEN: usize = 64;
+
+#[derive(Default, Serialize, Deserialize)]
+struct Registry {
+    vaults: Vec<Vault>,
+    last_opened_id: Option<String>,
+}
+
+/// Keeps track of the known vaults in `vaults.json` inside the app data directory.
+/// Each vault is a directory with its own `mdnote.db`.
+pub struct VaultService {
+    registry_path: PathBuf,
+    // Serializes read-modify-write cycles on the registry file
+    lock: Mutex<()>,
+}
+
+impl VaultService {
+    pub fn new(app_data_dir: &Path) -> Self {
+        Self {
+            registry_path: app_data_dir.join(REGISTRY_FILE),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn list_vaults(&self) -> Result<Vec<Vault>> {
+        Ok(self.load()?.vaults)
+    }
+
+    pub fn get_vault(&self, id: &str) -> Result<Vault> {
+        self.load()?
+            .vaults
+            .into_iter()
+            .find(|v| v.id == id)
+            .ok_or_else(|| AppError::NotFound(format!("Vault with id {} not found", id)))
+    }
+
+    /// The vault opened most recently, if it is still registered.
+    pub fn last_opened(&self) -> Result<Option<Vault>> {
+        let registry = self.load()?;
+        Ok(registry
+            .last_opened_id
+            .and_then(|id| registry.vaults.into_iter().find(|v| v.id == id)))
+    }
+
+    /// Registers `dir` as a new vault. The database itself is created when it is first opened.
+    pub fn create_vault(&self, name: String, dir: PathBuf) -> Result<Vault> {
+        let name = validate_vault_name(&name)?;
+        fs::create_dir_all(&dir)?;
+        let path = dir
+            .canonicalize()
+            ?
+            .to_string_lossy()
+            .into_owned();
+
+        self.update(|registry| {
+            if let Some(existing) = registry.vaults.iter().find(|v| v.path == path) {
+                return Err(AppError::AlreadyExists(format!(
+                    "{} is already registered as vault {}",
+                    path, existing.name
+                )));
+            }
+
+            let vault = Vault {
+                id: Ulid::new().to_string(),
+                name,
+                path,
+                created_at: chrono::Utc::now().timestamp_millis(),
+                last_opened_at: None,
+            };
+            registry.vaults.push(vault.clone());
+            Ok(vault)
+        })
+    }
+
+    pub fn rename_vault(&self, id: &str, name: String) -> Result<Vault> {
+        let name = validate_vault_name(&name)?;
+        self.update(|registry| {
+            let vault = registry
+                .vaults
+                .iter_mut()
+                .find(|v| v.id == id)
+                .ok_or_else(|| AppError::NotFound(format!("Vault with id {} not found", id)))?;
+            vault.name = name;
+            Ok(vault.clone())
+        })
+    }
+
+    /// Records `id` as the vault to reopen on the next start.
+    pub fn mark_opened(&self, id: &str) -> Result<Vault> {
+        self.update(|registry| {
+            let vault = registry
+                .vaults
+                .iter_mut()
+                .find(|v| v.id == id)
+                .ok_or_else(|| AppError::NotFound(format!("Vault with id {} not found", id)))?;
+            vault.last_opened_at = Some(chrono::Utc::now().timestamp_millis());
+            let vault = vault.clone();
+            registry.last_opened_id = Some(vault.id.clone());
+            Ok(vault)
+        })
+    }
+
+    /// Returns the vault to open at startup: the last opened one, otherwise the first
+    /// registered one, registering `app_data_dir` itself as the default vault when none exist.
+    pub fn startup_vault(&self, app_data_dir: &Path) -> Result<Vault> {
+        if let Some(vault) = self.last_opened()? {
+            return Ok(vault);
+        }
+        if let Some(vault) = self.list_vaults()?.into_iter().next() {
+            return Ok(vault);
+        }
+        self.create_vault(DEFAULT_VAULT_NAME.to_string(), app_data_dir.to_path_buf())
+    }
+
+    fn load(&self) -> Result<Registry> {
+        if !self.registry_path.exists() {
+            return Ok(Registry::default());
+        }
+        let json = fs::read_to_string(&self.registry_path)?;
+        serde_json::from_str(&json).map_err(|e| AppError::io("Invalid vault registry", e))
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut Registry) -> Result<T>) -> Result<T> {
+        let _guard = self.lock.lock().map_err(|e| AppError::lock(e.to_string()))?;
+        let mut registry = self.load()?;
+        let result = f(&mut registry)?;
+
+        let json = serde_json::to_string_pretty(&registry).map_err(|e| AppError::io("Could not write the vault registry", e))?;
+        // Write then rename, so a crash never leaves a half-written registry behind
+        let tmp_path = self.registry_path.with_extension("json.tmp");
+        fs::write(&tmp_path, json)?;
+        fs::rename(&tmp_path, &self.registry_path)?;
+        Ok(result)
+    }
+}
+
+fn validate_vault_name(name: &str) -> Result<String> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err(AppError::invalid("name", "Vault name must not be empty"));
+    }
+    if trimmed.chars().count() > MAX_VAULT_NAME_LEN {
+        return Err(AppError::invalid("name", format!(
+            "Vault name must be at most {} characters",
+            MAX_VAULT_NAME_LEN
+        )));
+    }
+    Ok(trimmed.to_string())
+}
@@ -0,0 +1,30 @@
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use mdnote_core::DatabaseService;
+
+/// A fresh migrated database that lives in memory.
+pub fn memory_db() -> DatabaseService {
+    DatabaseService::open_in_memory().expect("in-memory database")
+}
+
+/// A unique directory under the system temp dir, removed again on drop.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("mdnote-core-test-{}", ulid::Ulid::new()));
+        std::fs::create_dir_all(&path).expect("create temp dir");
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
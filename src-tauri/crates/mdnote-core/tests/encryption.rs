@@ -0,0 +1,168 @@
+mod common;
+
+use common::{memory_db, TempDir};
+use mdnote_core::errors::AppError;
+use mdnote_core::services::backlink::BacklinkService;
+use mdnote_core::services::database::{DbPool, DbState, DB_FILE_NAME};
+use mdnote_core::services::encryption::{self, KeyParams, NoteKeyring, KEY_PARAMS_FILE, PREVIOUS_KEY_PARAMS_FILE, REENCRYPT_MARKER};
+use mdnote_core::services::note::NoteService;
+use mdnote_core::services::search::SearchService;
+use mdnote_core::services::tag::TagService;
+
+#[test]
+fn key_derivation_is_deterministic_per_salt() {
+    let params = KeyParams::generate();
+    assert_eq!(params.derive("passphrase").unwrap(), params.derive("passphrase").unwrap());
+    assert_ne!(params.derive("passphrase").unwrap(), params.derive("passphrasf").unwrap());
+    assert_ne!(params.derive("passphrase").unwrap(), KeyParams::generate().derive("passphrase").unwrap());
+}
+
+#[test]
+fn enable_encryption_validates_before_touching_the_vault() {
+    let dir = TempDir::new();
+    let state = DbState::new();
+    state.open("vault".into(), DbPool::new(dir.path().to_path_buf(), 1).unwrap()).unwrap();
+
+    let result = encryption::enable_encryption(&state, "vault".into(), dir.path().to_path_buf(), "short");
+    assert!(matches!(result, Err(AppError::Validation { field: Some(f), .. }) if f == "passphrase"));
+    assert!(state.pool().is_ok());
+    assert!(!encryption::is_encrypted(dir.path()));
+}
+
+#[test]
+fn an_interrupted_reencryption_is_rolled_back_before_the_swap() {
+    let dir = TempDir::new();
+    DbPool::new(dir.path().to_path_buf(), 1).unwrap();
+    let old = KeyParams::generate();
+
+    // Encrypting a plaintext vault stopped before the new database replaced the old one
+    std::fs::write(dir.path().join(format!("{}.encrypting", DB_FILE_NAME)), "partial").unwrap();
+    std::fs::write(dir.path().join(REENCRYPT_MARKER), "").unwrap();
+    std::fs::write(dir.path().join(KEY_PARAMS_FILE), KeyParams::generate().to_json().unwrap()).unwrap();
+    encryption::recover_reencryption(dir.path()).unwrap();
+    assert!(!encryption::is_encrypted(dir.path()));
+    assert!(DbPool::new(dir.path().to_path_buf(), 1).is_ok());
+
+    // Changing the passphrase stopped at the same point keeps the previous params
+    std::fs::write(dir.path().join(format!("{}.encrypting", DB_FILE_NAME)), "partial").unwrap();
+    std::fs::write(dir.path().join(PREVIOUS_KEY_PARAMS_FILE), old.to_json().unwrap()).unwrap();
+    std::fs::write(dir.path().join(REENCRYPT_MARKER), "").unwrap();
+    std::fs::write(dir.path().join(KEY_PARAMS_FILE), KeyParams::generate().to_json().unwrap()).unwrap();
+    encryption::recover_reencryption(dir.path()).unwrap();
+    assert_eq!(KeyParams::load(dir.path()).unwrap().unwrap().salt, old.salt);
+    for leftover in [format!("{}.encrypting", DB_FILE_NAME), PREVIOUS_KEY_PARAMS_FILE.into(), REENCRYPT_MARKER.into()] {
+        assert!(!dir.path().join(leftover).exists());
+    }
+}
+
+#[test]
+fn an_interrupted_reencryption_is_finished_after_the_swap() {
+    let dir = TempDir::new();
+    let new = KeyParams::generate();
+    std::fs::write(dir.path().join(PREVIOUS_KEY_PARAMS_FILE), KeyParams::generate().to_json().unwrap()).unwrap();
+    std::fs::write(dir.path().join(REENCRYPT_MARKER), "").unwrap();
+    std::fs::write(dir.path().join(KEY_PARAMS_FILE), new.to_json().unwrap()).unwrap();
+
+    encryption::recover_reencryption(dir.path()).unwrap();
+    assert_eq!(KeyParams::load(dir.path()).unwrap().unwrap().salt, new.salt);
+    assert!(!dir.path().join(PREVIOUS_KEY_PARAMS_FILE).exists());
+    assert!(!dir.path().join(REENCRYPT_MARKER).exists());
+}
+
+#[test]
+fn encryption_fails_while_another_program_has_the_vault_open() {
+    let dir = TempDir::new();
+    let state = DbState::new();
+    state.open("vault".into(), DbPool::new(dir.path().to_path_buf(), 1).unwrap()).unwrap();
+    // The CLI has the vault open too, so it can't be locked for the copy
+    let other = mdnote_core::DatabaseService::open(dir.path().to_path_buf(), None).unwrap();
+
+    let result = encryption::enable_encryption(&state, "vault".into(), dir.path().to_path_buf(), "long passphrase");
+    assert!(matches!(result, Err(AppError::LockError { .. })));
+    drop(other);
+    assert!(!encryption::is_encrypted(dir.path()));
+    assert!(!dir.path().join(REENCRYPT_MARKER).exists());
+    state.pool().unwrap().with_writer(|db| NoteService::new(db).create_note(None, "a".into(), "b".into())).unwrap();
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn encrypted_vault_needs_the_passphrase() {
+    let dir = TempDir::new();
+    let state = DbState::new();
+    state.open("vault".into(), DbPool::new(dir.path().to_path_buf(), 1).unwrap()).unwrap();
+    let note = state.pool().unwrap()
+        .with_writer(|db| NoteService::new(db).create_note(None, "a".into(), "secret".into()))
+        .unwrap();
+
+    encryption::enable_encryption(&state, "vault".into(), dir.path().to_path_buf(), "long passphrase").unwrap();
+    assert!(encryption::is_encrypted(dir.path()));
+    state.lock().unwrap();
+
+    let wrong = encryption::unlock_vault(&state, "vault".into(), dir.path().to_path_buf(), "wrong passphrase");
+    assert!(matches!(wrong, Err(AppError::Locked(_))));
+    encryption::unlock_vault(&state, "vault".into(), dir.path().to_path_buf(), "long passphrase").unwrap();
+    let id = note.id.clone();
+    assert_eq!(state.pool().unwrap().with_reader(|db| NoteService::new(db).get_note(id)).unwrap().content, "secret");
+}
+
+#[test]
+fn encrypted_notes_are_hidden_until_unlocked() {
+    let db = memory_db();
+    let keyring = NoteKeyring::new();
+    let notes = NoteService::new(&db).with_keyring(&keyring);
+    let search = SearchService::new(&db);
+    let note = notes.create_note(None, "secret".into(), "zebrafish password #vault/keys".into()).unwrap();
+    assert_eq!(search.search_notes("zebrafish".into(), 10).unwrap().len(), 1);
+    assert_eq!(TagService::new(&db).get_note_tags(note.id.clone()).unwrap().len(), 1);
+
+    assert!(matches!(notes.encrypt_note(note.id.clone(), "short"), Err(AppError::Validation { .. })));
+    let encrypted = notes.encrypt_note(note.id.clone(), "correct horse").unwrap();
+    assert!(encrypted.encrypted && !encrypted.locked);
+    assert!(search.search_notes("zebrafish".into(), 10).unwrap().is_empty());
+
+    let stored: String = db
+        .connection()
+        .query_row("SELECT content FROM notes WHERE id = ?", [&note.id], |row| row.get(0))
+        .unwrap();
+    assert!(!stored.contains("zebrafish"));
+    // The hashtag's name would say what the note is about
+    assert!(TagService::new(&db).get_note_tags(note.id.clone()).unwrap().is_empty());
+
+    let (listed, _) = notes.list_notes(None, None, false, 10, 0).unwrap();
+    assert!(listed[0].encrypted && listed[0].excerpt.is_empty());
+
+    notes.update_note(note.id.clone(), None, Some("new body".into()), None, None).unwrap();
+    keyring.clear().unwrap();
+    let locked = notes.get_note(note.id.clone()).unwrap();
+    assert!(locked.locked && locked.content.is_empty());
+    assert!(matches!(notes.update_note(note.id.clone(), None, Some("x".into()), None, None), Err(AppError::Locked(_))));
+
+    assert!(matches!(notes.decrypt_note_for_session(note.id.clone(), "wrong pass"), Err(AppError::Locked(_))));
+    assert_eq!(notes.decrypt_note_for_session(note.id, "correct horse").unwrap().content, "new body");
+    assert!(search.search_notes("body".into(), 10).unwrap().is_empty());
+}
+
+#[test]
+fn encrypted_notes_keep_links_without_context() {
+    let db = memory_db();
+    let keyring = NoteKeyring::new();
+    let notes = NoteService::new(&db).with_keyring(&keyring);
+    let links = BacklinkService::new(&db);
+    let target = notes.create_note(None, "target".into(), String::new()).unwrap();
+    let content = format!("the vault code is 4711, see [[{}]]", target.id);
+    let source = notes.create_note(None, "secret".into(), content.clone()).unwrap();
+    links.sync_links(source.id.clone(), &content).unwrap();
+    assert!(links.get_backlinks(target.id.clone()).unwrap()[0].context.as_deref().unwrap().contains("4711"));
+
+    notes.encrypt_note(source.id.clone(), "correct horse").unwrap();
+    let backlinks = links.get_backlinks(target.id.clone()).unwrap();
+    assert_eq!(backlinks[0].source_id, source.id);
+    assert!(backlinks[0].context.is_none());
+
+    // Saving the unlocked note from the editor keeps the link but not the text around it
+    links.sync_links(source.id.clone(), &content).unwrap();
+    let backlinks = links.get_backlinks(target.id).unwrap();
+    assert_eq!(backlinks.len(), 1);
+    assert!(backlinks[0].context.is_none());
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use common::{memory_db, TempDir};
+use mdnote_core::errors::AppError;
+use mdnote_core::services::backlink::BacklinkService;
+use mdnote_core::services::database::{DbPool, DbState};
+use mdnote_core::services::events::ChangeEvent;
+use mdnote_core::services::folder::FolderService;
+use mdnote_core::services::note::NoteService;
+use mdnote_core::services::tag::TagService;
+
+/// A vault whose published events are collected for inspection.
+fn recording_state(dir: &TempDir) -> (DbState, Arc<Mutex<Vec<ChangeEvent>>>) {
+    let state = DbState::new();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = seen.clone();
+    state.changes().subscribe(Arc::new(move |event: &ChangeEvent| sink.lock().unwrap().push(event.clone())));
+    state.open("test".into(), DbPool::new(dir.path().to_path_buf(), 1).unwrap()).unwrap();
+    (state, seen)
+}
+
+fn names(events: &[ChangeEvent]) -> Vec<&'static str> {
+    events.iter().map(ChangeEvent::name).collect()
+}
+
+#[test]
+fn writes_publish_what_they_changed() {
+    let dir = TempDir::new();
+    let (state, seen) = recording_state(&dir);
+    let pool = state.pool().unwrap();
+
+    let (folder, target, note) = pool.with_writer(|db| {
+        let folder = FolderService::new(db).create_folder("Inbox".into(), None)?;
+        let target = NoteService::new(db).create_note(None, "Target".into(), String::new())?;
+        let note = NoteService::new(db).create_note(Some(folder.id.clone()), "Plan".into(), "#work".into())?;
+        Ok((folder, target, note))
+    }).unwrap();
+
+    let events = std::mem::take(&mut *seen.lock().unwrap());
+    assert_eq!(names(&events), ["folder:changed", "note:created", "note:created", "tag:changed", "tag:changed"]);
+    assert_eq!(events[2], ChangeEvent::NoteCreated { id: note.id.clone(), folder_id: Some(folder.id.clone()) });
+    assert!(matches!(&events[4], ChangeEvent::TagChanged { note_ids, .. } if *note_ids == [note.id.clone()]));
+
+    pool.with_writer(|db| {
+        let content = format!("see [[{}]]", target.id);
+        NoteService::new(db).update_note(note.id.clone(), None, Some(content.clone()), Some(None), None)?;
+        BacklinkService::new(db).sync_links(note.id.clone(), &content)?;
+        FolderService::new(db).update_folder(folder.id.clone(), None, Some(None))
+    }).unwrap();
+
+    let events = std::mem::take(&mut *seen.lock().unwrap());
+    assert_eq!(names(&events), ["note:updated", "tag:changed", "links:changed", "folder:moved"]);
+    assert_eq!(events[0], ChangeEvent::NoteUpdated { id: note.id.clone(), folder_id: None });
+    assert_eq!(events[2], ChangeEvent::LinksChanged { source_id: note.id.clone(), target_ids: vec![target.id.clone()] });
+}
+
+#[test]
+fn rolled_back_changes_are_never_published() {
+    let dir = TempDir::new();
+    let (state, seen) = recording_state(&dir);
+
+    let result: mdnote_core::Result<()> = state.pool().unwrap().with_writer(|db| {
+        let tx = db.connection().unchecked_transaction()?;
+        FolderService::new(db).create_folder("Scratch".into(), None)?;
+        drop(tx);
+        Err(AppError::validation("changed my mind"))
+    });
+    assert!(result.is_err());
+    assert!(seen.lock().unwrap().is_empty());
+
+    // Changes committed before a later step fails did happen, so they are announced
+    let result: mdnote_core::Result<()> = state.pool().unwrap().with_writer(|db| {
+        TagService::new(db).create_tag("solo".into(), None)?;
+        NoteService::new(db).delete_note("missing".into())
+    });
+    assert!(result.is_err());
+    assert_eq!(names(&seen.lock().unwrap()), ["tag:changed"]);
+}
+
+#[test]
+fn tag_rename_reports_rewritten_notes() {
+    let mut db = memory_db();
+    db.track_changes();
+
+    let note = NoteService::new(&db).create_note(None, "Todo".into(), "call mum #home".into()).unwrap();
+    let tag = TagService::new(&db).find_tag_by_name("home").unwrap().unwrap();
+    db.take_changes();
+
+    TagService::new(&db).rename_tag(tag.id.clone(), "family".into()).unwrap();
+    assert_eq!(
+        db.take_changes(),
+        [
+            ChangeEvent::NoteUpdated { id: note.id.clone(), folder_id: None },
+            ChangeEvent::TagChanged { tag_ids: vec![tag.id], note_ids: vec![note.id] },
+        ]
+    );
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use common::memory_db;
+use mdnote_core::errors::AppError;
+use mdnote_core::services::backlink::BacklinkService;
+use mdnote_core::services::folder::FolderService;
+use mdnote_core::services::merge::merge_text;
+use mdnote_core::services::note::NoteService;
+use mdnote_core::services::search::SearchService;
+
+#[test]
+fn create_get_update_delete_note() {
+    let db = memory_db();
+    let notes = NoteService::new(&db);
+
+    let note = notes.create_note(None, "Title".into(), "Body".into()).unwrap();
+    assert_eq!(notes.get_note(note.id.clone()).unwrap().content, "Body");
+
+    let updated = notes.update_note(note.id.clone(), Some("New title".into()), None, None, None).unwrap();
+    assert_eq!((updated.title.as_str(), updated.content.as_str()), ("New title", "Body"));
+
+    notes.delete_note(note.id.clone()).unwrap();
+    assert!(matches!(notes.get_note(note.id.clone()), Err(AppError::NotFound(_))));
+    assert!(matches!(notes.delete_note(note.id), Err(AppError::NotFound(_))));
+}
+
+#[test]
+fn database_constraint_failures_map_to_error_codes() {
+    let db = memory_db();
+    let notes = NoteService::new(&db);
+
+    // The foreign key on notes.folder_id
+    let err = notes.create_note(Some("missing".into()), "Orphan".into(), String::new()).unwrap_err();
+    assert_eq!(err.code(), "NOT_FOUND");
+
+    let folder = FolderService::new(&db).create_folder("Inbox".into(), None).unwrap();
+    let err = db
+        .connection()
+        .execute("INSERT INTO folders (id, name, created_at) VALUES (?, 'Copy', 0)", [&folder.id])
+        .map_err(AppError::from)
+        .unwrap_err();
+    assert!(matches!(err, AppError::AlreadyExists(ref msg) if msg == "A folder with this id already exists"), "{:?}", err);
+
+    let err = db
+        .connection()
+        .execute("INSERT INTO notes (id, title, content, created_at, updated_at) VALUES ('n', NULL, '', 0, 0)", [])
+        .map_err(AppError::from)
+        .unwrap_err();
+    assert!(matches!(err, AppError::Validation { field: Some(ref f), .. } if f == "title"), "{:?}", err);
+
+    // Every table has its own name in the message, not a mangled plural
+    let note = notes.create_note(None, "Props".into(), "---\nstatus: done\n---\n".into()).unwrap();
+    let err = db
+        .connection()
+        .execute("INSERT INTO note_properties (note_id, key, type, value, position) VALUES (?, 'status', 'text', 'x', 1)", [&note.id])
+        .map_err(AppError::from)
+        .unwrap_err();
+    assert!(matches!(err, AppError::AlreadyExists(ref msg) if msg.starts_with("A note property with")), "{:?}", err);
+    let err = db
+        .connection()
+        .execute("INSERT INTO _migrations (version, applied_at) VALUES (1, 0)", [])
+        .map_err(AppError::from)
+        .unwrap_err();
+    assert!(matches!(err, AppError::AlreadyExists(ref msg) if msg == "A record with this version already exists"), "{:?}", err);
+}
+
+#[test]
+fn list_notes_filters_by_folder_and_pages() {
+    let db = memory_db();
+    let notes = NoteService::new(&db);
+    let folder = FolderService::new(&db).create_folder("Work".into(), None).unwrap();
+
+    for i in 0..3 {
+        notes.create_note(Some(folder.id.clone()), format!("Work {}", i), "w".into()).unwrap();
+    }
+    notes.create_note(None, "Root".into(), "r".into()).unwrap();
+
+    let (root, total) = notes.list_notes(None, None, false, 50, 0).unwrap();
+    assert_eq!((root.len(), total), (1, 1));
+
+    let (page, total) = notes.list_notes(Some(folder.id), None, false, 2, 0).unwrap();
+    assert_eq!((page.len(), total), (2, 3));
+}
+
+#[test]
+fn search_finds_notes_by_content_and_follows_updates() {
+    let db = memory_db();
+    let notes = NoteService::new(&db);
+    let search = SearchService::new(&db);
+    let note = notes.create_note(None, "Groceries".into(), "buy apples".into()).unwrap();
+
+    assert_eq!(search.search_notes("apples".into(), 10).unwrap()[0].id, note.id);
+
+    notes.update_note(note.id.clone(), None, Some("buy pears".into()), None, None).unwrap();
+    assert!(search.search_notes("apples".into(), 10).unwrap().is_empty());
+    assert_eq!(search.search_notes("pears".into(), 10).unwrap().len(), 1);
+
+    notes.delete_note(note.id).unwrap();
+    assert!(search.search_notes("pears".into(), 10).unwrap().is_empty());
+}
+
+#[test]
+fn sync_links_records_wiki_links() {
+    let db = memory_db();
+    let notes = NoteService::new(&db);
+    let links = BacklinkService::new(&db);
+    let target = notes.create_note(None, "Target".into(), "".into()).unwrap();
+    let source = notes.create_note(None, "Source".into(), "".into()).unwrap();
+
+    let content = format!("see [[{}]] and [[{}]]", target.id, source.id);
+    links.sync_links(source.id.clone(), &content).unwrap();
+
+    let backlinks = links.get_backlinks(target.id.clone()).unwrap();
+    assert_eq!(backlinks.len(), 1);
+    assert_eq!(backlinks[0].source_id, source.id);
+    // Self-links are ignored
+    assert!(links.get_backlinks(source.id.clone()).unwrap().is_empty());
+
+    links.sync_links(source.id.clone(), "no links").unwrap();
+    assert!(links.get_outgoing_links(source.id).unwrap().is_empty());
+}
+
+#[test]
+fn a_note_write_joins_the_transaction_around_it() {
+    let db = memory_db();
+    let notes = NoteService::new(&db);
+    let failed: Result<(), AppError> = db.transaction(|| {
+        notes.create_note(None, "Draft".into(), "#tagged".into())?;
+        Err(AppError::validation("link sync failed"))
+    });
+    assert!(failed.is_err());
+
+    // Neither the note nor anything indexed from it outlives the rollback
+    let (listed, total) = notes.list_notes(None, None, false, 10, 0).unwrap();
+    assert!(listed.is_empty() && total == 0);
+    let tags: i64 = db.connection().query_row("SELECT COUNT(*) FROM note_tags", [], |row| row.get(0)).unwrap();
+    assert_eq!(tags, 0);
+}
+
+#[test]
+fn stale_updates_are_rejected_with_the_current_version() {
+    let db = memory_db();
+    let notes = NoteService::new(&db);
+    let note = notes.create_note(None, "Plan".into(), "one".into()).unwrap();
+
+    // Two saves within the same millisecond still produce different versions
+    let first = notes.update_note(note.id.clone(), None, Some("two".into()), None, Some(note.updated_at)).unwrap();
+    let second = notes.update_note(note.id.clone(), None, Some("three".into()), None, Some(first.updated_at)).unwrap();
+    assert!(first.updated_at > note.updated_at && second.updated_at > first.updated_at);
+
+    match notes.update_note(note.id.clone(), None, Some("stale".into()), None, Some(first.updated_at)) {
+        Err(err @ AppError::Conflict { .. }) => {
+            let payload = serde_json::to_value(&err).unwrap();
+            assert_eq!(payload["code"], "CONFLICT");
+            assert_eq!((payload["current"]["content"].as_str(), payload["current"]["updated_at"].as_i64()), (Some("three"), Some(second.updated_at)));
+        }
+        other => panic!("expected a conflict, got {:?}", other),
+    }
+    assert_eq!(notes.get_note(note.id.clone()).unwrap().content, "three");
+
+    // Without an expected version the write always goes through
+    notes.update_note(note.id.clone(), None, Some("four".into()), None, None).unwrap();
+}
+
+#[test]
+fn three_way_merge_keeps_both_sides() {
+    let base = "# Plan\nmilk\neggs\nbread\n";
+
+    let merged = merge_text(base, "# Plan\nmilk\neggs\nbread\nbutter\n", "# Weekend plan\nmilk\neggs\nbread\n");
+    assert_eq!((merged.merged.as_str(), merged.conflicts), ("# Weekend plan\nmilk\neggs\nbread\nbutter\n", 0));
+
+    let merged = merge_text(base, "# Plan\nmilk\nten eggs\nbread\n", "# Plan\nmilk\nsix eggs\nbread\n");
+    assert_eq!(merged.conflicts, 1);
+    assert_eq!(
+        merged.merged,
+        "# Plan\nmilk\n<<<<<<< yours\nten eggs\n=======\nsix eggs\n>>>>>>> theirs\nbread\n"
+    );
+
+    // Both sides making the same edit is not a conflict
+    let merged = merge_text(base, "milk\neggs\n", "milk\neggs\n");
+    assert_eq!((merged.merged.as_str(), merged.conflicts), ("milk\neggs\n", 0));
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::memory_db;
+use mdnote_core::errors::AppError;
+use mdnote_core::models::template::TemplateDraft;
+use mdnote_core::services::folder::FolderService;
+use mdnote_core::services::note::NoteService;
+use mdnote_core::services::periodic::{Period, PeriodicService};
+use mdnote_core::services::settings::SettingsService;
+use mdnote_core::services::template::TemplateService;
+
+fn date(value: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+}
+
+#[test]
+fn daily_notes_are_created_once_per_day_with_the_configured_setup() {
+    let db = memory_db();
+    let periodic = PeriodicService::new(&db);
+    let journal = FolderService::new(&db).create_folder("Journal".into(), None).unwrap();
+    let template = TemplateService::new(&db)
+        .create_template(TemplateDraft {
+            name: "Day".into(),
+            content: "# {{title}}\n\nDate: {{date}}\n{{cursor}}".into(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let settings = SettingsService::new(&db);
+    let mut current = settings.get_settings().unwrap();
+    current.periodic_notes.daily.folder_id = Some(journal.id.clone());
+    current.periodic_notes.daily.title_format = "%A %-d %B %Y".into();
+    current.periodic_notes.daily.template_id = Some(template.id);
+    settings.update_settings(current).unwrap();
+
+    let opened = periodic.open_periodic_note(Period::Daily, date("2026-03-02")).unwrap();
+    assert!(opened.created);
+    assert_eq!(opened.note.title, "Monday 2 March 2026");
+    assert_eq!(opened.note.content, "# Monday 2 March 2026\n\nDate: 2026-03-02\n");
+    assert_eq!(opened.note.folder_id, Some(journal.id));
+    assert_eq!(opened.cursor, Some(opened.note.content.len()));
+
+    // Renaming the note doesn't lose it
+    NoteService::new(&db).update_note(opened.note.id.clone(), Some("Big day".into()), None, None, None).unwrap();
+    let again = periodic.open_periodic_note(Period::Daily, date("2026-03-02")).unwrap();
+    assert!(!again.created);
+    assert_eq!(again.note.id, opened.note.id);
+
+    let mut invalid = settings.get_settings().unwrap();
+    invalid.periodic_notes.weekly.title_format = "%Q".into();
+    let err = settings.update_settings(invalid).unwrap_err();
+    assert!(matches!(err, AppError::Validation { field: Some(ref f), .. } if f == "title_format"), "{:?}", err);
+}
+
+#[test]
+fn weekly_and_monthly_notes_cover_their_whole_period() {
+    let db = memory_db();
+    let periodic = PeriodicService::new(&db);
+
+    // A note written by hand before daily notes were set up is adopted
+    let existing = NoteService::new(&db).create_note(None, "2026-W10".into(), "plans".into()).unwrap();
+    let week = periodic.open_periodic_note(Period::Weekly, date("2026-03-05")).unwrap();
+    assert_eq!((week.start_date.as_str(), week.created), ("2026-03-02", false));
+    assert_eq!(week.note.id, existing.id);
+    assert_eq!(periodic.open_periodic_note(Period::Weekly, date("2026-03-08")).unwrap().note.id, existing.id);
+
+    let month = periodic.open_periodic_note(Period::Monthly, date("2026-02-27")).unwrap();
+    assert_eq!((month.start_date.as_str(), month.note.title.as_str()), ("2026-02-01", "2026-02"));
+}
+
+#[test]
+fn the_calendar_shows_daily_notes_and_activity() {
+    let db = memory_db();
+    let periodic = PeriodicService::new(&db);
+    let notes = NoteService::new(&db);
+
+    let today = chrono::Local::now().date_naive();
+    let daily = periodic.open_periodic_note(Period::Daily, today).unwrap();
+    notes.update_note(daily.note.id.clone(), None, Some("three little words".into()), None, None).unwrap();
+    let month = periodic.open_periodic_note(Period::Monthly, today).unwrap();
+    let other = notes.create_note(None, "Other".into(), String::new()).unwrap();
+    // Created long ago, changed today
+    db.connection().execute("UPDATE notes SET created_at = 0 WHERE id = ?", [&other.id]).unwrap();
+
+    let from = today - chrono::Duration::days(2);
+    let calendar = periodic.list_periodic_notes(from, today).unwrap();
+    assert_eq!(calendar.days.len(), 3);
+    assert!(calendar.days[0].daily_note.is_none() && calendar.days[0].created.is_empty());
+
+    let day = &calendar.days[2];
+    let daily_note = day.daily_note.as_ref().unwrap();
+    assert_eq!((daily_note.note_id.as_str(), daily_note.word_count), (daily.note.id.as_str(), Some(3)));
+    let mut created: Vec<&str> = day.created.iter().map(|n| n.id.as_str()).collect();
+    created.sort();
+    let mut expected = vec![daily.note.id.as_str(), month.note.id.as_str()];
+    expected.sort();
+    assert_eq!(created, expected);
+    assert_eq!(day.updated.iter().map(|n| n.id.as_str()).collect::<Vec<_>>(), [other.id.as_str()]);
+    assert_eq!(calendar.months.len(), 1);
+    assert!(calendar.weeks.is_empty());
+
+    assert!(periodic.list_periodic_notes(today, from).is_err());
+    assert!(periodic.list_periodic_notes(from - chrono::Duration::days(400), today).is_err());
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use common::memory_db;
+use mdnote_core::errors::AppError;
+use mdnote_core::models::property::{NoteProperty, PropertyFilter, PropertyQuery, PropertySort, PropertyValue};
+use mdnote_core::services::note::NoteService;
+use mdnote_core::services::property::{extract_properties, set_front_matter_property};
+use mdnote_core::services::search::SearchService;
+
+fn property(key: &str, value: PropertyValue) -> NoteProperty {
+    NoteProperty { key: key.into(), value }
+}
+
+fn text(value: &str) -> PropertyValue {
+    PropertyValue::Text(value.into())
+}
+
+#[test]
+fn front_matter_is_parsed_into_typed_properties() {
+    let content = "---\n\
+        status: draft # first pass\n\
+        author: \"Ada: Lovelace\"\n\
+        version: 2.5\n\
+        published: 2026-03-02\n\
+        pinned: true\n\
+        aliases: [roadmap, 'Q1 plan']\n\
+        reviewers:\n  - sam\n  - \"lee\"\n\
+        summary: |\n  Two lines\n  of text\n\
+        empty:\n\
+        nested:\n  a: 1\n\
+        Status: ignored\n\
+        ---\n\
+        # Body\nstatus: not front matter\n";
+
+    assert_eq!(extract_properties(content), [
+        property("status", text("draft")),
+        property("author", text("Ada: Lovelace")),
+        property("version", PropertyValue::Number(2.5)),
+        property("published", PropertyValue::Date("2026-03-02".into())),
+        property("pinned", PropertyValue::Bool(true)),
+        property("aliases", PropertyValue::List(vec!["roadmap".into(), "Q1 plan".into()])),
+        property("reviewers", PropertyValue::List(vec!["sam".into(), "lee".into()])),
+        property("summary", text("Two lines\nof text")),
+    ]);
+    assert!(extract_properties("No front matter\n---\nstatus: x\n---\n").is_empty());
+    assert!(extract_properties("---\nstatus: unclosed\n").is_empty());
+}
+
+#[test]
+fn setting_a_property_rewrites_only_its_lines() {
+    let content = "---\r\ntitle: Plan\r\ntags:\r\n  - a\r\n  - b\r\nstatus: draft\r\n---\r\nBody\r\n";
+
+    let updated = set_front_matter_property(content, "TAGS", Some(&PropertyValue::List(vec!["c".into(), "d e".into()]))).unwrap();
+    assert_eq!(updated, "---\r\ntitle: Plan\r\nTAGS:\r\n  - c\r\n  - d e\r\nstatus: draft\r\n---\r\nBody\r\n");
+
+    // Values that would read back as another type are quoted
+    let updated = set_front_matter_property(content, "status", Some(&text("true"))).unwrap();
+    assert!(updated.contains("status: \"true\"\r\n"));
+    assert_eq!(extract_properties(&updated)[2], property("status", text("true")));
+
+    let added = set_front_matter_property(content, "priority", Some(&PropertyValue::Number(3.0))).unwrap();
+    assert!(added.ends_with("status: draft\r\npriority: 3\r\n---\r\nBody\r\n"));
+
+    let removed = set_front_matter_property(content, "tags", None).unwrap();
+    assert_eq!(removed, "---\r\ntitle: Plan\r\nstatus: draft\r\n---\r\nBody\r\n");
+
+    let created = set_front_matter_property("Body\n", "due", Some(&PropertyValue::Date("2026-03-02".into()))).unwrap();
+    assert_eq!(created, "---\ndue: 2026-03-02\n---\nBody\n");
+    assert_eq!(set_front_matter_property(&created, "due", None).unwrap(), "Body\n");
+
+    assert!(set_front_matter_property(content, "bad: key", Some(&text("x"))).is_err());
+    assert!(set_front_matter_property(content, "due", Some(&PropertyValue::Date("2026-02-30".into()))).is_err());
+}
+
+#[test]
+fn notes_can_be_filtered_and_sorted_by_property() {
+    let db = memory_db();
+    let notes = NoteService::new(&db);
+    let spec = notes.create_note(None, "Spec".into(), "---\nstatus: draft\npriority: 10\n---\nalpha".into()).unwrap();
+    let memo = notes.create_note(None, "Memo".into(), "---\nstatus: Draft\npriority: 2\naliases: [roadmap]\n---\nalpha".into()).unwrap();
+    let done = notes.create_note(None, "Done".into(), "---\nstatus: done\n---\nalpha".into()).unwrap();
+    assert_eq!(spec.properties[1], property("priority", PropertyValue::Number(10.0)));
+
+    let query = |filters: &[&str], sort: Option<(&str, bool)>| PropertyQuery {
+        filters: filters.iter().map(|f| f.parse().unwrap()).collect(),
+        sort: sort.map(|(key, descending)| PropertySort { key: key.into(), descending }),
+    };
+    let ids = |query: PropertyQuery| -> Vec<String> {
+        notes.list_notes_with_properties(None, None, false, &query, 50, 0).unwrap().0.into_iter().map(|n| n.id).collect()
+    };
+
+    // Numbers compare as numbers, so 10 sorts after 2
+    assert_eq!(ids(query(&["status = draft"], Some(("priority", false)))), [memo.id.as_str(), spec.id.as_str()]);
+    assert_eq!(ids(query(&["priority > 5"], None)), [spec.id.as_str()]);
+    assert_eq!(ids(query(&["aliases = ROADMAP"], None)), [memo.id.as_str()]);
+    assert_eq!(ids(query(&["status != draft"], None)), [done.id.as_str()]);
+    assert_eq!(ids(query(&[], Some(("priority", true)))), [spec.id.as_str(), memo.id.as_str(), done.id.as_str()]);
+
+    let search = SearchService::new(&db);
+    let found = search.search_notes_with_properties("alpha".into(), &query(&["priority"], Some(("priority", false))), 10).unwrap();
+    assert_eq!(found.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), [memo.id.as_str(), spec.id.as_str()]);
+
+    // Setting a property keeps the body and moves the note between filters
+    let updated = notes.set_note_property(done.id.clone(), "status".into(), Some(text("draft"))).unwrap();
+    assert_eq!(updated.content, "---\nstatus: draft\n---\nalpha");
+    assert_eq!(ids(query(&["status = draft"], None)).len(), 3);
+
+    assert!(matches!("status draft".parse::<PropertyFilter>(), Err(AppError::Validation { .. })));
+    assert!(matches!(
+        notes.set_note_property(done.id, "".into(), None),
+        Err(AppError::Validation { field: Some(ref f), .. }) if f == "key"
+    ));
+}
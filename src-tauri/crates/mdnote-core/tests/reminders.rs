@@ -0,0 +1,192 @@
+mod common;
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use common::memory_db;
+use mdnote_core::errors::AppError;
+use mdnote_core::models::reminder::{DueReminder, ReminderDraft};
+use mdnote_core::services::note::NoteService;
+use mdnote_core::services::reminder::{Clock, ReminderScheduler, ReminderService, REMINDER_CHECK_INTERVAL};
+use mdnote_core::services::task::TaskService;
+use mdnote_core::services::undo::UndoService;
+
+const MINUTE: i64 = 60 * 1000;
+
+#[derive(Default)]
+struct ManualClock(AtomicI64);
+
+impl ManualClock {
+    fn advance(&self, millis: i64) {
+        self.0.fetch_add(millis, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> i64 {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+fn scheduler(clock: &Arc<ManualClock>) -> (ReminderScheduler, Arc<Mutex<Vec<DueReminder>>>) {
+    let fired = Arc::new(Mutex::new(Vec::new()));
+    let sink = fired.clone();
+    let scheduler = ReminderScheduler::new(
+        clock.clone(),
+        Arc::new(move |reminder: &DueReminder| sink.lock().unwrap().push(reminder.clone())),
+    );
+    (scheduler, fired)
+}
+
+#[test]
+fn reminders_fire_once_when_due_and_again_after_a_snooze() {
+    let db = memory_db();
+    let clock = Arc::new(ManualClock(AtomicI64::new(1_000 * MINUTE)));
+    let (scheduler, fired) = scheduler(&clock);
+    let reminders = ReminderService::new(&db).with_clock(clock.as_ref());
+    let note = NoteService::new(&db).create_note(None, "Trip".into(), "- [ ] Pack due:2026-03-02".into()).unwrap();
+
+    let draft = |minutes: i64, task_line: Option<usize>| ReminderDraft {
+        note_id: note.id.clone(),
+        task_line,
+        message: Some(" Passport! ".into()),
+        remind_at: clock.now_millis() + minutes * MINUTE,
+    };
+    let pack = reminders.create_reminder(draft(10, Some(1))).unwrap();
+    assert_eq!((pack.task_text.as_deref(), pack.message.as_deref()), (Some("Pack"), Some("Passport!")));
+    let note_reminder = reminders.create_reminder(draft(20, None)).unwrap();
+    assert!(matches!(reminders.create_reminder(draft(0, None)), Err(AppError::Validation { field: Some(ref f), .. }) if f == "remind_at"));
+    assert!(matches!(reminders.create_reminder(draft(5, Some(2))), Err(AppError::NotFound(_))));
+
+    let upcoming: Vec<String> = reminders.list_upcoming_reminders(10).unwrap().into_iter().map(|r| r.id).collect();
+    assert_eq!(upcoming, [pack.id.clone(), note_reminder.id.clone()]);
+
+    // Nothing is due yet; the scheduler checks back no later than its interval
+    assert_eq!(scheduler.tick(&db).unwrap(), REMINDER_CHECK_INTERVAL);
+    assert!(fired.lock().unwrap().is_empty());
+
+    clock.advance(10 * MINUTE);
+    assert_eq!(scheduler.tick(&db).unwrap(), REMINDER_CHECK_INTERVAL);
+    assert_eq!(scheduler.tick(&db).unwrap(), REMINDER_CHECK_INTERVAL);
+    {
+        let fired = fired.lock().unwrap();
+        assert_eq!(fired.len(), 1);
+        assert_eq!((fired[0].reminder.id.as_str(), fired[0].missed), (pack.id.as_str(), false));
+    }
+
+    // Snoozing brings a fired reminder back; the wait shrinks as it gets close
+    assert!(reminders.snooze_reminder(pack.id.clone(), clock.now_millis()).is_err());
+    let snoozed = reminders.snooze_reminder(pack.id.clone(), clock.now_millis() + 15 * 1000).unwrap();
+    assert_eq!(snoozed.fired_at, None);
+    assert_eq!(scheduler.tick(&db).unwrap(), Duration::from_secs(15));
+    clock.advance(15 * 1000);
+    scheduler.tick(&db).unwrap();
+    assert_eq!(fired.lock().unwrap().len(), 2);
+
+    reminders.delete_reminder(note_reminder.id.clone()).unwrap();
+    assert!(matches!(reminders.get_reminder(&note_reminder.id), Err(AppError::NotFound(_))));
+    assert!(reminders.list_upcoming_reminders(10).unwrap().is_empty());
+}
+
+#[test]
+fn reminders_missed_while_closed_are_caught_up() {
+    let db = memory_db();
+    let clock = Arc::new(ManualClock(AtomicI64::new(1_000 * MINUTE)));
+    let (scheduler, fired) = scheduler(&clock);
+    let reminders = ReminderService::new(&db).with_clock(clock.as_ref());
+    let notes = NoteService::new(&db);
+    let note = notes.create_note(None, "Chores".into(), "- [ ] Water plants\n- [ ] Bins".into()).unwrap();
+
+    for (line, minutes) in [(1, 30), (2, 60)] {
+        let remind_at = clock.now_millis() + minutes * MINUTE;
+        reminders.create_reminder(ReminderDraft { note_id: note.id.clone(), task_line: Some(line), message: None, remind_at }).unwrap();
+    }
+    // Checked off before its reminder went off
+    TaskService::new(&db).toggle_task(note.id.clone(), 2).unwrap();
+
+    // The app was closed for a day
+    clock.advance(24 * 60 * MINUTE);
+    scheduler.tick(&db).unwrap();
+    let fired = fired.lock().unwrap();
+    assert_eq!(fired.len(), 1);
+    assert_eq!((fired[0].reminder.task_line, fired[0].missed), (Some(1), true));
+    assert!(reminders.list_upcoming_reminders(10).unwrap().is_empty());
+
+    notes.delete_note(note.id).unwrap();
+    assert_eq!(reminders.next_reminder_at().unwrap(), None);
+}
+
+#[test]
+fn reminders_fired_before_anyone_listened_stay_listed_until_acknowledged() {
+    let db = memory_db();
+    let clock = Arc::new(ManualClock(AtomicI64::new(1_000 * MINUTE)));
+    // Fired during startup, before the window listens: the events go nowhere
+    let scheduler = ReminderScheduler::new(clock.clone(), Arc::new(|_: &DueReminder| {}));
+    let reminders = ReminderService::new(&db).with_clock(clock.as_ref());
+    let note = NoteService::new(&db).create_note(None, "Chores".into(), "- [ ] Water plants\n- [ ] Bins".into()).unwrap();
+
+    let mut ids = Vec::new();
+    for (line, minutes) in [(None, 30), (Some(1), 60), (Some(2), 90)] {
+        let remind_at = clock.now_millis() + minutes * MINUTE;
+        let draft = ReminderDraft { note_id: note.id.clone(), task_line: line, message: None, remind_at };
+        ids.push(reminders.create_reminder(draft).unwrap().id);
+    }
+    clock.advance(60 * MINUTE);
+    scheduler.tick(&db).unwrap();
+    clock.advance(30 * MINUTE);
+    scheduler.tick(&db).unwrap();
+    // Checked off after its reminder fired, but before it was shown
+    TaskService::new(&db).toggle_task(note.id.clone(), 2).unwrap();
+
+    let missed = reminders.list_missed_reminders().unwrap();
+    let listed: Vec<(&str, bool)> = missed.iter().map(|m| (m.reminder.id.as_str(), m.missed)).collect();
+    assert_eq!(listed, [(ids[0].as_str(), true), (ids[1].as_str(), false)]);
+
+    reminders.acknowledge_reminders(&ids[..1]).unwrap();
+    let missed = reminders.list_missed_reminders().unwrap();
+    assert_eq!(missed.iter().map(|m| m.reminder.id.as_str()).collect::<Vec<_>>(), [ids[1].as_str()]);
+
+    // A snoozed reminder has to be shown again when it fires next
+    reminders.acknowledge_reminders(&ids[1..2]).unwrap();
+    reminders.snooze_reminder(ids[1].clone(), clock.now_millis() + MINUTE).unwrap();
+    assert!(reminders.list_missed_reminders().unwrap().is_empty());
+    clock.advance(MINUTE);
+    scheduler.tick(&db).unwrap();
+    assert_eq!(reminders.list_missed_reminders().unwrap()[0].reminder.id, ids[1]);
+}
+
+#[test]
+fn task_reminders_follow_their_task_when_lines_shift() {
+    let db = memory_db();
+    let clock = Arc::new(ManualClock(AtomicI64::new(1_000 * MINUTE)));
+    let reminders = ReminderService::new(&db).with_clock(clock.as_ref());
+    let notes = NoteService::new(&db);
+    let note = notes.create_note(None, "Calls".into(), "- [ ] Call mum\n- [ ] Call mum\n- [ ] Bins".into()).unwrap();
+
+    let remind = |line: usize| {
+        let draft = ReminderDraft { note_id: note.id.clone(), task_line: Some(line), message: None, remind_at: 2_000 * MINUTE };
+        reminders.create_reminder(draft).unwrap().id
+    };
+    let (first_call, second_call, bins) = (remind(1), remind(2), remind(3));
+    let anchors = || {
+        [&first_call, &second_call, &bins].map(|id| {
+            let reminder = reminders.get_reminder(id).unwrap();
+            (reminder.task_line.unwrap(), reminder.task_text)
+        })
+    };
+
+    let shifted = "# Today\n\n- [ ] Call mum\n- [ ] Call mum\nnotes\n- [ ] Bins";
+    let undo = UndoService::new(&db);
+    undo.record("Edit note", || notes.update_note(note.id.clone(), None, Some(shifted.into()), None, None)).unwrap();
+    let call = Some("Call mum".to_string());
+    assert_eq!(anchors(), [(3, call.clone()), (4, call.clone()), (6, Some("Bins".into()))]);
+
+    // Undoing the edit puts the reminders back with the tasks
+    undo.undo().unwrap();
+    assert_eq!(anchors(), [(1, call.clone()), (2, call), (3, Some("Bins".into()))]);
+
+    // An edited task keeps its reminder where it is
+    let edited = "- [ ] Call mum\n- [ ] Call dad\n- [ ] Bins";
+    notes.update_note(note.id.clone(), None, Some(edited.into()), None, None).unwrap();
+    assert_eq!(anchors()[1], (2, Some("Call dad".into())));
+}
@@ -0,0 +1,298 @@
+mod common;
+
+use std::path::Path;
+use common::{memory_db, TempDir};
+use mdnote_core::errors::AppError;
+use mdnote_core::models::settings::Settings;
+use mdnote_core::services::backup::{list_vault_backups, restore_failed_vault, BackupKind, BackupService};
+use mdnote_core::services::database::{latest_schema_version, DbPool, DbState};
+use mdnote_core::services::maintenance::MaintenanceService;
+use mdnote_core::services::note::NoteService;
+use mdnote_core::DatabaseService;
+
+#[test]
+fn in_memory_database_is_fully_migrated() {
+    let db = memory_db();
+    let version: i64 = db
+        .connection()
+        .query_row("SELECT MAX(version) FROM _migrations", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, latest_schema_version());
+    assert!(matches!(BackupService::new(&db).create_backup(None), Err(AppError::Unsupported(_))));
+}
+
+#[test]
+fn readers_are_not_blocked_by_a_writer() {
+    let dir = TempDir::new();
+    let pool = std::sync::Arc::new(DbPool::new(dir.path().to_path_buf(), 2).unwrap());
+    let note = pool.with_writer(|db| NoteService::new(db).create_note(None, "a".into(), "b".into())).unwrap();
+
+    let (release, held) = std::sync::mpsc::channel::<()>();
+    let writer_pool = pool.clone();
+    let writer = std::thread::spawn(move || writer_pool.with_writer(|_| { held.recv().unwrap(); Ok(()) }));
+    std::thread::sleep(std::time::Duration::from_millis(50));
+
+    let id = note.id.clone();
+    assert_eq!(pool.with_reader(|db| NoteService::new(db).get_note(id)).unwrap().title, "a");
+    // Reader connections are read-only
+    assert!(pool.with_reader(|db| NoteService::new(db).create_note(None, "x".into(), "y".into())).is_err());
+
+    release.send(()).unwrap();
+    writer.join().unwrap().unwrap();
+}
+
+#[test]
+fn readers_see_a_write_only_once_it_commits() {
+    let dir = TempDir::new();
+    let pool = std::sync::Arc::new(DbPool::new(dir.path().to_path_buf(), 2).unwrap());
+    let count = |pool: &DbPool| pool.with_reader(|db| Ok(NoteService::new(db).list_notes(None, None, false, 10, 0)?.1)).unwrap();
+
+    let (written, wait_written) = std::sync::mpsc::channel::<()>();
+    let (release, held) = std::sync::mpsc::channel::<()>();
+    let writer_pool = pool.clone();
+    let writer = std::thread::spawn(move || {
+        writer_pool.with_writer(|db| {
+            db.transaction(|| {
+                NoteService::new(db).create_note(None, "pending".into(), String::new())?;
+                written.send(()).unwrap();
+                held.recv().unwrap();
+                Ok(())
+            })
+        })
+    });
+
+    wait_written.recv().unwrap();
+    // Both readers can run while the writer holds its transaction open
+    assert_eq!(count(&pool), 0);
+    let other = pool.clone();
+    assert_eq!(std::thread::spawn(move || count(&other)).join().unwrap(), 0);
+
+    release.send(()).unwrap();
+    writer.join().unwrap().unwrap();
+    assert_eq!(count(&pool), 1);
+}
+
+#[test]
+fn db_state_reports_missing_and_locked_vaults() {
+    let dir = TempDir::new();
+    let state = DbState::new();
+    assert!(matches!(state.pool(), Err(AppError::NotFound(_))));
+
+    state.open("vault".into(), DbPool::new(dir.path().to_path_buf(), 1).unwrap()).unwrap();
+    assert!(state.pool().is_ok());
+    state.lock().unwrap();
+    assert!(state.is_locked().unwrap());
+    assert!(matches!(state.pool(), Err(AppError::Locked(_))));
+    assert_eq!(state.close().unwrap().as_deref(), Some("vault"));
+}
+
+#[test]
+fn backup_and_restore_round_trip() {
+    let dir = TempDir::new();
+    let pool = DbPool::new(dir.path().to_path_buf(), 2).unwrap();
+    let note = pool.with_writer(|db| NoteService::new(db).create_note(None, "before".into(), "b".into())).unwrap();
+    let backup = pool.with_reader(|db| BackupService::new(db).create_backup(None)).unwrap();
+    assert_eq!(backup.schema_version, latest_schema_version());
+
+    let id = note.id.clone();
+    pool.with_writer(|db| NoteService::new(db).update_note(id, Some("after".into()), None, None, None)).unwrap();
+    pool.with_writer(|db| {
+        let backup = BackupService::new(db).prepare_restore(&backup.id)?;
+        db.restore_from(Path::new(&backup.path))
+    })
+    .unwrap();
+
+    let id = note.id.clone();
+    assert_eq!(pool.with_reader(|db| NoteService::new(db).get_note(id)).unwrap().title, "before");
+    let mode: String = pool
+        .with_writer(|db| Ok(db.connection().query_row("PRAGMA journal_mode", [], |row| row.get(0))?))
+        .unwrap();
+    assert_eq!(mode, "wal");
+    // The manual backup plus the safety snapshot taken before restoring
+    assert_eq!(pool.with_reader(|db| BackupService::new(db).list_backups()).unwrap().len(), 2);
+}
+
+#[test]
+fn a_vault_that_fails_to_open_can_be_restored_from_a_backup() {
+    let dir = TempDir::new();
+    let backup = {
+        let pool = DbPool::new(dir.path().to_path_buf(), 1).unwrap();
+        pool.with_writer(|db| NoteService::new(db).create_note(None, "kept".into(), String::new())).unwrap();
+        pool.with_reader(|db| BackupService::new(db).create_backup(None)).unwrap()
+    };
+    // Overwritten with junk, with no WAL left to mask the damage
+    std::fs::write(dir.path().join("mdnote.db"), vec![0x42; 8192]).unwrap();
+    for companion in ["mdnote.db-wal", "mdnote.db-shm"] {
+        let _ = std::fs::remove_file(dir.path().join(companion));
+    }
+
+    let err = DbPool::new(dir.path().to_path_buf(), 1).err().unwrap();
+    assert!(matches!(err, AppError::Corrupt { .. }), "{:?}", err);
+    let state = DbState::new();
+    state.open_failed("vault".into(), dir.path().to_path_buf(), &err).unwrap();
+    assert!(matches!(state.pool(), Err(AppError::Corrupt { .. })));
+    assert!(!state.is_locked().unwrap());
+
+    let backups = list_vault_backups(dir.path(), None).unwrap();
+    assert_eq!(backups.len(), 1);
+    let restored = restore_failed_vault(&state, &backup.id).unwrap().unwrap();
+    assert_eq!(restored.id, backup.id);
+
+    let (notes, _) = state.pool().unwrap().with_reader(|db| NoteService::new(db).list_notes(None, None, false, 10, 0)).unwrap();
+    assert_eq!(notes[0].title, "kept");
+    assert_eq!(state.failed_vault_dir().unwrap(), None);
+    // The damaged file is set aside, not deleted
+    let set_aside = std::fs::read_dir(dir.path()).unwrap().filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().starts_with("mdnote.db.damaged-"))
+        .count();
+    assert_eq!(set_aside, 1);
+}
+
+#[test]
+fn scheduled_backups_respect_interval_and_retention() {
+    let dir = TempDir::new();
+    let pool = DbPool::new(dir.path().to_path_buf(), 1).unwrap();
+    let settings = Settings { backup_retention: 1, ..Settings::default() };
+
+    assert!(pool.with_reader(|db| BackupService::new(db).run_scheduled_backup(&settings)).unwrap().is_some());
+    assert!(pool.with_reader(|db| BackupService::new(db).run_scheduled_backup(&settings)).unwrap().is_none());
+}
+
+#[test]
+fn maintenance_commands_run_on_a_healthy_database() {
+    let dir = TempDir::new();
+    let db = DatabaseService::new(dir.path().to_path_buf()).unwrap();
+    NoteService::new(&db).create_note(None, "a".into(), "hello world".into()).unwrap();
+    let maintenance = MaintenanceService::new(&db);
+
+    assert!(maintenance.integrity_check(false).unwrap().ok);
+    let stats = maintenance.get_db_stats().unwrap();
+    assert!(stats.fts_index_size_bytes > 0);
+    assert!(stats.tables.iter().any(|t| t.name == "notes" && t.rows == 1));
+    maintenance.analyze().unwrap();
+    maintenance.vacuum().unwrap();
+    maintenance.wal_checkpoint(true).unwrap();
+    maintenance.incremental_vacuum(None).unwrap();
+}
+
+#[test]
+fn maintenance_reports_dangling_rows_and_reclaims_space() {
+    let dir = TempDir::new();
+    let db = DatabaseService::new(dir.path().to_path_buf()).unwrap();
+    let notes = NoteService::new(&db);
+    let big = "lorem ipsum ".repeat(2000);
+    let ids: Vec<String> = (0..20).map(|i| notes.create_note(None, format!("n{}", i), big.clone()).unwrap().id).collect();
+    for id in ids {
+        notes.delete_note(id).unwrap();
+    }
+    let maintenance = MaintenanceService::new(&db);
+    maintenance.wal_checkpoint(true).unwrap();
+    assert!(maintenance.get_db_stats().unwrap().freelist_count > 0);
+    maintenance.vacuum().unwrap();
+    maintenance.wal_checkpoint(true).unwrap();
+    let stats = maintenance.get_db_stats().unwrap();
+    assert_eq!((stats.freelist_count, stats.wal_size_bytes), (0, 0));
+
+    db.connection()
+        .execute_batch("PRAGMA foreign_keys = OFF; INSERT INTO note_tags (note_id, tag_id) VALUES ('gone', 'gone'); PRAGMA foreign_keys = ON;")
+        .unwrap();
+    let report = maintenance.integrity_check(true).unwrap();
+    assert!(!report.ok && report.errors.is_empty());
+    assert!(report.foreign_key_violations.iter().all(|v| v.table == "note_tags"));
+}
+
+#[test]
+fn garbage_database_file_is_reported_corrupt() {
+    let dir = TempDir::new();
+    std::fs::write(dir.path().join("mdnote.db"), vec![7u8; 8192]).unwrap();
+    let err = DatabaseService::new(dir.path().to_path_buf()).err().unwrap();
+    assert!(matches!(err, AppError::Corrupt { .. }), "{:?}", err);
+    // The SQLite error stays attached for the logs
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[test]
+fn migrations_backfill_checksums_and_refuse_tampering() {
+    let dir = TempDir::new();
+    let path = dir.path().to_path_buf();
+    {
+        let db = DatabaseService::new(path.clone()).unwrap();
+        NoteService::new(&db).create_note(None, "a".into(), "#x".into()).unwrap();
+        // Pretend the database predates checksums and the inline tag backfill
+        db.connection()
+            .execute_batch("UPDATE _migrations SET checksum = NULL; DELETE FROM _migrations WHERE version = 9; DELETE FROM note_tags; DELETE FROM tags;")
+            .unwrap();
+    }
+    {
+        let db = DatabaseService::new(path.clone()).unwrap();
+        let tagged: i64 = db.connection().query_row("SELECT COUNT(*) FROM note_tags", [], |row| row.get(0)).unwrap();
+        assert_eq!(tagged, 1);
+        let missing: i64 = db
+            .connection()
+            .query_row("SELECT COUNT(*) FROM _migrations WHERE checksum IS NULL", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(missing, 0);
+        assert_eq!(std::fs::read_dir(path.join("backups")).unwrap().count(), 1);
+        db.connection().execute("UPDATE _migrations SET checksum = 'x' WHERE version = 3", []).unwrap();
+    }
+    assert!(matches!(DatabaseService::new(path.clone()), Err(AppError::Corrupt { .. })));
+
+    let conn = rusqlite::Connection::open(path.join("mdnote.db")).unwrap();
+    conn.execute_batch("UPDATE _migrations SET checksum = NULL WHERE version = 3; INSERT INTO _migrations (version, applied_at) VALUES (9999, 0);")
+        .unwrap();
+    drop(conn);
+    assert!(matches!(DatabaseService::new(path), Err(AppError::Unsupported(_))));
+}
+
+#[test]
+fn task_and_property_backfills_index_existing_notes() {
+    let dir = TempDir::new();
+    let path = dir.path().to_path_buf();
+    {
+        let db = DatabaseService::new(path.clone()).unwrap();
+        NoteService::new(&db)
+            .create_note(None, "a".into(), "---\nstatus: draft\nrank: 2\n---\n- [ ] ship it due: 2026-01-02 #work".into())
+            .unwrap();
+        // Pretend the database predates both backfills
+        db.connection()
+            .execute_batch("DELETE FROM _migrations WHERE version IN (15, 18); DELETE FROM tasks; DELETE FROM note_properties;")
+            .unwrap();
+    }
+    let db = DatabaseService::new(path).unwrap();
+    let task: (String, Option<String>, String) = db
+        .connection()
+        .query_row("SELECT text, due_date, tags FROM tasks", [], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap();
+    assert_eq!(task, ("ship it #work".to_string(), Some("2026-01-02".to_string()), r#"["work"]"#.to_string()));
+    let properties: Vec<(String, String, Option<f64>)> = db
+        .connection()
+        .prepare("SELECT key, type, number FROM note_properties ORDER BY position")
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap();
+    assert_eq!(properties, vec![("status".into(), "text".into(), None), ("rank".into(), "number".into(), Some(2.0))]);
+}
+
+#[test]
+fn checking_a_backup_leaves_it_untouched_and_safety_snapshots_are_pruned() {
+    let dir = TempDir::new();
+    let pool = DbPool::new(dir.path().to_path_buf(), 1).unwrap();
+    pool.with_writer(|db| NoteService::new(db).create_note(None, "a".into(), "searchable".into())).unwrap();
+    let backup = pool.with_reader(|db| BackupService::new(db).create_backup(None)).unwrap();
+    let original = std::fs::read(&backup.path).unwrap();
+
+    for _ in 0..7 {
+        pool.with_writer(|db| BackupService::new(db).prepare_restore(&backup.id)).unwrap();
+    }
+    assert_eq!(std::fs::read(&backup.path).unwrap(), original);
+    let backups = pool.with_reader(|db| BackupService::new(db).list_backups()).unwrap();
+    assert_eq!(backups.iter().filter(|b| b.kind == BackupKind::PreRestore).count(), 5);
+    assert_eq!(backups.iter().filter(|b| b.kind == BackupKind::Manual).count(), 1);
+    // No journals or scratch copies are left beside the backups
+    let stray = std::fs::read_dir(dir.path().join("backups")).unwrap().filter_map(|e| e.ok())
+        .filter(|e| !e.file_name().to_string_lossy().ends_with(".db"))
+        .count();
+    assert_eq!(stray, 0);
+}
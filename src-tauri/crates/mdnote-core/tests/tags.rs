@@ -0,0 +1,289 @@
+mod common;
+
+use common::memory_db;
+use mdnote_core::errors::AppError;
+use mdnote_core::services::hashtag::{extract_hashtags, rewrite_hashtag};
+use mdnote_core::services::note::NoteService;
+use mdnote_core::services::suggestion::SuggestionService;
+use mdnote_core::services::tag::{list_palette, TagService, MAX_TAG_DESCRIPTION_LEN};
+
+#[test]
+fn tag_names_are_unique_ignoring_case() {
+    let db = memory_db();
+    let tags = TagService::new(&db);
+    tags.create_tag("project".into(), None).unwrap();
+    let err = tags.create_tag("Project".into(), None).unwrap_err();
+    assert!(matches!(err, AppError::AlreadyExists(_)), "{:?}", err);
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        serde_json::json!({ "code": "ALREADY_EXISTS", "message": "A tag with this name already exists" })
+    );
+}
+
+#[test]
+fn tag_tree_counts_notes_in_descendants() {
+    let db = memory_db();
+    let tags = TagService::new(&db);
+    let notes = NoteService::new(&db);
+    let parent = tags.create_tag("project".into(), None).unwrap();
+    let child = tags.create_tag("project/alpha".into(), None).unwrap();
+    let note = notes.create_note(None, "t".into(), "".into()).unwrap();
+    tags.add_tag_to_note(note.id.clone(), child.id.clone()).unwrap();
+    tags.add_tag_to_note(note.id.clone(), parent.id.clone()).unwrap();
+
+    let tree = tags.get_tag_tree().unwrap();
+    assert_eq!(tree.len(), 1);
+    assert_eq!(tree[0].total_count, 1);
+    assert_eq!(tree[0].children[0].path, "project/alpha");
+
+    let (listed, total) = notes.list_notes(None, Some(parent.id), true, 10, 0).unwrap();
+    assert_eq!((listed.len(), total), (1, 1));
+}
+
+#[test]
+fn tag_tree_and_descendants_ignore_case() {
+    let db = memory_db();
+    let tags = TagService::new(&db);
+    let notes = NoteService::new(&db);
+    let parent = tags.create_tag("Project".into(), None).unwrap();
+    let child = tags.create_tag("project/alpha".into(), None).unwrap();
+    let note = notes.create_note(None, "t".into(), "".into()).unwrap();
+    tags.add_tag_to_note(note.id.clone(), child.id).unwrap();
+
+    let tree = tags.get_tag_tree().unwrap();
+    assert_eq!(tree.len(), 1);
+    assert_eq!((tree[0].path.as_str(), tree[0].total_count), ("Project", 1));
+    assert_eq!(tree[0].children[0].path, "project/alpha");
+
+    let (_, total) = notes.list_notes(None, Some(parent.id), true, 10, 0).unwrap();
+    assert_eq!(total, 1);
+}
+
+#[test]
+fn rename_and_merge_rewrite_hashtags_in_content() {
+    let db = memory_db();
+    let tags = TagService::new(&db);
+    let notes = NoteService::new(&db);
+    let parent = tags.create_tag("project".into(), None).unwrap();
+    let child = tags.create_tag("project/alpha".into(), None).unwrap();
+    let note = notes.create_note(None, "t".into(), "hi #project/alpha and #Project, C#, x#project".into()).unwrap();
+
+    tags.rename_tag(parent.id.clone(), "work".into()).unwrap();
+    assert_eq!(notes.get_note(note.id.clone()).unwrap().content, "hi #work/alpha and #work, C#, x#project");
+    assert_eq!(tags.get_tag(child.id.clone()).unwrap().name, "work/alpha");
+
+    tags.merge_tags(vec![child.id], parent.id).unwrap();
+    assert_eq!(notes.get_note(note.id.clone()).unwrap().content, "hi #work and #work, C#, x#project");
+    assert_eq!(tags.get_note_tags(note.id).unwrap().len(), 1);
+}
+
+#[test]
+fn rename_moves_descendants_spelled_in_another_case() {
+    let db = memory_db();
+    let tags = TagService::new(&db);
+    let parent = tags.create_tag("Project".into(), None).unwrap();
+    let child = tags.create_tag("project/alpha".into(), None).unwrap();
+    let unrelated = tags.create_tag("projects".into(), None).unwrap();
+
+    tags.rename_tag(parent.id, "work".into()).unwrap();
+    assert_eq!(tags.get_tag(child.id).unwrap().name, "work/alpha");
+    assert_eq!(tags.get_tag(unrelated.id).unwrap().name, "projects");
+}
+
+#[test]
+fn merging_a_manual_tag_pins_the_target() {
+    let db = memory_db();
+    let tags = TagService::new(&db);
+    let notes = NoteService::new(&db);
+    let note = notes.create_note(None, "t".into(), "#meeting".into()).unwrap();
+    let meeting = tags.get_note_tags(note.id.clone()).unwrap().remove(0);
+    let todo = tags.create_tag("todo".into(), None).unwrap();
+    tags.add_tag_to_note(note.id.clone(), todo.id.clone()).unwrap();
+
+    tags.merge_tags(vec![todo.id], meeting.id.clone()).unwrap();
+    notes.update_note(note.id.clone(), None, Some("nothing".into()), None, None).unwrap();
+    let remaining = tags.get_note_tags(note.id).unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].id, meeting.id);
+}
+
+#[test]
+fn hashtags_skip_code_headings_and_urls() {
+    let content = "# Heading #nope\nhello #meeting #todo #42 C# http://x.com/#frag `#code`\n```\n#incode\n```\n#Meeting #a/b- ";
+    assert_eq!(extract_hashtags(content), vec!["meeting", "todo", "a/b"]);
+    assert_eq!(rewrite_hashtag("#Öl/x", "öl", "oil", true), "#oil/x");
+}
+
+#[test]
+fn inline_tags_follow_content_but_manual_tags_stay() {
+    let db = memory_db();
+    let tags = TagService::new(&db);
+    let notes = NoteService::new(&db);
+    let note = notes.create_note(None, "t".into(), "#meeting #todo".into()).unwrap();
+    assert_eq!(tags.get_note_tags(note.id.clone()).unwrap().len(), 2);
+
+    let todo = tags.get_note_tags(note.id.clone()).unwrap().into_iter().find(|t| t.name == "todo").unwrap();
+    tags.add_tag_to_note(note.id.clone(), todo.id).unwrap();
+    notes.update_note(note.id.clone(), None, Some("nothing".into()), None, None).unwrap();
+
+    let left = tags.get_note_tags(note.id).unwrap();
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].name, "todo");
+}
+
+#[test]
+fn tag_names_and_colors_are_validated() {
+    let db = memory_db();
+    let tags = TagService::new(&db);
+    for name in ["  ", "a,b", "a//b"] {
+        assert!(matches!(tags.create_tag(name.into(), None), Err(AppError::Validation { field: Some(f), .. }) if f == "name"), "{:?}", name);
+    }
+    assert!(matches!(tags.create_tag("x".into(), Some("nope".into())), Err(AppError::Validation { field: Some(f), .. }) if f == "color"));
+
+    let first = tags.create_tag(" a / b ".into(), None).unwrap();
+    assert_eq!((first.name.as_str(), first.color.as_str()), ("a/b", "#3b82f6"));
+    let named = tags.create_tag("d".into(), Some("Teal".into())).unwrap();
+    assert_eq!(named.color, "#14b8a6");
+
+    let updated = tags
+        .update_tag(named.id.clone(), Some("e".into()), Some("#ABC".into()), Some(Some("🔥".into())), None)
+        .unwrap();
+    assert_eq!((updated.name.as_str(), updated.color.as_str()), ("e", "#aabbcc"));
+    assert_eq!(tags.get_tag(named.id).unwrap().icon.as_deref(), Some("🔥"));
+}
+
+#[test]
+fn suggestions_rank_cooccurring_tags_first() {
+    let db = memory_db();
+    let notes = NoteService::new(&db);
+    notes.create_note(None, "Rust async".into(), "tokio runtime futures #rust #async".into()).unwrap();
+    notes.create_note(None, "More".into(), "tokio runtime executor #rust #async #perf".into()).unwrap();
+    let note = notes.create_note(None, "New".into(), "tokio runtime question #rust".into()).unwrap();
+
+    let suggestions = SuggestionService::new(&db);
+    let suggested = suggestions.suggest_tags(note.id, 10).unwrap();
+    assert_eq!(suggested[0].name, "async");
+    assert!(suggested.iter().any(|t| t.name == "perf"));
+    assert!(suggestions.get_tag_cooccurrence(1, 10).unwrap().iter().any(|p| p.jaccard > 0.6));
+}
+
+#[test]
+fn tag_tree_fills_missing_ancestors_and_counts_each_note_once() {
+    let db = memory_db();
+    let tags = TagService::new(&db);
+    let notes = NoteService::new(&db);
+    let deep = tags.create_tag("area/team/backend".into(), None).unwrap();
+    let team = tags.create_tag("area/team".into(), None).unwrap();
+    let both = notes.create_note(None, "both".into(), "".into()).unwrap();
+    let one = notes.create_note(None, "one".into(), "".into()).unwrap();
+    tags.add_tag_to_note(both.id.clone(), deep.id.clone()).unwrap();
+    tags.add_tag_to_note(both.id.clone(), team.id.clone()).unwrap();
+    tags.add_tag_to_note(one.id, deep.id.clone()).unwrap();
+
+    let tree = tags.get_tag_tree().unwrap();
+    let area = &tree[0];
+    // `area` was never created, so it has no id and no notes of its own
+    assert_eq!((area.id.as_deref(), area.note_count, area.total_count), (None, 0, 2));
+    let team_node = &area.children[0];
+    assert_eq!((team_node.id.as_deref(), team_node.note_count, team_node.total_count), (Some(team.id.as_str()), 1, 2));
+    let leaf = &team_node.children[0];
+    assert_eq!((leaf.name.as_str(), leaf.note_count, leaf.total_count), ("backend", 2, 2));
+
+    // Without descendants only the exact tag matches
+    let (_, total) = notes.list_notes(None, Some(team.id.clone()), false, 10, 0).unwrap();
+    assert_eq!(total, 1);
+    let (_, total) = notes.list_notes(None, Some(team.id), true, 10, 0).unwrap();
+    assert_eq!(total, 2);
+}
+
+#[test]
+fn renaming_onto_another_tag_is_refused_and_merging_moves_its_notes() {
+    let db = memory_db();
+    let tags = TagService::new(&db);
+    let notes = NoteService::new(&db);
+    let alpha = tags.create_tag("alpha".into(), None).unwrap();
+    let beta = tags.create_tag("beta".into(), None).unwrap();
+    let gamma = tags.create_tag("gamma".into(), None).unwrap();
+
+    let err = tags.rename_tag(beta.id.clone(), "ALPHA".into()).unwrap_err();
+    assert!(matches!(err, AppError::AlreadyExists(_)), "{:?}", err);
+    assert_eq!(tags.get_tag(beta.id.clone()).unwrap().name, "beta");
+    assert!(tags.find_tag_by_name("Beta").unwrap().is_some());
+
+    let first = notes.create_note(None, "first".into(), "".into()).unwrap();
+    let second = notes.create_note(None, "second".into(), "".into()).unwrap();
+    tags.add_tag_to_note(first.id.clone(), beta.id.clone()).unwrap();
+    tags.add_tag_to_note(second.id.clone(), gamma.id.clone()).unwrap();
+    tags.add_tag_to_note(second.id.clone(), alpha.id.clone()).unwrap();
+
+    tags.merge_tags(vec![beta.id.clone(), gamma.id.clone()], alpha.id.clone()).unwrap();
+    assert!(matches!(tags.get_tag(beta.id), Err(AppError::NotFound(_))));
+    assert!(matches!(tags.get_tag(gamma.id), Err(AppError::NotFound(_))));
+    for note in [first, second] {
+        let names: Vec<String> = tags.get_note_tags(note.id).unwrap().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, ["alpha"]);
+    }
+}
+
+#[test]
+fn hashtags_in_any_case_share_one_tag() {
+    let db = memory_db();
+    let tags = TagService::new(&db);
+    let notes = NoteService::new(&db);
+    let first = notes.create_note(None, "a".into(), "an #Idea for #2024".into()).unwrap();
+    let second = notes.create_note(None, "b".into(), "another #idea".into()).unwrap();
+
+    let listed = tags.list_tags().unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!((listed[0].name.as_str(), listed[0].note_count), ("Idea", 2));
+
+    // Dropping the hashtag unlinks the note but keeps the tag for the other one
+    notes.update_note(first.id.clone(), None, Some("no tags".into()), None, None).unwrap();
+    assert!(tags.get_note_tags(first.id).unwrap().is_empty());
+    assert_eq!(tags.get_note_tags(second.id).unwrap()[0].name, "Idea");
+}
+
+#[test]
+fn new_tags_take_the_least_used_palette_color() {
+    let db = memory_db();
+    let tags = TagService::new(&db);
+    let palette = list_palette();
+    let first = tags.create_tag("first".into(), None).unwrap();
+    let second = tags.create_tag("second".into(), None).unwrap();
+    assert_eq!((first.color.as_str(), second.color.as_str()), (palette[0].hex.as_str(), palette[1].hex.as_str()));
+
+    // Recoloring frees the first slot again
+    tags.update_tag(first.id.clone(), None, Some("slate".into()), None, None).unwrap();
+    assert_eq!(tags.create_tag("third".into(), None).unwrap().color, palette[0].hex);
+
+    let long = "x".repeat(MAX_TAG_DESCRIPTION_LEN + 1);
+    let err = tags.update_tag(first.id.clone(), None, None, None, Some(Some(long))).unwrap_err();
+    assert!(matches!(err, AppError::Validation { field: Some(ref f), .. } if f == "description"), "{:?}", err);
+    // A blank description clears it
+    tags.update_tag(first.id.clone(), None, None, None, Some(Some("notes".into()))).unwrap();
+    let cleared = tags.update_tag(first.id, None, None, None, Some(Some("  ".into()))).unwrap();
+    assert_eq!(cleared.description, None);
+}
+
+#[test]
+fn cooccurrence_pairs_are_ranked_by_overlap() {
+    let db = memory_db();
+    let notes = NoteService::new(&db);
+    for content in ["#rust #async", "#rust #async", "#rust #perf", "#cooking"] {
+        notes.create_note(None, "n".into(), content.into()).unwrap();
+    }
+
+    let pairs = SuggestionService::new(&db).get_tag_cooccurrence(1, 10).unwrap();
+    let named: Vec<(String, i64)> = pairs
+        .iter()
+        .map(|p| {
+            let mut names = [p.tag_a_name.as_str(), p.tag_b_name.as_str()];
+            names.sort();
+            (names.join("+"), p.shared_notes)
+        })
+        .collect();
+    assert_eq!(named, [("async+rust".to_string(), 2), ("perf+rust".to_string(), 1)]);
+    // rust is on 3 notes, async on 2, and they share 2
+    assert!((pairs[0].jaccard - 2.0 / 3.0).abs() < 1e-9);
+    assert_eq!(SuggestionService::new(&db).get_tag_cooccurrence(2, 10).unwrap().len(), 1);
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::memory_db;
+use mdnote_core::errors::AppError;
+use mdnote_core::models::task::{Priority, TaskFilter};
+use mdnote_core::services::note::NoteService;
+use mdnote_core::services::tag::TagService;
+use mdnote_core::services::task::{extract_tasks, TaskService};
+use mdnote_core::services::undo::UndoService;
+
+#[test]
+fn tasks_are_parsed_with_due_dates_priorities_and_tags() {
+    let content = "# Plan\n\
+        - [ ] Ship the release due:2026-03-02 ⏫ #work/release\n\
+        * [x] Book flights 📅 2026-02-27\n\
+        1. [ ] Call the bank priority:low\n\
+        - [ ]\n\
+        ```\n\
+        - [ ] not a task\n\
+        ```\n\
+        - [ ] Fix the 2026-02-30 typo due:2026-02-30\n";
+
+    let tasks = extract_tasks(content);
+    let summary: Vec<(usize, &str, bool)> = tasks.iter().map(|t| (t.line, t.text.as_str(), t.done)).collect();
+    assert_eq!(summary, [
+        (2, "Ship the release #work/release", false),
+        (3, "Book flights", true),
+        (4, "Call the bank", false),
+        (9, "Fix the 2026-02-30 typo due:2026-02-30", false),
+    ]);
+    assert_eq!(tasks[0].due_date.map(|d| d.to_string()).as_deref(), Some("2026-03-02"));
+    assert_eq!(tasks[0].priority, Some(Priority::High));
+    assert_eq!(tasks[0].tags, ["work/release"]);
+    assert_eq!(tasks[1].due_date.map(|d| d.to_string()).as_deref(), Some("2026-02-27"));
+    assert_eq!(tasks[2].priority, Some(Priority::Low));
+    assert_eq!(tasks[3].due_date, None);
+}
+
+#[test]
+fn the_agenda_follows_note_saves_and_groups_by_due_date() {
+    let db = memory_db();
+    let notes = NoteService::new(&db);
+    let tasks = TaskService::new(&db);
+
+    let work = notes
+        .create_note(None, "Work".into(), "- [ ] Review PR due:2026-03-03\n- [ ] Write docs due:2026-03-02 #docs\n- [ ] Someday".into())
+        .unwrap();
+    let home = notes
+        .create_note(None, "Home".into(), "- [ ] Taxes due:2026-03-02 ⏫\n- [x] Groceries due:2026-03-02".into())
+        .unwrap();
+
+    let open = tasks.list_tasks(TaskFilter { done: Some(false), ..Default::default() }).unwrap();
+    let agenda: Vec<(Option<&str>, Vec<&str>)> = open
+        .iter()
+        .map(|g| (g.due_date.as_deref(), g.tasks.iter().map(|t| t.text.as_str()).collect()))
+        .collect();
+    assert_eq!(agenda, [
+        (Some("2026-03-02"), vec!["Taxes", "Write docs #docs"]),
+        (Some("2026-03-03"), vec!["Review PR"]),
+        (None, vec!["Someday"]),
+    ]);
+
+    let tagged = tasks.list_tasks(TaskFilter { tag: Some("#Docs".into()), ..Default::default() }).unwrap();
+    assert_eq!(tagged.len(), 1);
+    assert_eq!(tagged[0].tasks[0].note_title, "Work");
+    let due = TaskFilter { due_to: Some("2026-03-02".into()), note_id: Some(home.id.clone()), ..Default::default() };
+    assert_eq!(tasks.list_tasks(due).unwrap()[0].tasks.len(), 2);
+    let bad = TaskFilter { due_from: Some("soon".into()), ..Default::default() };
+    assert!(matches!(tasks.list_tasks(bad), Err(AppError::Validation { field: Some(ref f), .. }) if f == "due_from"));
+
+    // Editing the note re-indexes its tasks
+    notes.update_note(work.id.clone(), None, Some("- [ ] Someday".into()), None, None).unwrap();
+    let work_tasks = tasks.list_tasks(TaskFilter { note_id: Some(work.id.clone()), ..Default::default() }).unwrap();
+    assert_eq!(work_tasks.len(), 1);
+    assert_eq!((work_tasks[0].tasks[0].line, work_tasks[0].due_date.as_deref()), (1, None));
+
+    notes.delete_note(home.id).unwrap();
+    assert_eq!(tasks.list_tasks(TaskFilter::default()).unwrap().len(), 1);
+}
+
+#[test]
+fn toggling_a_task_rewrites_its_checkbox() {
+    let db = memory_db();
+    let notes = NoteService::new(&db);
+    let tasks = TaskService::new(&db);
+    let note = notes.create_note(None, "Todo".into(), "Intro\r\n  - [ ] First\r\n- [X] Second\r\n".into()).unwrap();
+
+    let undo = UndoService::new(&db);
+    let first = undo.record("Toggle task", || tasks.toggle_task(note.id.clone(), 2)).unwrap();
+    assert!(first.done);
+    let second = undo.record("Toggle task", || tasks.toggle_task(note.id.clone(), 3)).unwrap();
+    assert!(!second.done);
+    assert_eq!(notes.get_note(note.id.clone()).unwrap().content, "Intro\r\n  - [x] First\r\n- [ ] Second\r\n");
+
+    assert!(matches!(tasks.toggle_task(note.id.clone(), 1), Err(AppError::NotFound(_))));
+    assert!(matches!(tasks.toggle_task(note.id.clone(), 9), Err(AppError::NotFound(_))));
+
+    // Undo puts the note and the index back together
+    undo.undo().unwrap();
+    undo.undo().unwrap();
+    assert_eq!(notes.get_note(note.id.clone()).unwrap().content, "Intro\r\n  - [ ] First\r\n- [X] Second\r\n");
+    assert!(!tasks.get_task(&note.id, 2).unwrap().done);
+    assert!(tasks.get_task(&note.id, 3).unwrap().done);
+}
+
+#[test]
+fn the_agenda_follows_tag_renames_and_merges() {
+    let db = memory_db();
+    let notes = NoteService::new(&db);
+    let tasks = TaskService::new(&db);
+    let tags = TagService::new(&db);
+    notes.create_note(None, "Work".into(), "- [ ] Write docs #docs\n- [ ] Ship #release".into()).unwrap();
+    let tagged = |name: &str| {
+        let filter = TaskFilter { tag: Some(name.into()), ..Default::default() };
+        tasks.list_tasks(filter).unwrap().iter().map(|g| g.tasks.len()).sum::<usize>()
+    };
+
+    let docs = tags.list_tags().unwrap().into_iter().find(|t| t.name == "docs").unwrap();
+    tags.rename_tag(docs.id.clone(), "writing".into()).unwrap();
+    assert_eq!((tagged("docs"), tagged("writing")), (0, 1));
+
+    let release = tags.list_tags().unwrap().into_iter().find(|t| t.name == "release").unwrap();
+    tags.merge_tags(vec![release.id], docs.id).unwrap();
+    assert_eq!((tagged("release"), tagged("writing")), (0, 2));
+}
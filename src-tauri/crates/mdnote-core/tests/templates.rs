@@ -0,0 +1,79 @@
+mod common;
+
+use std::collections::HashMap;
+use common::memory_db;
+use mdnote_core::errors::AppError;
+use mdnote_core::models::template::TemplateDraft;
+use mdnote_core::services::folder::FolderService;
+use mdnote_core::services::tag::TagService;
+use mdnote_core::services::template::TemplateService;
+
+fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+#[test]
+fn notes_from_templates_get_variables_folder_and_tags() {
+    let db = memory_db();
+    let templates = TemplateService::new(&db);
+    let meetings = FolderService::new(&db).create_folder("Meetings".into(), None).unwrap();
+    TagService::new(&db).create_tag("meeting".into(), Some("red".into())).unwrap();
+
+    let template = templates
+        .create_template(TemplateDraft {
+            name: " 1:1 ".into(),
+            title: "1:1 with {{ person }} {{date}}".into(),
+            content: "# {{title}}\n\nAt {{time}}\n- {{cursor}}\n\nOwner: {{person}}".into(),
+            folder_id: Some(meetings.id.clone()),
+            tags: vec!["meeting".into(), "one-on-one".into(), "Meeting".into()],
+        })
+        .unwrap();
+    assert_eq!(template.name, "1:1");
+    assert_eq!(template.tags, ["meeting", "one-on-one"]);
+    assert_eq!(template.variables, ["person"]);
+
+    let err = templates.preview_template(template.id.clone(), &HashMap::new()).unwrap_err();
+    assert_eq!(err.message(), "Template 1:1 uses undefined variables: person");
+
+    let vars = vars(&[("person", "Sam"), ("date", "2026-03-02"), ("time", "09:30")]);
+    let created = templates.create_note_from_template(template.id.clone(), None, &vars).unwrap();
+    let note = created.note;
+    assert_eq!(note.title, "1:1 with Sam 2026-03-02");
+    assert_eq!(note.content, "# 1:1 with Sam 2026-03-02\n\nAt 09:30\n- \n\nOwner: Sam");
+    assert_eq!(created.cursor, Some("# 1:1 with Sam 2026-03-02\n\nAt 09:30\n- ".len()));
+    assert_eq!(note.folder_id, Some(meetings.id));
+
+    let mut tags: Vec<String> = TagService::new(&db).get_note_tags(note.id).unwrap().into_iter().map(|t| t.name).collect();
+    tags.sort();
+    assert_eq!(tags, ["meeting", "one-on-one"]);
+
+    // An explicit folder wins over the template's
+    let inbox = FolderService::new(&db).create_folder("Inbox".into(), None).unwrap();
+    let created = templates.create_note_from_template(template.id, Some(inbox.id.clone()), &vars).unwrap();
+    assert_eq!(created.note.folder_id, Some(inbox.id));
+}
+
+#[test]
+fn template_titles_and_names_are_validated() {
+    let db = memory_db();
+    let templates = TemplateService::new(&db);
+    let draft = |name: &str, title: &str| TemplateDraft { name: name.into(), title: title.into(), ..Default::default() };
+
+    let err = templates.create_template(draft("Daily", "{{title}} again")).unwrap_err();
+    assert!(matches!(err, AppError::Validation { field: Some(ref f), .. } if f == "title"), "{:?}", err);
+    assert!(matches!(templates.create_template(draft("  ", "")), Err(AppError::Validation { .. })));
+
+    let daily = templates.create_template(draft("Daily", "")).unwrap();
+    assert!(matches!(templates.create_template(draft("daily", "")), Err(AppError::AlreadyExists(_))));
+
+    // Without a title the note is named after the template, and the cursor is optional
+    let preview = templates.preview_template(daily.id.clone(), &HashMap::new()).unwrap();
+    assert_eq!((preview.title.as_str(), preview.cursor), ("Daily", None));
+    assert!(templates.preview_template(daily.id.clone(), &vars(&[("cursor", "x")])).is_err());
+
+    let renamed = templates.update_template(daily.id.clone(), draft("Standup", "Standup {{date}}")).unwrap();
+    assert_eq!((renamed.name.as_str(), renamed.created_at), ("Standup", daily.created_at));
+    assert_eq!(templates.list_templates().unwrap().len(), 1);
+    templates.delete_template(daily.id.clone()).unwrap();
+    assert!(matches!(templates.get_template(daily.id), Err(AppError::NotFound(_))));
+}
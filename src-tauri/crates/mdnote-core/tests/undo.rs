@@ -0,0 +1,194 @@
+mod common;
+
+use std::path::Path;
+use common::{memory_db, TempDir};
+use mdnote_core::errors::AppError;
+use mdnote_core::models::reminder::ReminderDraft;
+use mdnote_core::services::backup::BackupService;
+use mdnote_core::services::events::ChangeEvent;
+use mdnote_core::services::folder::FolderService;
+use mdnote_core::services::note::NoteService;
+use mdnote_core::services::reminder::ReminderService;
+use mdnote_core::services::search::SearchService;
+use mdnote_core::services::tag::TagService;
+use mdnote_core::services::undo::UndoService;
+use mdnote_core::DatabaseService;
+
+fn tag_names(db: &DatabaseService, note_id: &str) -> Vec<String> {
+    let mut names: Vec<String> = TagService::new(db)
+        .get_note_tags(note_id.to_string())
+        .unwrap()
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn deleting_a_tag_can_be_undone_and_redone() {
+    let db = memory_db();
+    let undo = UndoService::new(&db);
+    let tags = TagService::new(&db);
+
+    let note = NoteService::new(&db).create_note(None, "Errands".into(), "buy milk #home".into()).unwrap();
+    let urgent = tags.create_tag("urgent".into(), Some("red".into())).unwrap();
+    undo.record("Tag note", || tags.add_tag_to_note(note.id.clone(), urgent.id.clone())).unwrap();
+    undo.record("Delete tag", || tags.delete_tag(urgent.id.clone())).unwrap();
+    assert_eq!(tag_names(&db, &note.id), ["home"]);
+
+    let undone = undo.undo().unwrap().unwrap();
+    assert_eq!(undone.label, "Delete tag");
+    assert_eq!(tag_names(&db, &note.id), ["home", "urgent"]);
+    assert_eq!(tags.get_tag(urgent.id.clone()).unwrap().color, "#ef4444");
+
+    assert_eq!(undo.undo().unwrap().unwrap().label, "Tag note");
+    assert_eq!(tag_names(&db, &note.id), ["home"]);
+    // Writes made outside an action are not in the history
+    assert_eq!(undo.undo().unwrap(), None);
+
+    assert_eq!(undo.redo().unwrap().unwrap().label, "Tag note");
+    assert_eq!(undo.redo().unwrap().unwrap().label, "Delete tag");
+    assert_eq!(undo.redo().unwrap(), None);
+    assert!(tags.get_tag(urgent.id).is_err());
+
+    let history: Vec<(String, bool)> = undo.list_history(10).unwrap().into_iter().map(|a| (a.label, a.undone)).collect();
+    assert_eq!(history, [("Delete tag".to_string(), false), ("Tag note".to_string(), false)]);
+}
+
+#[test]
+fn undoing_a_folder_delete_restores_its_subtree() {
+    let db = memory_db();
+    let undo = UndoService::new(&db);
+    let folders = FolderService::new(&db);
+    let notes = NoteService::new(&db);
+
+    let parent = folders.create_folder("Projects".into(), None).unwrap();
+    let child = folders.create_folder("Alpha".into(), Some(parent.id.clone())).unwrap();
+    let direct = notes.create_note(Some(parent.id.clone()), "Roadmap".into(), "quarterly goals #plan".into()).unwrap();
+    let nested = notes.create_note(Some(child.id.clone()), "Kickoff".into(), "agenda".into()).unwrap();
+
+    undo.record("Delete folder", || folders.delete_folder(parent.id.clone(), true)).unwrap();
+    assert!(folders.list_folders().unwrap().is_empty());
+    assert!(notes.get_note(direct.id.clone()).is_err());
+    assert_eq!(notes.get_note(nested.id.clone()).unwrap().folder_id, None);
+
+    undo.undo().unwrap();
+    assert_eq!(folders.list_folders().unwrap().len(), 2);
+    assert_eq!(notes.get_note(direct.id.clone()).unwrap().folder_id, Some(parent.id.clone()));
+    assert_eq!(notes.get_note(nested.id.clone()).unwrap().folder_id, Some(child.id));
+    assert_eq!(tag_names(&db, &direct.id), ["plan"]);
+    // The restored note is searchable again
+    assert_eq!(SearchService::new(&db).search_notes("quarterly".into(), 10).unwrap().len(), 1);
+
+    undo.redo().unwrap();
+    assert!(folders.list_folders().unwrap().is_empty());
+    assert!(notes.get_note(direct.id).is_err());
+}
+
+#[test]
+fn undo_refuses_to_overwrite_later_changes() {
+    let db = memory_db();
+    let undo = UndoService::new(&db);
+    let notes = NoteService::new(&db);
+
+    let note = notes.create_note(None, "Draft".into(), "v1".into()).unwrap();
+    undo.record("Edit note", || notes.update_note(note.id.clone(), None, Some("v2".into()), None, None)).unwrap();
+    // Another client edits the note without going through the history
+    notes.update_note(note.id.clone(), None, Some("v3".into()), None, None).unwrap();
+
+    assert!(matches!(undo.undo(), Err(AppError::Conflict { current: None, .. })));
+    assert_eq!(notes.get_note(note.id.clone()).unwrap().content, "v3");
+    assert!(!undo.list_history(1).unwrap()[0].undone);
+}
+
+#[test]
+fn history_survives_a_restart_and_new_actions_drop_redo() {
+    let dir = TempDir::new();
+    let note_id = {
+        let db = DatabaseService::new(dir.path().to_path_buf()).unwrap();
+        let undo = UndoService::new(&db);
+        let notes = NoteService::new(&db);
+        let note = undo.record("Create note", || notes.create_note(None, "Keep".into(), String::new())).unwrap();
+        undo.record("Rename note", || notes.update_note(note.id.clone(), Some("Kept".into()), None, None, None)).unwrap();
+        note.id
+    };
+
+    let db = DatabaseService::new(dir.path().to_path_buf()).unwrap();
+    let undo = UndoService::new(&db);
+    let notes = NoteService::new(&db);
+    assert_eq!(undo.undo().unwrap().unwrap().label, "Rename note");
+    assert_eq!(notes.get_note(note_id.clone()).unwrap().title, "Keep");
+
+    // An action that changes nothing isn't kept, and the redo stays available
+    undo.record("Nothing", || Ok(())).unwrap();
+    assert_eq!(undo.list_history(10).unwrap().len(), 2);
+    undo.record("Rename note", || notes.update_note(note_id.clone(), Some("Other".into()), None, None, None)).unwrap();
+    assert_eq!(undo.redo().unwrap(), None);
+    let labels: Vec<String> = undo.list_history(10).unwrap().into_iter().map(|a| a.label).collect();
+    assert_eq!(labels, ["Rename note", "Create note"]);
+}
+
+#[test]
+fn other_connections_do_not_write_into_an_action() {
+    let dir = TempDir::new();
+    let app = DatabaseService::new(dir.path().to_path_buf()).unwrap();
+    let cli = DatabaseService::new(dir.path().to_path_buf()).unwrap();
+    let undo = UndoService::new(&app);
+
+    let (mine, theirs) = undo
+        .record("Create note", || {
+            let mine = NoteService::new(&app).create_note(None, "Mine".into(), String::new())?;
+            // Another process saving while the action is still open
+            let theirs = NoteService::new(&cli).create_note(None, "Theirs".into(), String::new())?;
+            Ok((mine, theirs))
+        })
+        .unwrap();
+
+    undo.undo().unwrap().unwrap();
+    assert!(matches!(NoteService::new(&app).get_note(mine.id), Err(AppError::NotFound(_))));
+    assert_eq!(NoteService::new(&app).get_note(theirs.id).unwrap().title, "Theirs");
+}
+
+#[test]
+fn restoring_a_backup_stops_recording() {
+    let dir = TempDir::new();
+    let mut db = DatabaseService::new(dir.path().to_path_buf()).unwrap();
+    let backup = BackupService::new(&db).create_backup(None).unwrap();
+
+    // An action still open when the restore happens, which the snapshot knows nothing of
+    UndoService::new(&db)
+        .record("Create note", || NoteService::new(&db).create_note(None, "lost".into(), String::new()))
+        .unwrap();
+    db.connection()
+        .execute_batch("UPDATE temp.undo_recording SET action_id = (SELECT MAX(id) FROM undo_actions)")
+        .unwrap();
+
+    db.restore_from(Path::new(&backup.path)).unwrap();
+    let note = NoteService::new(&db).create_note(None, "after".into(), String::new()).unwrap();
+    assert!(UndoService::new(&db).list_history(10).unwrap().is_empty());
+    assert_eq!(NoteService::new(&db).get_note(note.id).unwrap().title, "after");
+}
+
+#[test]
+fn undoing_a_reminder_change_announces_it() {
+    let mut db = memory_db();
+    db.track_changes();
+    let note = NoteService::new(&db).create_note(None, "Dentist".into(), String::new()).unwrap();
+    let remind_at = chrono::Utc::now().timestamp_millis() + 60 * 60 * 1000;
+    let draft = ReminderDraft { note_id: note.id.clone(), task_line: None, message: None, remind_at };
+    let reminder = ReminderService::new(&db).create_reminder(draft).unwrap();
+
+    let undo = UndoService::new(&db);
+    undo.record("Snooze reminder", || ReminderService::new(&db).snooze_reminder(reminder.id.clone(), remind_at + 1000)).unwrap();
+    db.take_changes();
+
+    undo.undo().unwrap();
+    assert_eq!(ReminderService::new(&db).get_reminder(&reminder.id).unwrap().remind_at, remind_at);
+    assert_eq!(db.take_changes(), [ChangeEvent::ReminderChanged { id: reminder.id.clone(), note_id: note.id.clone() }]);
+
+    // Changed again since, so redoing it would overwrite the newer time
+    ReminderService::new(&db).snooze_reminder(reminder.id, remind_at + 2000).unwrap();
+    let conflict = undo.redo();
+    assert!(matches!(conflict, Err(AppError::Conflict { message, .. }) if message.starts_with("A reminder")));
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::TempDir;
+use mdnote_core::errors::AppError;
+use mdnote_core::services::database::{DbPool, DbState};
+use mdnote_core::services::note::NoteService;
+use mdnote_core::services::vault::{VaultService, MAX_VAULT_NAME_LEN};
+
+#[test]
+fn registry_tracks_vaults_and_last_opened() {
+    let dir = TempDir::new();
+    let vaults = VaultService::new(dir.path());
+
+    let default = vaults.startup_vault(dir.path()).unwrap();
+    assert_eq!(default.name, "Default");
+
+    let work = vaults.create_vault("Work".into(), dir.path().join("work")).unwrap();
+    assert!(vaults.create_vault("Again".into(), dir.path().join("work")).is_err());
+
+    vaults.mark_opened(&work.id).unwrap();
+    assert_eq!(VaultService::new(dir.path()).startup_vault(dir.path()).unwrap().id, work.id);
+    assert_eq!(vaults.rename_vault(&work.id, " Job ".into()).unwrap().name, "Job");
+    assert_eq!(vaults.list_vaults().unwrap().len(), 2);
+}
+
+#[test]
+fn switching_vaults_keeps_their_notes_apart() {
+    let dir = TempDir::new();
+    let vaults = VaultService::new(dir.path());
+    let home = vaults.create_vault("Home".into(), dir.path().join("home")).unwrap();
+    let work = vaults.create_vault("Work".into(), dir.path().join("work")).unwrap();
+    for name in ["", &"x".repeat(MAX_VAULT_NAME_LEN + 1)] {
+        assert!(matches!(vaults.rename_vault(&work.id, name.into()), Err(AppError::Validation { .. })), "{:?}", name);
+    }
+
+    let state = DbState::new();
+    let titles = |state: &DbState| {
+        let (notes, _) = state.pool().unwrap().with_reader(|db| NoteService::new(db).list_notes(None, None, false, 10, 0)).unwrap();
+        notes.into_iter().map(|n| n.title).collect::<Vec<_>>()
+    };
+    state.open(home.id.clone(), DbPool::new(home.path.clone().into(), 1).unwrap()).unwrap();
+    state.pool().unwrap().with_writer(|db| NoteService::new(db).create_note(None, "groceries".into(), String::new())).unwrap();
+    // A pool taken before switching keeps working on its own vault
+    let home_pool = state.pool().unwrap();
+
+    state.open(work.id.clone(), DbPool::new(work.path.clone().into(), 1).unwrap()).unwrap();
+    assert_eq!(state.current_vault_id().unwrap().as_deref(), Some(work.id.as_str()));
+    assert!(titles(&state).is_empty());
+    state.pool().unwrap().with_writer(|db| NoteService::new(db).create_note(None, "roadmap".into(), String::new())).unwrap();
+    assert_eq!(home_pool.with_reader(|db| NoteService::new(db).list_notes(None, None, false, 10, 0)).unwrap().1, 1);
+
+    state.open(home.id, DbPool::new(home.path.into(), 1).unwrap()).unwrap();
+    assert_eq!(titles(&state), ["groceries"]);
+}
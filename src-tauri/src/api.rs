@@ -0,0 +1,83 @@
+use std::sync::Mutex;
+use mdnote_api::{ApiConfig, ApiServer};
+use mdnote_core::errors::{AppError, Result};
+use mdnote_core::models::settings::Settings;
+use mdnote_core::services::database::DbState;
+use mdnote_core::services::settings::SettingsService;
+use crate::db::DbStateExt;
+
+struct Running {
+    server: ApiServer,
+    port: u16,
+    allowed_origins: Vec<String>,
+}
+
+/// The local HTTP API, running while the open vault has it turned on.
+#[derive(Default)]
+pub struct ApiState {
+    running: Mutex<Option<Running>>,
+}
+
+impl ApiState {
+    /// Starts, restarts or stops the server to match `settings`. Only a new port or origin
+    /// list restarts it; the server checks the token and the on/off switch per request.
+    pub fn apply(&self, state: &DbState, settings: &Settings) -> Result<()> {
+        let mut running = self.running.lock().map_err(|e| AppError::lock(e.to_string()))?;
+        if let Some(current) = running.as_ref() {
+            if settings.api_enabled
+                && current.port == settings.api_port
+                && current.allowed_origins == settings.api_allowed_origins
+            {
+                return Ok(());
+            }
+        }
+
+        // Dropping the old server frees its port before the new one binds
+        if let Some(old) = running.take() {
+            old.server.stop();
+        }
+        if !settings.api_enabled {
+            return Ok(());
+        }
+
+        let server = ApiServer::start(
+            state.clone(),
+            ApiConfig { port: settings.api_port, allowed_origins: settings.api_allowed_origins.clone() },
+        )?;
+        tracing::info!("Local API listening on 127.0.0.1:{}", server.port());
+        *running = Some(Running {
+            server,
+            port: settings.api_port,
+            allowed_origins: settings.api_allowed_origins.clone(),
+        });
+        Ok(())
+    }
+
+    /// Stops the server, if running. Used when the vault it serves is closed.
+    pub fn stop(&self) -> Result<()> {
+        let mut running = self.running.lock().map_err(|e| AppError::lock(e.to_string()))?;
+        if let Some(old) = running.take() {
+            old.server.stop();
+        }
+        Ok(())
+    }
+
+    /// Follows the settings of the vault that is open now, generating its token the first
+    /// time the API is turned on. A locked vault leaves the server as it is.
+    pub async fn sync(&self, state: &DbState) -> Result<()> {
+        let settings = state.write(|db| {
+            let service = SettingsService::new(db);
+            let settings = service.get_settings()?;
+            if settings.api_enabled {
+                service.ensure_api_token()?;
+            }
+            Ok(settings)
+        }).await;
+
+        match settings {
+            Ok(settings) => self.apply(state, &settings),
+            Err(AppError::Locked(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use tauri::State;
-use crate::models::note::NoteSummary;
-use crate::services::database::DbState;
-use crate::services::backlink::{BacklinkService, Backlink};
-use crate::errors::{Result, AppError};
+use mdnote_core::models::note::NoteSummary;
+use mdnote_core::services::database::DbState;
+use crate::db::DbStateExt;
+use mdnote_core::services::backlink::{BacklinkService, Backlink};
+use mdnote_core::errors::Result;
 
 #[tauri::command]
 pub async fn add_backlink(
@@ -11,9 +12,10 @@ pub async fn add_backlink(
     target_id: String,
     context: Option<String>,
 ) -> Result<()> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = BacklinkService::new(&db);
-    service.add_link(source_id, target_id, context)
+    state.action("Add link", move |db| {
+        let service = BacklinkService::new(db);
+        service.add_link(source_id, target_id, context)
+    }).await
 }
 
 #[tauri::command]
@@ -22,9 +24,10 @@ pub async fn remove_backlink(
     source_id: String,
     target_id: String,
 ) -> Result<()> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = BacklinkService::new(&db);
-    service.remove_link(source_id, target_id)
+    state.action("Remove link", move |db| {
+        let service = BacklinkService::new(db);
+        service.remove_link(source_id, target_id)
+    }).await
 }
 
 #[tauri::command]
@@ -32,9 +35,10 @@ pub async fn get_backlinks(
     state: State<'_, DbState>,
     target_id: String,
 ) -> Result<Vec<Backlink>> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = BacklinkService::new(&db);
-    service.get_backlinks(target_id)
+    state.read(move |db| {
+        let service = BacklinkService::new(db);
+        service.get_backlinks(target_id)
+    }).await
 }
 
 #[tauri::command]
@@ -42,9 +46,10 @@ pub async fn get_outgoing_links(
     state: State<'_, DbState>,
     source_id: String,
 ) -> Result<Vec<NoteSummary>> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = BacklinkService::new(&db);
-    service.get_outgoing_links(source_id)
+    state.read(move |db| {
+        let service = BacklinkService::new(db);
+        service.get_outgoing_links(source_id)
+    }).await
 }
 
 #[tauri::command]
@@ -53,28 +58,8 @@ pub async fn sync_backlinks(
     source_id: String,
     content: String,
 ) -> Result<()> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = BacklinkService::new(&db);
-    
-    // Simple regex-based discovery for now: [[note-id]] or #tag-like-links
-    // In a real app, we'd use a markdown parser. 
-    // Here we'll look for [[ulid]] patterns.
-    service.clear_outgoing_links(source_id.clone())?;
-    
-    let re = regex::Regex::new(r"\[\[([0-9A-HJKMNP-TV-Z]{26})\]\]").unwrap();
-    for cap in re.captures_iter(&content) {
-        let target_id = cap[1].to_string();
-        // Avoid self-links
-        if target_id != source_id {
-            // Extract a snippet of context (surrounding text)
-            let match_pos = cap.get(0).unwrap().start();
-            let start = match_pos.saturating_sub(40);
-            let end = (match_pos + 66).min(content.len());
-            let context = content[start..end].to_string();
-            
-            service.add_link(source_id.clone(), target_id, Some(context))?;
-        }
-    }
-    
-    Ok(())
+    state.write(move |db| {
+        let service = BacklinkService::new(db);
+        service.sync_links(source_id, &content)
+    }).await
 }
@@ -0,0 +1,56 @@
+use std::path::Path;
+use tauri::State;
+use mdnote_core::services::database::DbState;
+use crate::db::DbStateExt;
+use mdnote_core::services::backup::{list_vault_backups, restore_failed_vault, BackupService, BackupInfo};
+use mdnote_core::errors::{AppError, Result};
+
+#[tauri::command]
+pub async fn create_backup(
+    state: State<'_, DbState>,
+    path: Option<String>,
+) -> Result<BackupInfo> {
+    state.read(move |db| {
+        let service = BackupService::new(db);
+        service.create_backup(path)
+    }).await
+}
+
+#[tauri::command]
+pub async fn list_backups(
+    state: State<'_, DbState>,
+) -> Result<Vec<BackupInfo>> {
+    // A vault whose database failed to open still lists its backups, so one can be restored
+    if let Some(dir) = state.failed_vault_dir()? {
+        return tauri::async_runtime::spawn_blocking(move || list_vault_backups(&dir, None))
+            .await
+            .map_err(|e| AppError::lock(e.to_string()))?;
+    }
+    state.read(move |db| {
+        let service = BackupService::new(db);
+        service.list_backups()
+    }).await
+}
+
+#[tauri::command]
+pub async fn restore_backup(
+    state: State<'_, DbState>,
+    id: String,
+) -> Result<BackupInfo> {
+    if state.failed_vault_dir()?.is_some() {
+        let db_state = state.inner().clone();
+        let backup_id = id.clone();
+        let restored = tauri::async_runtime::spawn_blocking(move || restore_failed_vault(&db_state, &backup_id))
+            .await
+            .map_err(|e| AppError::lock(e.to_string()))??;
+        if let Some(backup) = restored {
+            return Ok(backup);
+        }
+    }
+
+    state.write(move |db| {
+        let backup = BackupService::new(db).prepare_restore(&id)?;
+        db.restore_from(Path::new(&backup.path))?;
+        Ok(backup)
+    }).await
+}
@@ -0,0 +1,114 @@
+use std::path::{Path, PathBuf};
+use tauri::State;
+use mdnote_core::services::database::DbState;
+use mdnote_core::services::encryption::{self, EncryptionStatus, NoteKeyring};
+use mdnote_core::services::vault::VaultService;
+use mdnote_core::errors::{Result, AppError};
+use serde::Serialize;
+use crate::api::ApiState;
+
+#[derive(Serialize)]
+pub struct EnableEncryptionResponse {
+    pub status: EncryptionStatus,
+    /// Backups written before encryption was enabled; they are still readable without the passphrase.
+    pub plaintext_backups: Vec<String>,
+}
+
+fn current_vault(state: &DbState, vaults: &VaultService) -> Result<(String, PathBuf)> {
+    let id = state
+        .current_vault_id()?
+        .ok_or_else(|| AppError::NotFound("No vault is open".to_string()))?;
+    let vault = vaults.get_vault(&id)?;
+    Ok((id, PathBuf::from(vault.path)))
+}
+
+fn status(state: &DbState, vault_dir: &Path) -> Result<EncryptionStatus> {
+    Ok(EncryptionStatus {
+        supported: encryption::SUPPORTED,
+        encrypted: encryption::is_encrypted(vault_dir),
+        locked: state.is_locked()?,
+    })
+}
+
+#[tauri::command]
+pub async fn get_encryption_status(
+    state: State<'_, DbState>,
+    vaults: State<'_, VaultService>,
+) -> Result<EncryptionStatus> {
+    let (_, dir) = current_vault(&state, &vaults)?;
+    status(&state, &dir)
+}
+
+#[tauri::command]
+pub async fn enable_encryption(
+    state: State<'_, DbState>,
+    vaults: State<'_, VaultService>,
+    passphrase: String,
+) -> Result<EnableEncryptionResponse> {
+    let (id, dir) = current_vault(&state, &vaults)?;
+    let db_state = state.inner().clone();
+    let vault_dir = dir.clone();
+    let plaintext_backups = tauri::async_runtime::spawn_blocking(move || {
+        encryption::enable_encryption(&db_state, id, vault_dir, &passphrase)
+    })
+    .await
+    .map_err(|e| AppError::lock(e.to_string()))??;
+
+    Ok(EnableEncryptionResponse {
+        status: status(&state, &dir)?,
+        plaintext_backups,
+    })
+}
+
+#[tauri::command]
+pub async fn change_passphrase(
+    state: State<'_, DbState>,
+    vaults: State<'_, VaultService>,
+    current_passphrase: String,
+    new_passphrase: String,
+) -> Result<()> {
+    let (id, dir) = current_vault(&state, &vaults)?;
+    let db_state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        encryption::change_passphrase(&db_state, id, dir, &current_passphrase, &new_passphrase)
+    })
+    .await
+    .map_err(|e| AppError::lock(e.to_string()))?
+}
+
+#[tauri::command]
+pub async fn unlock_vault(
+    state: State<'_, DbState>,
+    vaults: State<'_, VaultService>,
+    api: State<'_, ApiState>,
+    passphrase: String,
+) -> Result<EncryptionStatus> {
+    let (id, dir) = current_vault(&state, &vaults)?;
+    let db_state = state.inner().clone();
+    let vault_dir = dir.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        encryption::unlock_vault(&db_state, id, vault_dir, &passphrase)
+    })
+    .await
+    .map_err(|e| AppError::lock(e.to_string()))??;
+
+    if let Err(e) = api.sync(&state).await {
+        tracing::warn!("Local API not started after unlock: {}", e);
+    }
+    status(&state, &dir)
+}
+
+#[tauri::command]
+pub async fn lock_vault(
+    state: State<'_, DbState>,
+    vaults: State<'_, VaultService>,
+    keyring: State<'_, NoteKeyring>,
+    api: State<'_, ApiState>,
+) -> Result<EncryptionStatus> {
+    let (_, dir) = current_vault(&state, &vaults)?;
+    encryption::lock_vault(&state, &dir)?;
+    keyring.clear()?;
+    // Like closing: the server goes down with the vault and unlock_vault brings it back
+    api.stop()?;
+    status(&state, &dir)
+}
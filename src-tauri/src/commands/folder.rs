@@ -1,8 +1,9 @@
 use tauri::State;
-use crate::models::folder::Folder;
-use crate::services::database::DbState;
-use crate::services::folder::{FolderService, FolderListItem};
-use crate::errors::{Result, AppError};
+use mdnote_core::models::folder::Folder;
+use mdnote_core::services::database::DbState;
+use crate::db::DbStateExt;
+use mdnote_core::services::folder::{FolderService, FolderListItem};
+use mdnote_core::errors::Result;
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -17,18 +18,20 @@ pub async fn create_folder(
     name: String,
     parent_id: Option<String>,
 ) -> Result<Folder> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = FolderService::new(&db);
-    service.create_folder(name, parent_id)
+    state.action("Create folder", move |db| {
+        let service = FolderService::new(db);
+        service.create_folder(name, parent_id)
+    }).await
 }
 
 #[tauri::command]
 pub async fn list_folders(
     state: State<'_, DbState>,
 ) -> Result<Vec<FolderListItem>> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = FolderService::new(&db);
-    service.list_folders()
+    state.read(move |db| {
+        let service = FolderService::new(db);
+        service.list_folders()
+    }).await
 }
 
 #[tauri::command]
@@ -38,9 +41,11 @@ pub async fn update_folder(
     name: Option<String>,
     parent_id: Option<Option<String>>,
 ) -> Result<Folder> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = FolderService::new(&db);
-    service.update_folder(id, name, parent_id)
+    let label = if name.is_none() && parent_id.is_some() { "Move folder" } else { "Rename folder" };
+    state.action(label, move |db| {
+        let service = FolderService::new(db);
+        service.update_folder(id, name, parent_id)
+    }).await
 }
 
 #[tauri::command]
@@ -49,8 +54,9 @@ pub async fn delete_folder(
     id: String,
     delete_notes: Option<bool>,
 ) -> Result<DeleteFolderResponse> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = FolderService::new(&db);
-    let moved_notes = service.delete_folder(id, delete_notes.unwrap_or(false))?;
-    Ok(DeleteFolderResponse { success: true, moved_notes })
+    state.action("Delete folder", move |db| {
+        let service = FolderService::new(db);
+        let moved_notes = service.delete_folder(id, delete_notes.unwrap_or(false))?;
+        Ok(DeleteFolderResponse { success: true, moved_notes })
+    }).await
 }
@@ -0,0 +1,78 @@
+use tauri::State;
+use mdnote_core::services::database::DbState;
+use crate::db::DbStateExt;
+use mdnote_core::services::maintenance::{CheckpointResult, DbStats, ForeignKeyViolation, IntegrityReport, MaintenanceService};
+use mdnote_core::errors::Result;
+
+#[tauri::command]
+pub async fn integrity_check(
+    state: State<'_, DbState>,
+    quick: Option<bool>,
+) -> Result<IntegrityReport> {
+    state.read(move |db| {
+        let service = MaintenanceService::new(db);
+        service.integrity_check(quick.unwrap_or(false))
+    }).await
+}
+
+#[tauri::command]
+pub async fn foreign_key_check(
+    state: State<'_, DbState>,
+) -> Result<Vec<ForeignKeyViolation>> {
+    state.read(move |db| {
+        let service = MaintenanceService::new(db);
+        service.foreign_key_check()
+    }).await
+}
+
+#[tauri::command]
+pub async fn vacuum(
+    state: State<'_, DbState>,
+) -> Result<()> {
+    state.write(move |db| {
+        let service = MaintenanceService::new(db);
+        service.vacuum()
+    }).await
+}
+
+#[tauri::command]
+pub async fn incremental_vacuum(
+    state: State<'_, DbState>,
+    pages: Option<i64>,
+) -> Result<i64> {
+    state.write(move |db| {
+        let service = MaintenanceService::new(db);
+        service.incremental_vacuum(pages)
+    }).await
+}
+
+#[tauri::command]
+pub async fn analyze(
+    state: State<'_, DbState>,
+) -> Result<()> {
+    state.write(move |db| {
+        let service = MaintenanceService::new(db);
+        service.analyze()
+    }).await
+}
+
+#[tauri::command]
+pub async fn wal_checkpoint(
+    state: State<'_, DbState>,
+    truncate: Option<bool>,
+) -> Result<CheckpointResult> {
+    state.write(move |db| {
+        let service = MaintenanceService::new(db);
+        service.wal_checkpoint(truncate.unwrap_or(false))
+    }).await
+}
+
+#[tauri::command]
+pub async fn get_db_stats(
+    state: State<'_, DbState>,
+) -> Result<DbStats> {
+    state.read(move |db| {
+        let service = MaintenanceService::new(db);
+        service.get_db_stats()
+    }).await
+}
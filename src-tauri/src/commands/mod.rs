@@ -5,3 +5,12 @@ pub mod search;
 pub mod file;
 pub mod settings;
 pub mod backlink;
+pub mod backup;
+pub mod maintenance;
+pub mod vault;
+pub mod encryption;
+pub mod undo;
+pub mod template;
+pub mod periodic;
+pub mod task;
+pub mod reminder;
@@ -1,8 +1,12 @@
 use tauri::State;
-use crate::models::note::{Note, NoteSummary};
-use crate::services::database::DbState;
-use crate::services::note::NoteService;
-use crate::errors::{Result, AppError};
+use mdnote_core::models::note::{Note, NoteSummary};
+use mdnote_core::models::property::{PropertyQuery, PropertyValue};
+use mdnote_core::services::database::DbState;
+use crate::db::DbStateExt;
+use mdnote_core::services::encryption::NoteKeyring;
+use mdnote_core::services::merge::{merge_text, MergeResult};
+use mdnote_core::services::note::NoteService;
+use mdnote_core::errors::Result;
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -18,32 +22,66 @@ pub async fn create_note(
     title: String,
     content: String,
 ) -> Result<Note> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = NoteService::new(&db);
-    service.create_note(folder_id, title, content)
+    state.action("Create note", move |db| {
+        let service = NoteService::new(db);
+        service.create_note(folder_id, title, content)
+    }).await
 }
 
 #[tauri::command]
 pub async fn get_note(
     state: State<'_, DbState>,
+    keyring: State<'_, NoteKeyring>,
     id: String,
 ) -> Result<Note> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = NoteService::new(&db);
-    service.get_note(id)
+    let keyring = keyring.inner().clone();
+    state.read(move |db| {
+        let service = NoteService::new(db).with_keyring(&keyring);
+        service.get_note(id)
+    }).await
 }
 
 #[tauri::command]
 pub async fn update_note(
     state: State<'_, DbState>,
+    keyring: State<'_, NoteKeyring>,
     id: String,
     title: Option<String>,
     content: Option<String>,
     folder_id: Option<Option<String>>,
+    expected_updated_at: Option<i64>,
 ) -> Result<Note> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = NoteService::new(&db);
-    service.update_note(id, title, content, folder_id)
+    let keyring = keyring.inner().clone();
+    let label = if title.is_none() && content.is_none() && folder_id.is_some() { "Move note" } else { "Edit note" };
+    state.action(label, move |db| {
+        let service = NoteService::new(db).with_keyring(&keyring);
+        service.update_note(id, title, content, folder_id, expected_updated_at)
+    }).await
+}
+
+/// Sets a front matter property, or removes it when `value` is null.
+#[tauri::command]
+pub async fn set_note_property(
+    state: State<'_, DbState>,
+    keyring: State<'_, NoteKeyring>,
+    id: String,
+    key: String,
+    value: Option<PropertyValue>,
+) -> Result<Note> {
+    let keyring = keyring.inner().clone();
+    state.action("Set property", move |db| {
+        let service = NoteService::new(db).with_keyring(&keyring);
+        service.set_note_property(id, key, value)
+    }).await
+}
+
+#[tauri::command]
+pub async fn merge_note_content(
+    base: String,
+    ours: String,
+    theirs: String,
+) -> Result<MergeResult> {
+    Ok(merge_text(&base, &ours, &theirs))
 }
 
 #[tauri::command]
@@ -51,10 +89,11 @@ pub async fn delete_note(
     state: State<'_, DbState>,
     id: String,
 ) -> Result<bool> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = NoteService::new(&db);
-    service.delete_note(id)?;
-    Ok(true)
+    state.action("Delete note", move |db| {
+        let service = NoteService::new(db);
+        service.delete_note(id)?;
+        Ok(true)
+    }).await
 }
 
 #[tauri::command]
@@ -62,11 +101,56 @@ pub async fn list_notes(
     state: State<'_, DbState>,
     folder_id: Option<String>,
     tag_id: Option<String>,
+    include_descendants: Option<bool>,
     limit: Option<i32>,
     offset: Option<i32>,
+    properties: Option<PropertyQuery>,
 ) -> Result<ListNotesResponse> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = NoteService::new(&db);
-    let (notes, total) = service.list_notes(folder_id, tag_id, limit.unwrap_or(50), offset.unwrap_or(0))?;
-    Ok(ListNotesResponse { notes, total })
+    state.read(move |db| {
+        let service = NoteService::new(db);
+        let (notes, total) = service.list_notes_with_properties(
+            folder_id,
+            tag_id,
+            include_descendants.unwrap_or(false),
+            &properties.unwrap_or_default(),
+            limit.unwrap_or(50),
+            offset.unwrap_or(0),
+        )?;
+        Ok(ListNotesResponse { notes, total })
+    }).await
+}
+
+#[tauri::command]
+pub async fn encrypt_note(
+    state: State<'_, DbState>,
+    keyring: State<'_, NoteKeyring>,
+    id: String,
+    passphrase: String,
+) -> Result<Note> {
+    let keyring = keyring.inner().clone();
+    state.write(move |db| {
+        let service = NoteService::new(db).with_keyring(&keyring);
+        service.encrypt_note(id, &passphrase)
+    }).await
+}
+
+#[tauri::command]
+pub async fn decrypt_note_for_session(
+    state: State<'_, DbState>,
+    keyring: State<'_, NoteKeyring>,
+    id: String,
+    passphrase: String,
+) -> Result<Note> {
+    let keyring = keyring.inner().clone();
+    state.read(move |db| {
+        let service = NoteService::new(db).with_keyring(&keyring);
+        service.decrypt_note_for_session(id, &passphrase)
+    }).await
+}
+
+#[tauri::command]
+pub async fn lock_all_notes(
+    keyring: State<'_, NoteKeyring>,
+) -> Result<()> {
+    keyring.clear()
 }
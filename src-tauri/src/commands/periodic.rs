@@ -0,0 +1,59 @@
+use tauri::State;
+use mdnote_core::services::database::DbState;
+use crate::db::DbStateExt;
+use mdnote_core::services::periodic::{parse_date, today, Period, PeriodicCalendar, PeriodicNote, PeriodicService};
+use mdnote_core::errors::Result;
+
+/// Opens the note for the period containing `date`, today when it is omitted.
+async fn open_periodic_note(state: State<'_, DbState>, period: Period, date: Option<String>) -> Result<PeriodicNote> {
+    let date = match date {
+        Some(date) => parse_date("date", &date)?,
+        None => today(),
+    };
+    let label = match period {
+        Period::Daily => "Create daily note",
+        Period::Weekly => "Create weekly note",
+        Period::Monthly => "Create monthly note",
+    };
+    state.action(label, move |db| {
+        let service = PeriodicService::new(db);
+        service.open_periodic_note(period, date)
+    }).await
+}
+
+#[tauri::command]
+pub async fn open_daily_note(
+    state: State<'_, DbState>,
+    date: Option<String>,
+) -> Result<PeriodicNote> {
+    open_periodic_note(state, Period::Daily, date).await
+}
+
+#[tauri::command]
+pub async fn open_weekly_note(
+    state: State<'_, DbState>,
+    date: Option<String>,
+) -> Result<PeriodicNote> {
+    open_periodic_note(state, Period::Weekly, date).await
+}
+
+#[tauri::command]
+pub async fn open_monthly_note(
+    state: State<'_, DbState>,
+    date: Option<String>,
+) -> Result<PeriodicNote> {
+    open_periodic_note(state, Period::Monthly, date).await
+}
+
+#[tauri::command]
+pub async fn list_periodic_notes(
+    state: State<'_, DbState>,
+    from: String,
+    to: String,
+) -> Result<PeriodicCalendar> {
+    let (from, to) = (parse_date("from", &from)?, parse_date("to", &to)?);
+    state.read(move |db| {
+        let service = PeriodicService::new(db);
+        service.list_periodic_notes(from, to)
+    }).await
+}
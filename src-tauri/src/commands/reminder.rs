@@ -0,0 +1,72 @@
+use tauri::State;
+use mdnote_core::services::database::DbState;
+use crate::db::DbStateExt;
+use mdnote_core::models::reminder::{DueReminder, Reminder, ReminderDraft};
+use mdnote_core::services::reminder::ReminderService;
+use mdnote_core::errors::Result;
+
+#[tauri::command]
+pub async fn create_reminder(
+    state: State<'_, DbState>,
+    reminder: ReminderDraft,
+) -> Result<Reminder> {
+    state.write(move |db| {
+        let service = ReminderService::new(db);
+        service.create_reminder(reminder)
+    }).await
+}
+
+#[tauri::command]
+pub async fn snooze_reminder(
+    state: State<'_, DbState>,
+    id: String,
+    until: i64,
+) -> Result<Reminder> {
+    state.write(move |db| {
+        let service = ReminderService::new(db);
+        service.snooze_reminder(id, until)
+    }).await
+}
+
+#[tauri::command]
+pub async fn delete_reminder(
+    state: State<'_, DbState>,
+    id: String,
+) -> Result<()> {
+    state.write(move |db| {
+        let service = ReminderService::new(db);
+        service.delete_reminder(id)
+    }).await
+}
+
+#[tauri::command]
+pub async fn list_upcoming_reminders(
+    state: State<'_, DbState>,
+    limit: Option<i64>,
+) -> Result<Vec<Reminder>> {
+    state.read(move |db| {
+        let service = ReminderService::new(db);
+        service.list_upcoming_reminders(limit.unwrap_or(50))
+    }).await
+}
+
+#[tauri::command]
+pub async fn list_missed_reminders(
+    state: State<'_, DbState>,
+) -> Result<Vec<DueReminder>> {
+    state.read(move |db| {
+        let service = ReminderService::new(db);
+        service.list_missed_reminders()
+    }).await
+}
+
+#[tauri::command]
+pub async fn acknowledge_reminders(
+    state: State<'_, DbState>,
+    ids: Vec<String>,
+) -> Result<()> {
+    state.write(move |db| {
+        let service = ReminderService::new(db);
+        service.acknowledge_reminders(&ids)
+    }).await
+}
@@ -1,15 +1,19 @@
 use tauri::State;
-use crate::services::database::DbState;
-use crate::services::search::{SearchService, SearchResult};
-use crate::errors::{Result, AppError};
+use mdnote_core::services::database::DbState;
+use crate::db::DbStateExt;
+use mdnote_core::models::property::PropertyQuery;
+use mdnote_core::services::search::{SearchService, SearchResult};
+use mdnote_core::errors::Result;
 
 #[tauri::command]
 pub async fn search_notes(
     state: State<'_, DbState>,
     query: String,
     limit: Option<i32>,
+    properties: Option<PropertyQuery>,
 ) -> Result<Vec<SearchResult>> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = SearchService::new(&db);
-    service.search_notes(query, limit.unwrap_or(20))
+    state.read(move |db| {
+        let service = SearchService::new(db);
+        service.search_notes_with_properties(query, &properties.unwrap_or_default(), limit.unwrap_or(20))
+    }).await
 }
@@ -1,24 +1,45 @@
 use tauri::State;
-use crate::models::settings::Settings;
-use crate::services::database::DbState;
-use crate::services::settings::SettingsService;
-use crate::errors::{Result, AppError};
+use mdnote_core::models::settings::Settings;
+use mdnote_core::services::database::DbState;
+use crate::db::DbStateExt;
+use mdnote_core::services::settings::SettingsService;
+use mdnote_core::errors::Result;
+use crate::api::ApiState;
 
 #[tauri::command]
 pub async fn get_settings(
     state: State<'_, DbState>,
 ) -> Result<Settings> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = SettingsService::new(&db);
-    service.get_settings()
+    state.read(move |db| {
+        let service = SettingsService::new(db);
+        service.get_settings()
+    }).await
 }
 
 #[tauri::command]
 pub async fn update_settings(
     state: State<'_, DbState>,
+    api: State<'_, ApiState>,
     settings: Settings,
 ) -> Result<Settings> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = SettingsService::new(&db);
-    service.update_settings(settings)
+    let settings = state.write(move |db| {
+        let service = SettingsService::new(db);
+        let mut settings = service.update_settings(settings)?;
+        if settings.api_enabled {
+            settings.api_token = Some(service.ensure_api_token()?);
+        }
+        Ok(settings)
+    }).await?;
+    api.apply(&state, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn regenerate_api_token(
+    state: State<'_, DbState>,
+) -> Result<String> {
+    state.write(move |db| {
+        let service = SettingsService::new(db);
+        service.regenerate_api_token()
+    }).await
 }
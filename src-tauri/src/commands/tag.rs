@@ -1,8 +1,10 @@
 use tauri::State;
-use crate::models::tag::Tag;
-use crate::services::database::DbState;
-use crate::services::tag::{TagService, TagWithCount};
-use crate::errors::{Result, AppError};
+use mdnote_core::models::tag::Tag;
+use mdnote_core::services::database::DbState;
+use crate::db::DbStateExt;
+use mdnote_core::services::suggestion::{SuggestionService, TagCooccurrence, TagSuggestion};
+use mdnote_core::services::tag::{list_palette, PaletteColor, TagService, TagTreeNode, TagWithCount};
+use mdnote_core::errors::Result;
 
 #[tauri::command]
 pub async fn create_tag(
@@ -10,18 +12,30 @@ pub async fn create_tag(
     name: String,
     color: Option<String>,
 ) -> Result<Tag> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = TagService::new(&db);
-    service.create_tag(name, color)
+    state.action("Create tag", move |db| {
+        let service = TagService::new(db);
+        service.create_tag(name, color)
+    }).await
 }
 
 #[tauri::command]
 pub async fn list_tags(
     state: State<'_, DbState>,
 ) -> Result<Vec<TagWithCount>> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = TagService::new(&db);
-    service.list_tags()
+    state.read(move |db| {
+        let service = TagService::new(db);
+        service.list_tags()
+    }).await
+}
+
+#[tauri::command]
+pub async fn get_tag_tree(
+    state: State<'_, DbState>,
+) -> Result<Vec<TagTreeNode>> {
+    state.read(move |db| {
+        let service = TagService::new(db);
+        service.get_tag_tree()
+    }).await
 }
 
 #[tauri::command]
@@ -30,9 +44,10 @@ pub async fn add_tag_to_note(
     note_id: String,
     tag_id: String,
 ) -> Result<()> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = TagService::new(&db);
-    service.add_tag_to_note(note_id, tag_id)
+    state.action("Tag note", move |db| {
+        let service = TagService::new(db);
+        service.add_tag_to_note(note_id, tag_id)
+    }).await
 }
 
 #[tauri::command]
@@ -41,9 +56,10 @@ pub async fn remove_tag_from_note(
     note_id: String,
     tag_id: String,
 ) -> Result<()> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = TagService::new(&db);
-    service.remove_tag_from_note(note_id, tag_id)
+    state.action("Untag note", move |db| {
+        let service = TagService::new(db);
+        service.remove_tag_from_note(note_id, tag_id)
+    }).await
 }
 
 #[tauri::command]
@@ -51,9 +67,54 @@ pub async fn get_note_tags(
     state: State<'_, DbState>,
     note_id: String,
 ) -> Result<Vec<Tag>> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = TagService::new(&db);
-    service.get_note_tags(note_id)
+    state.read(move |db| {
+        let service = TagService::new(db);
+        service.get_note_tags(note_id)
+    }).await
+}
+
+#[tauri::command]
+pub async fn update_tag(
+    state: State<'_, DbState>,
+    tag_id: String,
+    name: Option<String>,
+    color: Option<String>,
+    icon: Option<Option<String>>,
+    description: Option<Option<String>>,
+) -> Result<Tag> {
+    state.action("Edit tag", move |db| {
+        let service = TagService::new(db);
+        service.update_tag(tag_id, name, color, icon, description)
+    }).await
+}
+
+#[tauri::command]
+pub async fn list_tag_palette() -> Result<Vec<PaletteColor>> {
+    Ok(list_palette())
+}
+
+#[tauri::command]
+pub async fn rename_tag(
+    state: State<'_, DbState>,
+    tag_id: String,
+    name: String,
+) -> Result<Tag> {
+    state.action("Rename tag", move |db| {
+        let service = TagService::new(db);
+        service.rename_tag(tag_id, name)
+    }).await
+}
+
+#[tauri::command]
+pub async fn merge_tags(
+    state: State<'_, DbState>,
+    source_ids: Vec<String>,
+    target_id: String,
+) -> Result<Tag> {
+    state.action("Merge tags", move |db| {
+        let service = TagService::new(db);
+        service.merge_tags(source_ids, target_id)
+    }).await
 }
 
 #[tauri::command]
@@ -61,7 +122,32 @@ pub async fn delete_tag(
     state: State<'_, DbState>,
     tag_id: String,
 ) -> Result<()> {
-    let db = state.0.lock().map_err(|e| AppError::LockError(e.to_string()))?;
-    let service = TagService::new(&db);
-    service.delete_tag(tag_id)
+    state.action("Delete tag", move |db| {
+        let service = TagService::new(db);
+        service.delete_tag(tag_id)
+    }).await
+}
+
+#[tauri::command]
+pub async fn suggest_tags(
+    state: State<'_, DbState>,
+    note_id: String,
+    limit: Option<i32>,
+) -> Result<Vec<TagSuggestion>> {
+    state.read(move |db| {
+        let service = SuggestionService::new(db);
+        service.suggest_tags(note_id, limit.unwrap_or(10))
+    }).await
+}
+
+#[tauri::command]
+pub async fn get_tag_cooccurrence(
+    state: State<'_, DbState>,
+    min_shared: Option<i64>,
+    limit: Option<i32>,
+) -> Result<Vec<TagCooccurrence>> {
+    state.read(move |db| {
+        let service = SuggestionService::new(db);
+        service.get_tag_cooccurrence(min_shared.unwrap_or(1), limit.unwrap_or(50))
+    }).await
 }
@@ -0,0 +1,29 @@
+use tauri::State;
+use mdnote_core::services::database::DbState;
+use crate::db::DbStateExt;
+use mdnote_core::models::task::{AgendaGroup, Task, TaskFilter};
+use mdnote_core::services::task::TaskService;
+use mdnote_core::errors::Result;
+
+#[tauri::command]
+pub async fn list_tasks(
+    state: State<'_, DbState>,
+    filter: Option<TaskFilter>,
+) -> Result<Vec<AgendaGroup>> {
+    state.read(move |db| {
+        let service = TaskService::new(db);
+        service.list_tasks(filter.unwrap_or_default())
+    }).await
+}
+
+#[tauri::command]
+pub async fn toggle_task(
+    state: State<'_, DbState>,
+    note_id: String,
+    line: usize,
+) -> Result<Task> {
+    state.action("Toggle task", move |db| {
+        let service = TaskService::new(db);
+        service.toggle_task(note_id, line)
+    }).await
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use tauri::State;
+use mdnote_core::models::template::{Template, TemplateDraft};
+use mdnote_core::services::database::DbState;
+use crate::db::DbStateExt;
+use mdnote_core::services::template::{RenderedTemplate, TemplateNote, TemplateService};
+use mdnote_core::errors::Result;
+
+#[tauri::command]
+pub async fn list_templates(
+    state: State<'_, DbState>,
+) -> Result<Vec<Template>> {
+    state.read(move |db| {
+        let service = TemplateService::new(db);
+        service.list_templates()
+    }).await
+}
+
+#[tauri::command]
+pub async fn create_template(
+    state: State<'_, DbState>,
+    template: TemplateDraft,
+) -> Result<Template> {
+    state.write(move |db| {
+        let service = TemplateService::new(db);
+        service.create_template(template)
+    }).await
+}
+
+#[tauri::command]
+pub async fn update_template(
+    state: State<'_, DbState>,
+    id: String,
+    template: TemplateDraft,
+) -> Result<Template> {
+    state.write(move |db| {
+        let service = TemplateService::new(db);
+        service.update_template(id, template)
+    }).await
+}
+
+#[tauri::command]
+pub async fn delete_template(
+    state: State<'_, DbState>,
+    id: String,
+) -> Result<()> {
+    state.write(move |db| {
+        let service = TemplateService::new(db);
+        service.delete_template(id)
+    }).await
+}
+
+#[tauri::command]
+pub async fn preview_template(
+    state: State<'_, DbState>,
+    template_id: String,
+    vars: Option<HashMap<String, String>>,
+) -> Result<RenderedTemplate> {
+    state.read(move |db| {
+        let service = TemplateService::new(db);
+        service.preview_template(template_id, &vars.unwrap_or_default())
+    }).await
+}
+
+#[tauri::command]
+pub async fn create_note_from_template(
+    state: State<'_, DbState>,
+    template_id: String,
+    folder_id: Option<String>,
+    vars: Option<HashMap<String, String>>,
+) -> Result<TemplateNote> {
+    state.action("Create note", move |db| {
+        let service = TemplateService::new(db);
+        service.create_note_from_template(template_id, folder_id, &vars.unwrap_or_default())
+    }).await
+}
@@ -0,0 +1,36 @@
+use tauri::State;
+use mdnote_core::services::database::DbState;
+use crate::db::DbStateExt;
+use mdnote_core::services::undo::{UndoAction, UndoService};
+use mdnote_core::errors::Result;
+
+#[tauri::command]
+pub async fn undo(
+    state: State<'_, DbState>,
+) -> Result<Option<UndoAction>> {
+    state.write(move |db| {
+        let service = UndoService::new(db);
+        service.undo()
+    }).await
+}
+
+#[tauri::command]
+pub async fn redo(
+    state: State<'_, DbState>,
+) -> Result<Option<UndoAction>> {
+    state.write(move |db| {
+        let service = UndoService::new(db);
+        service.redo()
+    }).await
+}
+
+#[tauri::command]
+pub async fn list_undo_history(
+    state: State<'_, DbState>,
+    limit: Option<i64>,
+) -> Result<Vec<UndoAction>> {
+    state.read(move |db| {
+        let service = UndoService::new(db);
+        service.list_history(limit.unwrap_or(50))
+    }).await
+}
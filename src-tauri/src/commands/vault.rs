@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+use tauri::State;
+use mdnote_core::models::vault::Vault;
+use mdnote_core::services::database::{DbPool, DbState, DEFAULT_READER_COUNT};
+use mdnote_core::services::encryption::{is_encrypted, recover_reencryption, NoteKeyring};
+use mdnote_core::services::vault::VaultService;
+use mdnote_core::errors::{Result, AppError};
+use serde::Serialize;
+use crate::api::ApiState;
+
+#[derive(Serialize)]
+pub struct ListVaultsResponse {
+    pub vaults: Vec<Vault>,
+    pub current_vault_id: Option<String>,
+}
+
+/// Opens the vault's database on a blocking thread (it may run migrations) and makes it current.
+/// An encrypted vault becomes current but stays locked until `unlock_vault`.
+pub async fn open_vault_pool(state: &DbState, vaults: &VaultService, vault: Vault) -> Result<Vault> {
+    let dir = PathBuf::from(&vault.path);
+    recover_reencryption(&dir)?;
+    if is_encrypted(&dir) {
+        state.open_locked(vault.id.clone())?;
+        return vaults.mark_opened(&vault.id);
+    }
+    let pool = tauri::async_runtime::spawn_blocking(move || DbPool::new(dir, DEFAULT_READER_COUNT))
+        .await
+        .map_err(|e| AppError::lock(e.to_string()))??;
+
+    state.open(vault.id.clone(), pool)?;
+    vaults.mark_opened(&vault.id)
+}
+
+#[tauri::command]
+pub async fn list_vaults(
+    state: State<'_, DbState>,
+    vaults: State<'_, VaultService>,
+) -> Result<ListVaultsResponse> {
+    Ok(ListVaultsResponse {
+        vaults: vaults.list_vaults()?,
+        current_vault_id: state.current_vault_id()?,
+    })
+}
+
+#[tauri::command]
+pub async fn get_current_vault(
+    state: State<'_, DbState>,
+    vaults: State<'_, VaultService>,
+) -> Result<Option<Vault>> {
+    match state.current_vault_id()? {
+        Some(id) => Ok(Some(vaults.get_vault(&id)?)),
+        None => Ok(None),
+    }
+}
+
+#[tauri::command]
+pub async fn create_vault(
+    vaults: State<'_, VaultService>,
+    name: String,
+    path: String,
+) -> Result<Vault> {
+    vaults.create_vault(name, PathBuf::from(path))
+}
+
+#[tauri::command]
+pub async fn open_vault(
+    state: State<'_, DbState>,
+    vaults: State<'_, VaultService>,
+    keyring: State<'_, NoteKeyring>,
+    api: State<'_, ApiState>,
+    id: String,
+) -> Result<Vault> {
+    let vault = vaults.get_vault(&id)?;
+    keyring.clear()?;
+    let vault = open_vault_pool(&state, &vaults, vault).await?;
+    if let Err(e) = api.sync(&state).await {
+        tracing::warn!("Local API not started for vault {}: {}", vault.id, e);
+    }
+    Ok(vault)
+}
+
+#[tauri::command]
+pub async fn close_vault(
+    state: State<'_, DbState>,
+    keyring: State<'_, NoteKeyring>,
+    api: State<'_, ApiState>,
+) -> Result<Option<String>> {
+    keyring.clear()?;
+    // The server answers with the closed vault's token, so it goes down with it
+    api.stop()?;
+    state.close()
+}
+
+#[tauri::command]
+pub async fn rename_vault(
+    vaults: State<'_, VaultService>,
+    id: String,
+    name: String,
+) -> Result<Vault> {
+    vaults.rename_vault(&id, name)
+}
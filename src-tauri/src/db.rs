@@ -0,0 +1,58 @@
+use mdnote_core::errors::{AppError, Result};
+use mdnote_core::services::database::{DatabaseService, DbState};
+use mdnote_core::services::undo::UndoService;
+
+/// Runs a command's database work on a blocking thread, so a slow query never
+/// stalls the async runtime.
+pub(crate) trait DbStateExt {
+    async fn read<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&DatabaseService) -> Result<T> + Send + 'static,
+        T: Send + 'static;
+
+    async fn write<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut DatabaseService) -> Result<T> + Send + 'static,
+        T: Send + 'static;
+
+    /// Like `write`, recording everything `f` changes as one undoable action called `label`.
+    async fn action<T, F>(&self, label: &'static str, f: F) -> Result<T>
+    where
+        F: FnOnce(&DatabaseService) -> Result<T> + Send + 'static,
+        T: Send + 'static;
+}
+
+impl DbStateExt for DbState {
+    async fn read<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&DatabaseService) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.pool()?;
+        tauri::async_runtime::spawn_blocking(move || pool.with_reader(f))
+            .await
+            .map_err(|e| AppError::lock(e.to_string()))?
+    }
+
+    async fn write<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut DatabaseService) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.pool()?;
+        tauri::async_runtime::spawn_blocking(move || pool.with_writer(f))
+            .await
+            .map_err(|e| AppError::lock(e.to_string()))?
+    }
+
+    async fn action<T, F>(&self, label: &'static str, f: F) -> Result<T>
+    where
+        F: FnOnce(&DatabaseService) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.write(move |db| {
+            let db: &DatabaseService = db;
+            UndoService::new(db).record(label, || f(db))
+        }).await
+    }
+}
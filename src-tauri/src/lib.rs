@@ -1,18 +1,35 @@
-mod services;
-mod models;
+mod api;
 mod commands;
-mod errors;
+mod db;
 
-use services::database::{DatabaseService, DbState};
-use tauri::Manager;
+use api::ApiState;
+use mdnote_core::services::database::{DbPool, DbState, DEFAULT_READER_COUNT};
+use mdnote_core::services::backup::spawn_backup_scheduler;
+use mdnote_core::services::encryption::{is_encrypted, recover_reencryption, NoteKeyring};
+use mdnote_core::services::events::ChangeEvent;
+use mdnote_core::models::reminder::DueReminder;
+use mdnote_core::services::reminder::{spawn_reminder_scheduler, ReminderScheduler, SystemClock};
+use mdnote_core::services::vault::VaultService;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{Emitter, Manager};
 
 // Re-export commands for visibility
-use commands::note::{create_note, get_note, update_note, delete_note, list_notes};
+use commands::note::{create_note, get_note, update_note, set_note_property, merge_note_content, delete_note, list_notes, encrypt_note, decrypt_note_for_session, lock_all_notes};
 use commands::search::{search_notes};
 use commands::folder::{create_folder, list_folders, update_folder, delete_folder};
-use commands::tag::{create_tag, list_tags, add_tag_to_note, remove_tag_from_note, get_note_tags, delete_tag};
-use commands::settings::{get_settings, update_settings};
+use commands::tag::{create_tag, list_tags, get_tag_tree, add_tag_to_note, remove_tag_from_note, get_note_tags, update_tag, list_tag_palette, rename_tag, merge_tags, delete_tag, suggest_tags, get_tag_cooccurrence};
+use commands::settings::{get_settings, update_settings, regenerate_api_token};
 use commands::backlink::{add_backlink, remove_backlink, get_backlinks, get_outgoing_links, sync_backlinks};
+use commands::backup::{create_backup, list_backups, restore_backup};
+use commands::maintenance::{integrity_check, foreign_key_check, vacuum, incremental_vacuum, analyze, wal_checkpoint, get_db_stats};
+use commands::vault::{list_vaults, get_current_vault, create_vault, open_vault, close_vault, rename_vault};
+use commands::encryption::{get_encryption_status, enable_encryption, change_passphrase, unlock_vault, lock_vault};
+use commands::undo::{undo, redo, list_undo_history};
+use commands::template::{list_templates, create_template, update_template, delete_template, preview_template, create_note_from_template};
+use commands::periodic::{open_daily_note, open_weekly_note, open_monthly_note, list_periodic_notes};
+use commands::task::{list_tasks, toggle_task};
+use commands::reminder::{create_reminder, snooze_reminder, delete_reminder, list_upcoming_reminders, list_missed_reminders, acknowledge_reminders};
 
 #[cfg(target_os = "macos")]
 use window_vibrancy::{apply_vibrancy, NSVisualEffectMaterial};
@@ -24,10 +41,58 @@ pub fn run() {
         .setup(|app| {
             tracing_subscriber::fmt::init();
 
-            // Initialize database
+            // Initialize database: reopen the last vault, or the default one in the app data dir
             let app_data_dir = app.path().app_data_dir().expect("failed to get app data dir");
-            let database_service = DatabaseService::new(app_data_dir).expect("failed to init db");
-            app.manage(DbState(std::sync::Mutex::new(database_service)));
+            let vaults = VaultService::new(&app_data_dir);
+            let state = DbState::new();
+            // Every committed change reaches all windows, whichever window or API client made it
+            let handle = app.handle().clone();
+            state.changes().subscribe(Arc::new(move |event: &ChangeEvent| {
+                if let Err(e) = handle.emit(event.name(), event) {
+                    tracing::warn!("Failed to emit {}: {}", event.name(), e);
+                }
+            }));
+            let opened = vaults.startup_vault(&app_data_dir).and_then(|vault| {
+                let dir = PathBuf::from(&vault.path);
+                recover_reencryption(&dir)?;
+                // Encrypted vaults start locked; the UI asks for the passphrase
+                if is_encrypted(&dir) {
+                    state.open_locked(vault.id.clone())?;
+                } else {
+                    match DbPool::new(dir.clone(), DEFAULT_READER_COUNT) {
+                        Ok(pool) => state.open(vault.id.clone(), pool)?,
+                        // Keep running: commands report the damage and backups can be restored
+                        Err(e) => {
+                            tracing::error!("Failed to open database of vault {}: {}", vault.id, e.report());
+                            state.open_failed(vault.id.clone(), dir, &e)?;
+                        }
+                    }
+                }
+                vaults.mark_opened(&vault.id)
+            });
+            if let Err(e) = opened {
+                tracing::error!("Failed to open a vault: {}", e.report());
+            }
+            spawn_backup_scheduler(state.clone());
+            // Fires right away to catch up on reminders that came due while the app was closed.
+            // The window isn't listening yet; it fetches those with `list_missed_reminders`
+            let handle = app.handle().clone();
+            let notify = move |reminder: &DueReminder| {
+                if let Err(e) = handle.emit("reminder:due", reminder) {
+                    tracing::warn!("Failed to emit reminder:due: {}", e);
+                }
+            };
+            spawn_reminder_scheduler(state.clone(), ReminderScheduler::new(Arc::new(SystemClock), Arc::new(notify)));
+
+            // The local HTTP API is opt-in per vault; failing to start it must not stop the app
+            let api = ApiState::default();
+            if let Err(e) = tauri::async_runtime::block_on(api.sync(&state)) {
+                tracing::warn!("Local API not started: {}", e);
+            }
+            app.manage(api);
+            app.manage(state);
+            app.manage(vaults);
+            app.manage(NoteKeyring::new());
 
             // Apply macOS vibrancy effect
             #[cfg(target_os = "macos")]
@@ -45,8 +110,13 @@ pub fn run() {
             create_note,
             get_note,
             update_note,
+            set_note_property,
+            merge_note_content,
             delete_note,
             list_notes,
+            encrypt_note,
+            decrypt_note_for_session,
+            lock_all_notes,
             search_notes,
             create_folder,
             list_folders,
@@ -54,17 +124,67 @@ pub fn run() {
             delete_folder,
             create_tag,
             list_tags,
+            get_tag_tree,
             add_tag_to_note,
             remove_tag_from_note,
             get_note_tags,
+            update_tag,
+            list_tag_palette,
+            rename_tag,
+            merge_tags,
             delete_tag,
+            suggest_tags,
+            get_tag_cooccurrence,
             get_settings,
             update_settings,
+            regenerate_api_token,
             add_backlink,
             remove_backlink,
             get_backlinks,
             get_outgoing_links,
             sync_backlinks,
+            create_backup,
+            list_backups,
+            restore_backup,
+            integrity_check,
+            foreign_key_check,
+            vacuum,
+            incremental_vacuum,
+            analyze,
+            wal_checkpoint,
+            get_db_stats,
+            list_vaults,
+            get_current_vault,
+            create_vault,
+            open_vault,
+            close_vault,
+            rename_vault,
+            get_encryption_status,
+            enable_encryption,
+            change_passphrase,
+            unlock_vault,
+            lock_vault,
+            undo,
+            redo,
+            list_undo_history,
+            list_templates,
+            create_template,
+            update_template,
+            delete_template,
+            preview_template,
+            create_note_from_template,
+            open_daily_note,
+            open_weekly_note,
+            open_monthly_note,
+            list_periodic_notes,
+            list_tasks,
+            toggle_task,
+            create_reminder,
+            snooze_reminder,
+            delete_reminder,
+            list_upcoming_reminders,
+            list_missed_reminders,
+            acknowledge_reminders,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
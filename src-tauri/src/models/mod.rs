@@ -1,4 +0,0 @@
-pub mod note;
-pub mod folder;
-pub mod tag;
-pub mod settings;
@@ -1,7 +0,0 @@
-pub mod database;
-pub mod note;
-pub mod folder;
-pub mod search;
-pub mod tag;
-pub mod settings;
-pub mod backlink;
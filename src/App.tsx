@@ -1,19 +1,25 @@
-import React, { useEffect, useState, useCallback } from 'react';
+import React, { useEffect, useState, useCallback, useRef } from 'react';
 import { useNotes } from './hooks/useNotes';
 import { useFolders } from './hooks/useFolders';
 import { useTags } from './hooks/useTags';
 import { useSettings } from './hooks/useSettings';
-import { NoteSummary, Note, FolderListItem, TagWithCount } from './types';
+import { useChangeEvents } from './hooks/useChangeEvents';
+import { useUndo } from './hooks/useUndo';
+import { usePeriodicNotes } from './hooks/usePeriodicNotes';
+import { useReminders } from './hooks/useReminders';
+import { NoteSummary, Note, FolderListItem, TagWithCount, NoteConflictError } from './types';
 import Sidebar from './components/Sidebar';
 import Editor from './components/Editor';
 import CommandPalette from './components/CommandPalette';
 import SettingsModal from './components/SettingsModal';
 
 const App: React.FC = () => {
-  const { listNotes, getNote, createNote, updateNote } = useNotes();
+  const { listNotes, getNote, createNote, updateNote, mergeNoteContent } = useNotes();
   const { listFolders, createFolder, updateFolder, deleteFolder } = useFolders();
   const { listTags, createTag, deleteTag } = useTags();
   const { settings, updateSettings } = useSettings();
+  const { undo, redo } = useUndo();
+  const { openPeriodicNote } = usePeriodicNotes();
 
   const [notes, setNotes] = useState<NoteSummary[]>([]);
   const [folders, setFolders] = useState<FolderListItem[]>([]);
@@ -26,6 +32,16 @@ const App: React.FC = () => {
   const [showSettings, setShowSettings] = useState(false);
   const [isLoadingSidebar, setIsLoadingSidebar] = useState(true);
   const [isLoadingNote, setIsLoadingNote] = useState(false);
+  // Bumped to reload the editor when a save merged in someone else's edits
+  const [editorRevision, setEditorRevision] = useState(0);
+  // The last stored version of each note the editor has seen, which its next save is based on
+  const savedVersions = useRef(new Map<string, Note>());
+  const saveQueue = useRef<Promise<void>>(Promise.resolve());
+
+  const showNote = useCallback((note: Note) => {
+    savedVersions.current.set(note.id, note);
+    setSelectedNote(note);
+  }, []);
 
   const fetchFolders = useCallback(async () => {
     try {
@@ -67,6 +83,23 @@ const App: React.FC = () => {
     fetchNotes(selectedFolderId, selectedTagId);
   }, [selectedFolderId, selectedTagId, fetchNotes]);
 
+  // Keep the sidebar in step with changes made in other windows or through the API
+  const refreshNotes = useCallback(() => {
+    fetchNotes(selectedFolderId, selectedTagId);
+  }, [fetchNotes, selectedFolderId, selectedTagId]);
+
+  // Clicking a reminder's notification opens its note
+  useReminders((reminder) => handleSelectNote(reminder.note_id));
+
+  useChangeEvents({
+    'note:created': refreshNotes,
+    'note:updated': refreshNotes,
+    'note:deleted': refreshNotes,
+    'folder:changed': () => { fetchFolders(); refreshNotes(); },
+    'folder:moved': () => { fetchFolders(); },
+    'tag:changed': () => { fetchTags(); },
+  });
+
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
       // Cmd/Ctrl + K: Open search
@@ -84,16 +117,40 @@ const App: React.FC = () => {
         e.preventDefault();
         handleCreateNote();
       }
+      // Cmd/Ctrl + Shift + D: Open today's daily note
+      if ((e.metaKey || e.ctrlKey) && e.shiftKey && e.key.toLowerCase() === 'd') {
+        e.preventDefault();
+        handleOpenDailyNote();
+      }
+      // Cmd/Ctrl + Z / Shift + Z: Undo or redo the last data operation, unless a text field has focus
+      const target = e.target as HTMLElement | null;
+      const editingText = target?.isContentEditable || target?.tagName === 'INPUT' || target?.tagName === 'TEXTAREA';
+      if ((e.metaKey || e.ctrlKey) && e.key.toLowerCase() === 'z' && !editingText) {
+        e.preventDefault();
+        handleUndo(e.shiftKey);
+      }
     };
     window.addEventListener('keydown', handleKeyDown);
     return () => window.removeEventListener('keydown', handleKeyDown);
-  }, [selectedFolderId, selectedTagId]);
+  }, [selectedFolderId, selectedTagId, selectedNote?.id]);
+
+  const handleUndo = async (isRedo: boolean) => {
+    try {
+      const action = isRedo ? await redo() : await undo();
+      // The open note may be one the action changed
+      if (action && selectedNote) {
+        handleSelectNote(selectedNote.id);
+      }
+    } catch (e) {
+      console.error(e);
+    }
+  };
 
   const handleSelectNote = async (id: string) => {
     try {
       setIsLoadingNote(true);
       const note = await getNote(id);
-      setSelectedNote(note);
+      showNote(note);
     } catch (e) {
       console.error(e);
     } finally {
@@ -104,7 +161,7 @@ const App: React.FC = () => {
   const handleCreateNote = async () => {
     try {
       const note = await createNote('New Note', '', selectedFolderId);
-      setSelectedNote(note);
+      showNote(note);
       fetchNotes(selectedFolderId, selectedTagId);
       fetchFolders();
       fetchTags();
@@ -113,6 +170,20 @@ const App: React.FC = () => {
     }
   };
 
+  const handleOpenDailyNote = async () => {
+    try {
+      const { note, created } = await openPeriodicNote('daily');
+      showNote(note);
+      if (created) {
+        fetchNotes(selectedFolderId, selectedTagId);
+        fetchFolders();
+        fetchTags();
+      }
+    } catch (e) {
+      console.error(e);
+    }
+  };
+
   const handleCreateFolder = async (name: string) => {
     try {
       await createFolder(name);
@@ -172,11 +243,30 @@ const App: React.FC = () => {
     }
   };
 
-  const handleSaveNote = async (content: string, title?: string) => {
+  // Saves run one at a time, so each is based on the version the previous one stored
+  const handleSaveNote = (content: string, title?: string) => {
     if (!selectedNote) return;
+    const id = selectedNote.id;
+    saveQueue.current = saveQueue.current.then(() => saveNote(id, content, title));
+  };
+
+  const saveNote = async (id: string, content: string, title?: string) => {
+    const base = savedVersions.current.get(id);
+    if (!base) return;
     try {
-      const updated = await updateNote(selectedNote.id, { content, title });
-      setSelectedNote(updated);
+      let updated: Note;
+      try {
+        updated = await updateNote(id, { content, title, expectedUpdatedAt: base.updated_at });
+      } catch (e: any) {
+        if (e?.code !== 'CONFLICT' || !e.current) throw e;
+        // Saved elsewhere in the meantime: keep their edits and ours, marking any overlap
+        const current = (e as NoteConflictError).current;
+        const { merged } = await mergeNoteContent(base.content, content, current.content);
+        updated = await updateNote(id, { content: merged, title, expectedUpdatedAt: current.updated_at });
+        setEditorRevision((r) => r + 1);
+      }
+      savedVersions.current.set(id, updated);
+      setSelectedNote((selected) => (selected?.id === id ? updated : selected));
       fetchNotes(selectedFolderId, selectedTagId);
     } catch (e) {
       console.error(e);
@@ -208,7 +298,7 @@ const App: React.FC = () => {
       <main className="flex-1 overflow-hidden relative border-l border-border/10">
         <div className="absolute inset-0 bg-background/30 backdrop-blur-sm">
           {selectedNote ? (
-            <Editor note={selectedNote} onSave={handleSaveNote} onSelectNote={handleSelectNote} onTagsChanged={fetchTags} loading={isLoadingNote} />
+            <Editor key={`${selectedNote.id}:${editorRevision}`} note={selectedNote} onSave={handleSaveNote} onSelectNote={handleSelectNote} onTagsChanged={fetchTags} loading={isLoadingNote} />
           ) : (
             <div className="flex h-full items-center justify-center text-muted-foreground gap-6 flex-col animate-in fade-in zoom-in duration-500">
               <div className="w-20 h-20 rounded-3xl bg-primary/10 flex items-center justify-center border border-primary/20 shadow-2xl shadow-primary/5">
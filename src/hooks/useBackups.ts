@@ -0,0 +1,58 @@
+import { useState, useCallback } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { BackupInfo } from '../types';
+
+export const useBackups = () => {
+    const [loading, setLoading] = useState(false);
+    const [error, setError] = useState<string | null>(null);
+
+    const createBackup = useCallback(async (path: string | null = null) => {
+        setLoading(true);
+        setError(null);
+        try {
+            const backup = await invoke<BackupInfo>('create_backup', { path });
+            return backup;
+        } catch (e: any) {
+            setError(e.message || 'Failed to create backup');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const listBackups = useCallback(async () => {
+        setLoading(true);
+        setError(null);
+        try {
+            const backups = await invoke<BackupInfo[]>('list_backups');
+            return backups;
+        } catch (e: any) {
+            setError(e.message || 'Failed to list backups');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const restoreBackup = useCallback(async (id: string) => {
+        setLoading(true);
+        setError(null);
+        try {
+            const backup = await invoke<BackupInfo>('restore_backup', { id });
+            return backup;
+        } catch (e: any) {
+            setError(e.message || 'Failed to restore backup');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    return {
+        loading,
+        error,
+        createBackup,
+        listBackups,
+        restoreBackup,
+    };
+};
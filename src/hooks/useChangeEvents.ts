@@ -0,0 +1,39 @@
+import { useEffect, useRef } from 'react';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
+import { ChangeEventName, ChangeEventPayloads } from '../types';
+
+type ChangeHandlers = {
+    [K in ChangeEventName]?: (payload: ChangeEventPayloads[K]) => void;
+};
+
+// Subscribes to the change events the backend emits after each committed write,
+// whether this window, another window or an API client made it
+export const useChangeEvents = (handlers: ChangeHandlers) => {
+    const handlersRef = useRef(handlers);
+    handlersRef.current = handlers;
+
+    const names = (Object.keys(handlers) as ChangeEventName[]).sort().join(',');
+
+    useEffect(() => {
+        let cancelled = false;
+        const unlisteners: UnlistenFn[] = [];
+
+        for (const name of names.split(',').filter(Boolean) as ChangeEventName[]) {
+            listen<ChangeEventPayloads[typeof name]>(name, (event) => {
+                const handler = handlersRef.current[name] as ((payload: unknown) => void) | undefined;
+                handler?.(event.payload);
+            }).then((unlisten) => {
+                if (cancelled) {
+                    unlisten();
+                } else {
+                    unlisteners.push(unlisten);
+                }
+            });
+        }
+
+        return () => {
+            cancelled = true;
+            unlisteners.forEach((unlisten) => unlisten());
+        };
+    }, [names]);
+};
@@ -0,0 +1,45 @@
+import { useState, useCallback } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { EnableEncryptionResponse, EncryptionStatus } from '../types';
+
+export const useEncryption = () => {
+    const [loading, setLoading] = useState(false);
+    const [error, setError] = useState<string | null>(null);
+
+    const run = useCallback(async <T,>(command: string, args: Record<string, unknown>, failure: string) => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<T>(command, args);
+        } catch (e: any) {
+            setError(e.message || failure);
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const getEncryptionStatus = useCallback(() =>
+        run<EncryptionStatus>('get_encryption_status', {}, 'Failed to get encryption status'), [run]);
+
+    const enableEncryption = useCallback((passphrase: string) =>
+        run<EnableEncryptionResponse>('enable_encryption', { passphrase }, 'Failed to enable encryption'), [run]);
+
+    const changePassphrase = useCallback((currentPassphrase: string, newPassphrase: string) =>
+        run<void>('change_passphrase', { currentPassphrase, newPassphrase }, 'Failed to change passphrase'), [run]);
+
+    const unlockVault = useCallback((passphrase: string) =>
+        run<EncryptionStatus>('unlock_vault', { passphrase }, 'Failed to unlock vault'), [run]);
+
+    const lockVault = useCallback(() => run<EncryptionStatus>('lock_vault', {}, 'Failed to lock vault'), [run]);
+
+    return {
+        loading,
+        error,
+        getEncryptionStatus,
+        enableEncryption,
+        changePassphrase,
+        unlockVault,
+        lockVault,
+    };
+};
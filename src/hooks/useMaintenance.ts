@@ -0,0 +1,43 @@
+import { useState, useCallback } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { CheckpointResult, DbStats, IntegrityReport } from '../types';
+
+export const useMaintenance = () => {
+    const [loading, setLoading] = useState(false);
+    const [error, setError] = useState<string | null>(null);
+
+    const run = useCallback(async <T,>(command: string, args: Record<string, unknown>, failure: string) => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<T>(command, args);
+        } catch (e: any) {
+            setError(e.message || failure);
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const integrityCheck = useCallback((quick: boolean = false) =>
+        run<IntegrityReport>('integrity_check', { quick }, 'Integrity check failed'), [run]);
+
+    const vacuum = useCallback(() => run<void>('vacuum', {}, 'Failed to vacuum database'), [run]);
+
+    const analyze = useCallback(() => run<void>('analyze', {}, 'Failed to analyze database'), [run]);
+
+    const walCheckpoint = useCallback((truncate: boolean = false) =>
+        run<CheckpointResult>('wal_checkpoint', { truncate }, 'Failed to checkpoint database'), [run]);
+
+    const getDbStats = useCallback(() => run<DbStats>('get_db_stats', {}, 'Failed to load database statistics'), [run]);
+
+    return {
+        loading,
+        error,
+        integrityCheck,
+        vacuum,
+        analyze,
+        walCheckpoint,
+        getDbStats,
+    };
+};
@@ -1,6 +1,6 @@
 import { useState, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { Note, ListNotesResponse, SearchResult } from '../types';
+import { Note, ListNotesResponse, SearchResult, MergeResult, PropertyQuery, PropertyValue } from '../types';
 
 export const useNotes = () => {
     const [loading, setLoading] = useState(false);
@@ -34,7 +34,8 @@ export const useNotes = () => {
         }
     }, []);
 
-    const updateNote = useCallback(async (id: string, updates: { title?: string; content?: string; folderId?: string | null }) => {
+    // With expectedUpdatedAt the update is rejected with a Conflict error if the note changed since that version
+    const updateNote = useCallback(async (id: string, updates: { title?: string; content?: string; folderId?: string | null; expectedUpdatedAt?: number }) => {
         setLoading(true);
         setError(null);
         try {
@@ -42,7 +43,8 @@ export const useNotes = () => {
                 id,
                 title: updates.title,
                 content: updates.content,
-                folderId: updates.folderId !== undefined ? updates.folderId : undefined
+                folderId: updates.folderId !== undefined ? updates.folderId : undefined,
+                expectedUpdatedAt: updates.expectedUpdatedAt
             });
             return note;
         } catch (e: any) {
@@ -53,6 +55,24 @@ export const useNotes = () => {
         }
     }, []);
 
+    // Rewrites just that key of the note's front matter; a null value removes it
+    const setNoteProperty = useCallback(async (id: string, key: string, value: PropertyValue | null) => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<Note>('set_note_property', { id, key, value });
+        } catch (e: any) {
+            setError(e.message || 'Failed to set property');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const mergeNoteContent = useCallback(async (base: string, ours: string, theirs: string) => {
+        return invoke<MergeResult>('merge_note_content', { base, ours, theirs });
+    }, []);
+
     const deleteNote = useCallback(async (id: string) => {
         setLoading(true);
         setError(null);
@@ -67,11 +87,12 @@ export const useNotes = () => {
         }
     }, []);
 
-    const listNotes = useCallback(async (folderId: string | null = null, tagId: string | null = null, limit: number = 50, offset: number = 0) => {
+    // Property filters, like a tag, list notes from every folder unless folderId is given
+    const listNotes = useCallback(async (folderId: string | null = null, tagId: string | null = null, limit: number = 50, offset: number = 0, includeDescendants: boolean = false, properties: PropertyQuery | null = null) => {
         setLoading(true);
         setError(null);
         try {
-            const response = await invoke<ListNotesResponse>('list_notes', { folderId, tagId, limit, offset });
+            const response = await invoke<ListNotesResponse>('list_notes', { folderId, tagId, includeDescendants, limit, offset, properties });
             return response;
         } catch (e: any) {
             setError(e.message || 'Failed to list notes');
@@ -81,11 +102,11 @@ export const useNotes = () => {
         }
     }, []);
 
-    const searchNotes = useCallback(async (query: string, limit: number = 20) => {
+    const searchNotes = useCallback(async (query: string, limit: number = 20, properties: PropertyQuery | null = null) => {
         setLoading(true);
         setError(null);
         try {
-            const results = await invoke<SearchResult[]>('search_notes', { query, limit });
+            const results = await invoke<SearchResult[]>('search_notes', { query, limit, properties });
             return results;
         } catch (e: any) {
             setError(e.message || 'Search failed');
@@ -95,14 +116,49 @@ export const useNotes = () => {
         }
     }, []);
 
+    const encryptNote = useCallback(async (id: string, passphrase: string) => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<Note>('encrypt_note', { id, passphrase });
+        } catch (e: any) {
+            setError(e.message || 'Failed to encrypt note');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const decryptNoteForSession = useCallback(async (id: string, passphrase: string) => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<Note>('decrypt_note_for_session', { id, passphrase });
+        } catch (e: any) {
+            setError(e.message || 'Failed to unlock note');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const lockAllNotes = useCallback(async () => {
+        await invoke<void>('lock_all_notes');
+    }, []);
+
     return {
         loading,
         error,
         createNote,
         getNote,
         updateNote,
+        setNoteProperty,
+        mergeNoteContent,
         deleteNote,
         listNotes,
         searchNotes,
+        encryptNote,
+        decryptNoteForSession,
+        lockAllNotes,
     };
 };
@@ -0,0 +1,48 @@
+import { useState, useCallback } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Period, PeriodicCalendar, PeriodicNote } from '../types';
+
+const OPEN_COMMANDS: Record<Period, string> = {
+    daily: 'open_daily_note',
+    weekly: 'open_weekly_note',
+    monthly: 'open_monthly_note',
+};
+
+export const usePeriodicNotes = () => {
+    const [loading, setLoading] = useState(false);
+    const [error, setError] = useState<string | null>(null);
+
+    /** Opens, or creates, the note for the period containing `date` (`YYYY-MM-DD`, today by default). */
+    const openPeriodicNote = useCallback(async (period: Period, date: string | null = null) => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<PeriodicNote>(OPEN_COMMANDS[period], { date });
+        } catch (e: any) {
+            setError(e.message || `Failed to open ${period} note`);
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const listPeriodicNotes = useCallback(async (from: string, to: string) => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<PeriodicCalendar>('list_periodic_notes', { from, to });
+        } catch (e: any) {
+            setError(e.message || 'Failed to load the calendar');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    return {
+        loading,
+        error,
+        openPeriodicNote,
+        listPeriodicNotes,
+    };
+};
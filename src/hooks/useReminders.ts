@@ -0,0 +1,129 @@
+import { useState, useCallback, useEffect, useRef } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { DueReminder, Reminder, ReminderDraft } from '../types';
+import { useChangeEvents } from './useChangeEvents';
+
+const notify = (reminder: DueReminder, onOpen?: (reminder: DueReminder) => void) => {
+    if (typeof Notification === 'undefined' || Notification.permission !== 'granted') {
+        return;
+    }
+    const title = reminder.missed ? `Missed reminder: ${reminder.note_title}` : reminder.note_title;
+    const body = [reminder.task_text, reminder.message].filter(Boolean).join(' — ');
+    const notification = new Notification(title, { body, tag: reminder.id });
+    notification.onclick = () => onOpen?.(reminder);
+};
+
+const acknowledge = (ids: string[]) => {
+    if (ids.length > 0) {
+        invoke('acknowledge_reminders', { ids }).catch(() => {});
+    }
+};
+
+// Shows a notification for every reminder the backend scheduler fires, including ones
+// missed while the app was closed, and manages the reminder list. Reminders that fired
+// before the listener was up are fetched once it is, and each shown one is acknowledged
+// so it isn't shown again.
+export const useReminders = (onOpen?: (reminder: DueReminder) => void) => {
+    const [upcoming, setUpcoming] = useState<Reminder[]>([]);
+    const [error, setError] = useState<string | null>(null);
+    const onOpenRef = useRef(onOpen);
+    onOpenRef.current = onOpen;
+
+    const fetchUpcoming = useCallback(async (limit?: number) => {
+        setError(null);
+        try {
+            const result = await invoke<Reminder[]>('list_upcoming_reminders', { limit });
+            setUpcoming(result);
+            return result;
+        } catch (e: any) {
+            setError(e.message || 'Failed to load reminders');
+            throw e;
+        }
+    }, []);
+
+    // Reminders changed elsewhere, e.g. by an undo or another window, reload the list
+    useChangeEvents({
+        'reminder:changed': () => {
+            fetchUpcoming().catch(() => {});
+        },
+    });
+
+    useEffect(() => {
+        if (typeof Notification !== 'undefined' && Notification.permission === 'default') {
+            Notification.requestPermission();
+        }
+
+        let cancelled = false;
+        let unlisten: (() => void) | undefined;
+        listen<DueReminder>('reminder:due', (event) => {
+            notify(event.payload, onOpenRef.current);
+            setUpcoming(reminders => reminders.filter(r => r.id !== event.payload.id));
+            acknowledge([event.payload.id]);
+        }).then(async (fn) => {
+            if (cancelled) {
+                fn();
+                return;
+            }
+            unlisten = fn;
+            // Notifications share the reminder id as their tag, so one that also arrived
+            // as an event just now replaces itself instead of showing twice
+            const missed = await invoke<DueReminder[]>('list_missed_reminders').catch(() => []);
+            if (cancelled) {
+                return;
+            }
+            missed.forEach(reminder => notify(reminder, onOpenRef.current));
+            acknowledge(missed.map(r => r.id));
+        });
+
+        return () => {
+            cancelled = true;
+            unlisten?.();
+        };
+    }, []);
+
+    const createReminder = useCallback(async (reminder: ReminderDraft) => {
+        setError(null);
+        try {
+            const created = await invoke<Reminder>('create_reminder', { reminder });
+            setUpcoming(reminders => [...reminders, created].sort((a, b) => a.remind_at - b.remind_at));
+            return created;
+        } catch (e: any) {
+            setError(e.message || 'Failed to create reminder');
+            throw e;
+        }
+    }, []);
+
+    /** Fires the reminder again at `until` (epoch milliseconds). */
+    const snoozeReminder = useCallback(async (id: string, until: number) => {
+        setError(null);
+        try {
+            const snoozed = await invoke<Reminder>('snooze_reminder', { id, until });
+            setUpcoming(reminders => [...reminders.filter(r => r.id !== id), snoozed].sort((a, b) => a.remind_at - b.remind_at));
+            return snoozed;
+        } catch (e: any) {
+            setError(e.message || 'Failed to snooze reminder');
+            throw e;
+        }
+    }, []);
+
+    const deleteReminder = useCallback(async (id: string) => {
+        setError(null);
+        try {
+            await invoke('delete_reminder', { id });
+            setUpcoming(reminders => reminders.filter(r => r.id !== id));
+        } catch (e: any) {
+            setError(e.message || 'Failed to delete reminder');
+            throw e;
+        }
+    }, []);
+
+    return {
+        upcoming,
+        error,
+        fetchUpcoming,
+        createReminder,
+        snoozeReminder,
+        deleteReminder,
+    };
+};
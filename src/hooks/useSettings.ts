@@ -57,6 +57,18 @@ export const useSettings = () => {
         }
     }, [applyTheme]);
 
+    const regenerateApiToken = useCallback(async () => {
+        setError(null);
+        try {
+            const token = await invoke<string>('regenerate_api_token');
+            setSettings((prev) => (prev ? { ...prev, api_token: token } : prev));
+            return token;
+        } catch (e: any) {
+            setError(e.message || 'Failed to regenerate API token');
+            throw e;
+        }
+    }, []);
+
     useEffect(() => {
         fetchSettings();
     }, [fetchSettings]);
@@ -67,5 +79,6 @@ export const useSettings = () => {
         error,
         fetchSettings,
         updateSettings,
+        regenerateApiToken,
     };
 };
@@ -1,6 +1,6 @@
 import { useState, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { Tag, TagWithCount } from '../types';
+import { PaletteColor, Tag, TagCooccurrence, TagSuggestion, TagTreeNode, TagWithCount } from '../types';
 
 export const useTags = () => {
     const [loading, setLoading] = useState(false);
@@ -34,6 +34,20 @@ export const useTags = () => {
         }
     }, []);
 
+    const getTagTree = useCallback(async () => {
+        setLoading(true);
+        setError(null);
+        try {
+            const tree = await invoke<TagTreeNode[]>('get_tag_tree');
+            return tree;
+        } catch (e: any) {
+            setError(e.message || 'Failed to load tag tree');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
     const addTagToNote = useCallback(async (noteId: string, tagId: string) => {
         setLoading(true);
         setError(null);
@@ -74,6 +88,57 @@ export const useTags = () => {
         }
     }, []);
 
+    const updateTag = useCallback(async (tagId: string, updates: { name?: string; color?: string; icon?: string | null; description?: string | null }) => {
+        setLoading(true);
+        setError(null);
+        try {
+            const tag = await invoke<Tag>('update_tag', { tagId, ...updates });
+            return tag;
+        } catch (e: any) {
+            setError(e.message || 'Failed to update tag');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const listTagPalette = useCallback(async () => {
+        try {
+            return await invoke<PaletteColor[]>('list_tag_palette');
+        } catch (e: any) {
+            setError(e.message || 'Failed to load tag palette');
+            throw e;
+        }
+    }, []);
+
+    const renameTag = useCallback(async (tagId: string, name: string) => {
+        setLoading(true);
+        setError(null);
+        try {
+            const tag = await invoke<Tag>('rename_tag', { tagId, name });
+            return tag;
+        } catch (e: any) {
+            setError(e.message || 'Failed to rename tag');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const mergeTags = useCallback(async (sourceIds: string[], targetId: string) => {
+        setLoading(true);
+        setError(null);
+        try {
+            const tag = await invoke<Tag>('merge_tags', { sourceIds, targetId });
+            return tag;
+        } catch (e: any) {
+            setError(e.message || 'Failed to merge tags');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
     const deleteTag = useCallback(async (tagId: string) => {
         setLoading(true);
         setError(null);
@@ -87,14 +152,43 @@ export const useTags = () => {
         }
     }, []);
 
+    const suggestTags = useCallback(async (noteId: string, limit: number = 10) => {
+        try {
+            return await invoke<TagSuggestion[]>('suggest_tags', { noteId, limit });
+        } catch (e: any) {
+            setError(e.message || 'Failed to suggest tags');
+            throw e;
+        }
+    }, []);
+
+    const getTagCooccurrence = useCallback(async (minShared: number = 1, limit: number = 50) => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<TagCooccurrence[]>('get_tag_cooccurrence', { minShared, limit });
+        } catch (e: any) {
+            setError(e.message || 'Failed to load tag co-occurrence');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
     return {
         loading,
         error,
         createTag,
         listTags,
+        getTagTree,
         addTagToNote,
         removeTagFromNote,
         getNoteTags,
+        updateTag,
+        listTagPalette,
+        renameTag,
+        mergeTags,
         deleteTag,
+        suggestTags,
+        getTagCooccurrence,
     };
 };
@@ -0,0 +1,48 @@
+import { useState, useCallback } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { AgendaGroup, Task, TaskFilter } from '../types';
+
+export const useTasks = () => {
+    const [agenda, setAgenda] = useState<AgendaGroup[]>([]);
+    const [loading, setLoading] = useState(false);
+    const [error, setError] = useState<string | null>(null);
+
+    const fetchAgenda = useCallback(async (filter: TaskFilter = { done: false }) => {
+        setLoading(true);
+        setError(null);
+        try {
+            const result = await invoke<AgendaGroup[]>('list_tasks', { filter });
+            setAgenda(result);
+            return result;
+        } catch (e: any) {
+            setError(e.message || 'Failed to load tasks');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    /** Checks or unchecks the task in its note; fails with a `CONFLICT` error if the note changed meanwhile. */
+    const toggleTask = useCallback(async (noteId: string, line: number) => {
+        setError(null);
+        try {
+            const task = await invoke<Task>('toggle_task', { noteId, line });
+            setAgenda(groups => groups.map(group => ({
+                ...group,
+                tasks: group.tasks.map(t => (t.note_id === noteId && t.line === line ? task : t)),
+            })));
+            return task;
+        } catch (e: any) {
+            setError(e.message || 'Failed to update the task');
+            throw e;
+        }
+    }, []);
+
+    return {
+        agenda,
+        loading,
+        error,
+        fetchAgenda,
+        toggleTask,
+    };
+};
@@ -0,0 +1,101 @@
+import { useState, useCallback } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { RenderedTemplate, Template, TemplateDraft, TemplateNote } from '../types';
+
+export const useTemplates = () => {
+    const [loading, setLoading] = useState(false);
+    const [error, setError] = useState<string | null>(null);
+
+    const listTemplates = useCallback(async () => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<Template[]>('list_templates');
+        } catch (e: any) {
+            setError(e.message || 'Failed to list templates');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const createTemplate = useCallback(async (template: TemplateDraft) => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<Template>('create_template', { template });
+        } catch (e: any) {
+            setError(e.message || 'Failed to create template');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const updateTemplate = useCallback(async (id: string, template: TemplateDraft) => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<Template>('update_template', { id, template });
+        } catch (e: any) {
+            setError(e.message || 'Failed to update template');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const deleteTemplate = useCallback(async (id: string) => {
+        setLoading(true);
+        setError(null);
+        try {
+            await invoke('delete_template', { id });
+        } catch (e: any) {
+            setError(e.message || 'Failed to delete template');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const previewTemplate = useCallback(async (templateId: string, vars: Record<string, string> = {}) => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<RenderedTemplate>('preview_template', { templateId, vars });
+        } catch (e: any) {
+            setError(e.message || 'Failed to preview template');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const createNoteFromTemplate = useCallback(async (
+        templateId: string,
+        folderId: string | null = null,
+        vars: Record<string, string> = {},
+    ) => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<TemplateNote>('create_note_from_template', { templateId, folderId, vars });
+        } catch (e: any) {
+            setError(e.message || 'Failed to create note from template');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    return {
+        loading,
+        error,
+        listTemplates,
+        createTemplate,
+        updateTemplate,
+        deleteTemplate,
+        previewTemplate,
+        createNoteFromTemplate,
+    };
+};
@@ -0,0 +1,55 @@
+import { useState, useCallback } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { UndoAction } from '../types';
+
+export const useUndo = () => {
+    const [loading, setLoading] = useState(false);
+    const [error, setError] = useState<string | null>(null);
+
+    const undo = useCallback(async () => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<UndoAction | null>('undo');
+        } catch (e: any) {
+            setError(e.message || 'Failed to undo');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const redo = useCallback(async () => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<UndoAction | null>('redo');
+        } catch (e: any) {
+            setError(e.message || 'Failed to redo');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const listUndoHistory = useCallback(async (limit?: number) => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<UndoAction[]>('list_undo_history', { limit });
+        } catch (e: any) {
+            setError(e.message || 'Failed to list undo history');
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    return {
+        loading,
+        error,
+        undo,
+        redo,
+        listUndoHistory,
+    };
+};
@@ -0,0 +1,46 @@
+import { useState, useCallback } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { ListVaultsResponse, Vault } from '../types';
+
+export const useVaults = () => {
+    const [loading, setLoading] = useState(false);
+    const [error, setError] = useState<string | null>(null);
+
+    const run = useCallback(async <T,>(command: string, args: Record<string, unknown>, failure: string) => {
+        setLoading(true);
+        setError(null);
+        try {
+            return await invoke<T>(command, args);
+        } catch (e: any) {
+            setError(e.message || failure);
+            throw e;
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    const listVaults = useCallback(() => run<ListVaultsResponse>('list_vaults', {}, 'Failed to list vaults'), [run]);
+
+    const getCurrentVault = useCallback(() => run<Vault | null>('get_current_vault', {}, 'Failed to get current vault'), [run]);
+
+    const createVault = useCallback((name: string, path: string) =>
+        run<Vault>('create_vault', { name, path }, 'Failed to create vault'), [run]);
+
+    const openVault = useCallback((id: string) => run<Vault>('open_vault', { id }, 'Failed to open vault'), [run]);
+
+    const closeVault = useCallback(() => run<string | null>('close_vault', {}, 'Failed to close vault'), [run]);
+
+    const renameVault = useCallback((id: string, name: string) =>
+        run<Vault>('rename_vault', { id, name }, 'Failed to rename vault'), [run]);
+
+    return {
+        loading,
+        error,
+        listVaults,
+        getCurrentVault,
+        createVault,
+        openVault,
+        closeVault,
+        renameVault,
+    };
+};
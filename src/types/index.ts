@@ -5,6 +5,28 @@ export interface Note {
     content: string;
     created_at: number;
     updated_at: number;
+    encrypted: boolean;
+    /** Encrypted and not unlocked this session; `content` is empty. */
+    locked: boolean;
+    /** Parsed from the YAML front matter of `content`, in order. */
+    properties: NoteProperty[];
+}
+
+/** A front matter value, typed by how it is written; quoted values are always text. */
+export type PropertyValue =
+    | { type: 'text'; value: string }
+    | { type: 'number'; value: number }
+    | { type: 'date'; value: string }
+    | { type: 'bool'; value: boolean }
+    | { type: 'list'; value: string[] };
+
+export type NoteProperty = { key: string } & PropertyValue;
+
+export interface PropertyQuery {
+    /** All must match, e.g. `status = draft`, `priority >= 2`, `aliases ~ plan` or just `author` */
+    filters?: string[];
+    /** Notes without the property come last */
+    sort?: { key: string; descending?: boolean } | null;
 }
 
 export interface NoteSummary {
@@ -13,6 +35,7 @@ export interface NoteSummary {
     title: string;
     excerpt: string;
     updated_at: number;
+    encrypted: boolean;
 }
 
 export interface ListNotesResponse {
@@ -46,21 +69,139 @@ export interface Tag {
     id: string;
     name: string;
     color: string;
+    icon: string | null;
+    description: string | null;
 }
 
 export interface TagWithCount {
     id: string;
     name: string;
     color: string;
+    icon: string | null;
+    description: string | null;
     note_count: number;
 }
 
+export interface PaletteColor {
+    name: string;
+    hex: string;
+}
+
+export interface TagTreeNode {
+    id: string | null;
+    name: string;
+    path: string;
+    color: string | null;
+    note_count: number;
+    total_count: number;
+    children: TagTreeNode[];
+}
+
+export interface TagSuggestion {
+    id: string;
+    name: string;
+    color: string;
+    score: number;
+    cooccurrence: number;
+    term_overlap: number;
+    linked: number;
+}
+
+export interface TagCooccurrence {
+    tag_a_id: string;
+    tag_a_name: string;
+    tag_b_id: string;
+    tag_b_name: string;
+    shared_notes: number;
+    jaccard: number;
+}
+
 export interface Settings {
     theme: string;
     font_size: number;
     font_family: string;
     auto_save_delay: number;
     spell_check: boolean;
+    backup_enabled: boolean;
+    backup_interval_hours: number;
+    backup_retention: number;
+    /** Serve the local HTTP API on 127.0.0.1 while this vault is open */
+    api_enabled: boolean;
+    api_port: number;
+    /** Bearer token for the local API; generated when the API is first enabled */
+    api_token: string | null;
+    /** Browser origins allowed to call the local API */
+    api_allowed_origins: string[];
+    periodic_notes: PeriodicNotesSettings;
+}
+
+export interface PeriodicNoteSettings {
+    /** Folder new notes go in; the root when null */
+    folder_id: string | null;
+    /** chrono strftime format applied to the period's first day, e.g. `%Y-%m-%d` */
+    title_format: string;
+    template_id: string | null;
+}
+
+export interface PeriodicNotesSettings {
+    daily: PeriodicNoteSettings;
+    weekly: PeriodicNoteSettings;
+    monthly: PeriodicNoteSettings;
+}
+
+export interface BackupInfo {
+    id: string;
+    kind: 'manual' | 'auto' | 'pre_restore';
+    path: string;
+    created_at: number;
+    size_bytes: number;
+    schema_version: number;
+}
+
+export interface IntegrityReport {
+    ok: boolean;
+    errors: string[];
+    foreign_key_violations: { table: string; rowid: number | null; parent: string }[];
+}
+
+export interface CheckpointResult {
+    busy: boolean;
+    wal_frames: number;
+    checkpointed_frames: number;
+}
+
+export interface DbStats {
+    file_size_bytes: number;
+    wal_size_bytes: number;
+    page_size: number;
+    page_count: number;
+    freelist_count: number;
+    tables: { name: string; rows: number; size_bytes: number }[];
+    fts_index_size_bytes: number;
+}
+
+export interface Vault {
+    id: string;
+    name: string;
+    path: string;
+    created_at: number;
+    last_opened_at: number | null;
+}
+
+export interface ListVaultsResponse {
+    vaults: Vault[];
+    current_vault_id: string | null;
+}
+
+export interface EncryptionStatus {
+    supported: boolean;
+    encrypted: boolean;
+    locked: boolean;
+}
+
+export interface EnableEncryptionResponse {
+    status: EncryptionStatus;
+    plaintext_backups: string[];
 }
 
 export interface Backlink {
@@ -69,7 +210,192 @@ export interface Backlink {
     context: string | null;
 }
 
+export interface MergeResult {
+    merged: string;
+    /** Regions both sides changed; each is left in `merged` between conflict markers. */
+    conflicts: number;
+}
+
+/** Rejected `update_note`: the note changed since `expectedUpdatedAt`. */
+export interface NoteConflictError extends AppError {
+    code: 'CONFLICT';
+    current: Note;
+}
+
+export interface Template {
+    id: string;
+    name: string;
+    /** May contain placeholders, like `content`. */
+    title: string;
+    content: string;
+    folder_id: string | null;
+    /** Tag names attached to every note created from the template. */
+    tags: string[];
+    /** Variables to pass besides the built-in `date`, `time`, `title` and `cursor`. */
+    variables: string[];
+    created_at: number;
+    updated_at: number;
+}
+
+export interface TemplateDraft {
+    name: string;
+    title: string;
+    content: string;
+    folder_id: string | null;
+    tags: string[];
+}
+
+export interface RenderedTemplate {
+    title: string;
+    content: string;
+    /** Where `{{cursor}}` was, as an index into `content`. */
+    cursor: number | null;
+    folder_id: string | null;
+    tags: string[];
+}
+
+export interface TemplateNote {
+    note: Note;
+    cursor: number | null;
+}
+
+export type Period = 'daily' | 'weekly' | 'monthly';
+
+export interface PeriodicNote {
+    period: Period;
+    /** First day of the period, `YYYY-MM-DD` */
+    start_date: string;
+    note: Note;
+    /** False when the note already existed */
+    created: boolean;
+    cursor: number | null;
+}
+
+export interface PeriodicNoteSummary {
+    period: Period;
+    start_date: string;
+    note_id: string;
+    title: string;
+    /** null for encrypted notes */
+    word_count: number | null;
+}
+
+export interface CalendarDay {
+    date: string;
+    daily_note: PeriodicNoteSummary | null;
+    created: NoteSummary[];
+    /** Notes last changed that day, other than the ones created on it */
+    updated: NoteSummary[];
+}
+
+export interface PeriodicCalendar {
+    days: CalendarDay[];
+    weeks: PeriodicNoteSummary[];
+    months: PeriodicNoteSummary[];
+}
+
+export type TaskPriority = 'high' | 'medium' | 'low';
+
+export interface Task {
+    note_id: string;
+    note_title: string;
+    /** 1-based line of the checkbox in the note */
+    line: number;
+    /** The item's text without its due date and priority markers */
+    text: string;
+    done: boolean;
+    /** `YYYY-MM-DD`, from `due:2026-03-02` or `📅 2026-03-02` */
+    due_date: string | null;
+    priority: TaskPriority | null;
+    tags: string[];
+}
+
+/** Fields left out match every task. */
+export interface TaskFilter {
+    done?: boolean;
+    due_from?: string;
+    due_to?: string;
+    has_due_date?: boolean;
+    priority?: TaskPriority;
+    /** Tasks tagged with a descendant of the tag match too */
+    tag?: string;
+    note_id?: string;
+    folder_id?: string;
+}
+
+/** The tasks due on one day; `due_date` is null for the ones without a date. */
+export interface AgendaGroup {
+    due_date: string | null;
+    tasks: Task[];
+}
+
+export interface Reminder {
+    id: string;
+    note_id: string;
+    note_title: string;
+    /** Set for a reminder about the task on this line of the note */
+    task_line: number | null;
+    /** The task's text, while that line still holds a task */
+    task_text: string | null;
+    message: string | null;
+    /** Epoch milliseconds */
+    remind_at: number;
+    fired_at: number | null;
+    created_at: number;
+}
+
+export interface ReminderDraft {
+    note_id: string;
+    task_line?: number | null;
+    message?: string | null;
+    remind_at: number;
+}
+
+/** Payload of the `reminder:due` event. */
+export interface DueReminder extends Reminder {
+    /** It was due well before it fired, e.g. while the app was closed */
+    missed: boolean;
+}
+
+export interface UndoAction {
+    id: number;
+    label: string;
+    created_at: number;
+    undone: boolean;
+}
+
+export interface ChangeEventPayloads {
+    'note:created': { id: string; folder_id: string | null };
+    'note:updated': { id: string; folder_id: string | null };
+    'note:deleted': { id: string };
+    'folder:changed': { id: string };
+    'folder:moved': { id: string; parent_id: string | null };
+    'tag:changed': { tag_ids: string[]; note_ids: string[] };
+    'links:changed': { source_id: string; target_ids: string[] };
+    'reminder:changed': { id: string; note_id: string };
+}
+
+export type ChangeEventName = keyof ChangeEventPayloads;
+
+/** Stable error codes; switch on these rather than on `message`. */
+export type AppErrorCode =
+    | 'NOT_FOUND'
+    | 'VALIDATION'
+    | 'ALREADY_EXISTS'
+    | 'CONFLICT'
+    | 'LOCKED'
+    | 'CORRUPT'
+    | 'UNSUPPORTED'
+    | 'BUSY'
+    | 'DATABASE'
+    | 'IO';
+
+/** The shape every failed command rejects with. */
 export interface AppError {
-    code: string;
+    code: AppErrorCode;
     message: string;
+    /** The invalid argument, for some `VALIDATION` errors. */
+    field?: string;
+    /** The stored note, for `CONFLICT` errors from note updates. */
+    current?: Note;
 }